@@ -0,0 +1,64 @@
+use std::{env, fs, path::Path};
+
+fn main() {
+    println!("cargo:rerun-if-changed=src/ffi.rs");
+    println!("cargo:rerun-if-changed=cbindgen.toml");
+    println!("cargo:rerun-if-env-changed=AOC_UPDATE_HEADER");
+
+    // The C test in `tests/ffi.rs` compiles for the same target as the library.
+    println!(
+        "cargo:rustc-env=BUILD_TARGET={}",
+        env::var("TARGET").unwrap()
+    );
+
+    let config = cbindgen::Config::from_file("cbindgen.toml").expect("Invalid cbindgen.toml");
+
+    let header = cbindgen::Builder::new()
+        .with_config(config)
+        .with_src("src/ffi.rs")
+        .generate()
+        .expect("Could not generate the C header");
+
+    // Builds leave the source tree alone. `tests/ffi.rs` checks that the copy in `include` is up to
+    // date, and `AOC_UPDATE_HEADER=1 cargo build` refreshes it.
+    header.write_to_file(Path::new(&env::var("OUT_DIR").unwrap()).join("include/advent_of_code.h"));
+
+    if env::var_os("AOC_UPDATE_HEADER").is_some() {
+        header.write_to_file("include/advent_of_code.h");
+    }
+
+    generate_example_tests();
+}
+
+/// Writes a test for every example input to `$OUT_DIR/examples.rs`, for `tests/examples.rs` to
+/// include.
+fn generate_example_tests() {
+    const EXAMPLES: &str = "input/2022/examples";
+
+    println!("cargo:rerun-if-changed={EXAMPLES}");
+
+    let mut names = fs::read_dir(EXAMPLES)
+        .expect("Could not read the examples")
+        .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+        .filter_map(|file| {
+            let name = file.strip_suffix(".txt")?;
+            name.starts_with("day").then(|| name.to_string())
+        })
+        .collect::<Vec<_>>();
+
+    names.sort();
+
+    let tests = names
+        .iter()
+        .map(|name| {
+            format!(
+                "#[test]\nfn {}() {{\n    check({name:?});\n}}\n",
+                name.replace('-', "_")
+            )
+        })
+        .collect::<Vec<_>>();
+
+    let out = Path::new(&env::var("OUT_DIR").unwrap()).join("examples.rs");
+
+    fs::write(out, tests.join("\n")).expect("Could not write the example tests");
+}
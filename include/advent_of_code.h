@@ -0,0 +1,52 @@
+#ifndef ADVENT_OF_CODE_H
+#define ADVENT_OF_CODE_H
+
+/* Generated by build.rs from src/ffi.rs, do not edit. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+// Outcome of `aoc_solve`.
+typedef enum AocStatus {
+  // The answer was found.
+  AOC_STATUS_OK = 0,
+  // There is no such day or part.
+  AOC_STATUS_UNKNOWN_PUZZLE = 1,
+  // The input is not valid UTF-8.
+  AOC_STATUS_INVALID_UTF8 = 2,
+  // The input is not what the puzzle expects.
+  AOC_STATUS_INVALID_INPUT = 3,
+  // The solver panicked.
+  AOC_STATUS_PANICKED = 4,
+  // `answer` is null, or `input` is null while `len` isn't 0.
+  AOC_STATUS_INVALID_ARGUMENT = 5,
+} AocStatus;
+
+// Solves `part` of `day` for the `len` bytes of UTF-8 at `input`, which don't have to end in a
+// NUL.
+//
+// Unless the arguments are invalid, `*answer` is set to a NUL terminated string that must be
+// released with `aoc_free_string`. It holds the answer when the puzzle was solved, and describes
+// the problem otherwise.
+//
+// # Safety
+//
+// `input` must point to `len` readable bytes, unless `len` is 0, and `answer` must be null or
+// point to writable memory for a pointer.
+enum AocStatus aoc_solve(uint8_t day,
+                         uint8_t part,
+                         const uint8_t *input,
+                         size_t len,
+                         char **answer);
+
+// Releases a string returned by `aoc_solve`. Does nothing when `s` is null.
+//
+// # Safety
+//
+// `s` must be null or a string from `aoc_solve` that hasn't been released yet.
+void aoc_free_string(char *s);
+
+#endif  /* ADVENT_OF_CODE_H */
@@ -0,0 +1,265 @@
+//! Deadlines, cancellation and progress reports for the searches that can run for a long time.
+//!
+//! A search that is given a [`Budget`] keeps going until it is done or the budget runs out, and
+//! then returns its best answer so far as an [`Outcome`] that tells the two apart.
+
+use std::{
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+use crate::Answer;
+
+/// Number of expanded states in between two looks at the clock.
+pub(crate) const CHECK_EVERY: usize = 1024;
+
+/// Stops every search whose [`Budget`] it was given, from any thread.
+#[derive(Debug, Clone, Default)]
+pub struct Cancel(Arc<AtomicBool>);
+
+impl Cancel {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// How far a search has come, as handed to the callback of [`Budget::on_progress`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Progress {
+    /// Time since the budget was created.
+    pub elapsed: Duration,
+    /// States expanded so far, by all searches sharing the budget.
+    pub expanded: usize,
+    /// The answer the search would return if it stopped now.
+    pub best: Answer,
+}
+
+struct Reporter {
+    every: Duration,
+    next: Mutex<Instant>,
+    callback: Box<dyn Fn(&Progress) + Send + Sync>,
+}
+
+/// Limits on how long a solver may search, for a single solve.
+///
+/// Searches that run in parallel share the budget, along with its count of expanded states.
+pub struct Budget {
+    started: Instant,
+    deadline: Option<Instant>,
+    cancel: Option<Cancel>,
+    reporter: Option<Reporter>,
+    expanded: AtomicUsize,
+}
+
+impl Default for Budget {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Budget {
+    /// A budget that never runs out.
+    pub fn new() -> Self {
+        Budget {
+            started: Instant::now(),
+            deadline: None,
+            cancel: None,
+            reporter: None,
+            expanded: AtomicUsize::new(0),
+        }
+    }
+
+    /// Runs out `timeout` after the budget was created.
+    pub fn timeout(self, timeout: Duration) -> Self {
+        let deadline = self.started + timeout;
+        self.deadline(deadline)
+    }
+
+    /// Runs out at `deadline`.
+    pub fn deadline(mut self, deadline: Instant) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    /// Runs out once `cancel` is cancelled.
+    pub fn cancel_on(mut self, cancel: &Cancel) -> Self {
+        self.cancel = Some(cancel.clone());
+        self
+    }
+
+    /// Calls `callback` at most once per `every` while a search is running.
+    pub fn on_progress(
+        mut self,
+        every: Duration,
+        callback: impl Fn(&Progress) + Send + Sync + 'static,
+    ) -> Self {
+        self.reporter = Some(Reporter {
+            every,
+            next: Mutex::new(self.started + every),
+            callback: Box::new(callback),
+        });
+        self
+    }
+
+    /// Starts keeping track of one search, which may run alongside others on the same budget.
+    pub(crate) fn meter(&self) -> Meter<'_> {
+        Meter {
+            budget: self,
+            ticks: 0,
+            stopped: false,
+        }
+    }
+
+    fn has_run_out(&self, now: Instant) -> bool {
+        self.cancel.as_ref().is_some_and(Cancel::is_cancelled)
+            || self.deadline.is_some_and(|deadline| now >= deadline)
+    }
+}
+
+/// The part of a [`Budget`] that a single search uses up.
+pub(crate) struct Meter<'a> {
+    budget: &'a Budget,
+    ticks: usize,
+    stopped: bool,
+}
+
+impl Meter<'_> {
+    /// Counts one expanded state, and tells whether the search has to stop. `best` is only called
+    /// when progress is reported.
+    pub(crate) fn tick(&mut self, best: impl FnOnce() -> Answer) -> bool {
+        self.tick_by(1, best)
+    }
+
+    /// Like [`Meter::tick`], but counts `count` expanded states at once, for searches that only
+    /// check in every now and then.
+    pub(crate) fn tick_by(&mut self, count: usize, best: impl FnOnce() -> Answer) -> bool {
+        if self.stopped {
+            return true;
+        }
+
+        self.ticks += count;
+
+        if self.ticks < CHECK_EVERY {
+            return false;
+        }
+
+        let ticks = std::mem::take(&mut self.ticks);
+        let budget = self.budget;
+        let expanded = budget.expanded.fetch_add(ticks, Ordering::Relaxed) + ticks;
+        let now = Instant::now();
+
+        if let Some(reporter) = &budget.reporter {
+            // Another search sharing the budget is already reporting if the lock is taken.
+            if let Ok(mut next) = reporter.next.try_lock() {
+                if now >= *next {
+                    *next = now + reporter.every;
+                    drop(next);
+
+                    (reporter.callback)(&Progress {
+                        elapsed: now - budget.started,
+                        expanded,
+                        best: best(),
+                    });
+                }
+            }
+        }
+
+        self.stopped = budget.has_run_out(now);
+        self.stopped
+    }
+}
+
+/// The answer of a solver that was given a [`Budget`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Outcome<T> {
+    pub answer: T,
+    /// Whether the search ran to the end. If it didn't, `answer` is the best one it found before
+    /// the budget ran out, which need not be the right one.
+    pub is_final: bool,
+}
+
+impl<T> Outcome<T> {
+    pub fn map<U>(self, f: impl FnOnce(T) -> U) -> Outcome<U> {
+        Outcome {
+            answer: f(self.answer),
+            is_final: self.is_final,
+        }
+    }
+
+    /// Combines the outcomes of two searches that are part of the same answer.
+    pub(crate) fn zip(self, other: Outcome<T>, f: impl FnOnce(T, T) -> T) -> Outcome<T> {
+        Outcome {
+            answer: f(self.answer, other.answer),
+            is_final: self.is_final && other.is_final,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::{atomic::Ordering, Arc, Mutex},
+        time::{Duration, Instant},
+    };
+
+    use super::{Budget, Cancel, CHECK_EVERY};
+    use crate::Answer;
+
+    #[test]
+    fn cancel() {
+        let cancel = Cancel::new();
+        let budget = Budget::new().cancel_on(&cancel);
+        let mut meter = budget.meter();
+
+        assert!(!(0..CHECK_EVERY).any(|_| meter.tick(|| Answer::Usize(0))));
+
+        cancel.cancel();
+
+        assert!(!(1..CHECK_EVERY).any(|_| meter.tick(|| Answer::Usize(0))));
+        assert!(meter.tick(|| Answer::Usize(0)));
+        assert!(meter.tick(|| Answer::Usize(0)));
+    }
+
+    #[test]
+    fn tick_by() {
+        let budget = Budget::new().deadline(Instant::now());
+        let mut meter = budget.meter();
+
+        assert!(!meter.tick_by(CHECK_EVERY - 1, || Answer::Usize(0)));
+        assert!(meter.tick_by(CHECK_EVERY + 1, || Answer::Usize(0)));
+        assert_eq!(budget.expanded.load(Ordering::Relaxed), 2 * CHECK_EVERY);
+    }
+
+    #[test]
+    fn progress() {
+        let reports = Arc::new(Mutex::new(Vec::new()));
+        let budget = Budget::new()
+            .deadline(Instant::now())
+            .on_progress(Duration::ZERO, {
+                let reports = reports.clone();
+                move |progress| reports.lock().unwrap().push(progress.clone())
+            });
+        let mut meter = budget.meter();
+
+        let ticks = (1..).find(|&n| meter.tick(|| Answer::Usize(n))).unwrap();
+
+        assert_eq!(ticks, CHECK_EVERY);
+
+        let reports = reports.lock().unwrap();
+
+        assert_eq!(reports.len(), 1);
+        assert_eq!(reports[0].expanded, CHECK_EVERY);
+        assert_eq!(reports[0].best, Answer::Usize(CHECK_EVERY));
+    }
+}
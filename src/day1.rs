@@ -1,24 +1,70 @@
 use std::cmp::Reverse;
-use std::num::ParseIntError;
 
-use aoc_runner_derive::aoc;
+use aoc_runner_derive::{aoc, aoc_generator};
+use serde::Serialize;
 
-#[aoc(day1, part1)]
-pub fn part1(input: &str) -> Result<u64, ParseIntError> {
-    let elves = input
-        .split("\n\n")
-        .map(|elf| elf.split_whitespace().map(|s| s.parse::<u64>()).sum())
-        .collect::<Result<Vec<u64>, _>>()?;
+use crate::{
+    diagnostic::{check, Diagnostic},
+    util::parse::{self, blocks, lines, number},
+    Error,
+};
+
+/// Checks that there are enough elves for both parts.
+pub fn validate(input: &str) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    let mut elves = 0;
+
+    for elf in input.split("\n\n").filter(|elf| !elf.trim().is_empty()) {
+        check(
+            &mut diagnostics,
+            input,
+            parse::all(elf, lines(number::<u64>)),
+        );
+
+        elves += 1;
+    }
 
-    Ok(*elves.iter().max().expect("No elves"))
+    if elves < 3 {
+        diagnostics.push(Diagnostic::error(format!(
+            "Part 2 needs at least three elves, found {elves}"
+        )));
+    }
+
+    diagnostics
+}
+
+/// Total calories carried by every elf, in the order of the input.
+#[derive(Debug, Serialize)]
+pub struct Elves(Vec<u64>);
+
+#[aoc_generator(day1)]
+pub fn parse(input: &str) -> Result<Elves, Error> {
+    let elves = parse::all(input, blocks(lines(number::<u64>)))?;
+
+    Ok(Elves(elves.iter().map(|elf| elf.iter().sum()).collect()))
+}
+
+#[aoc(day1, part1)]
+pub fn part1(elves: &Elves) -> Result<u64, Error> {
+    elves
+        .0
+        .iter()
+        .max()
+        .copied()
+        .ok_or_else(|| Error::NoSolution {
+            reason: "there are no elves".to_string(),
+        })
 }
 
 #[aoc(day1, part2)]
-pub fn part2(input: &str) -> Result<u64, ParseIntError> {
-    let mut elves = input
-        .split("\n\n")
-        .map(|elf| elf.split_whitespace().map(|s| s.parse::<u64>()).sum())
-        .collect::<Result<Vec<u64>, _>>()?;
+pub fn part2(elves: &Elves) -> Result<u64, Error> {
+    let mut elves = elves.0.clone();
+
+    if elves.len() < 3 {
+        return Err(Error::NoSolution {
+            reason: format!("there are {} elves, not three", elves.len()),
+        });
+    }
 
     elves.sort_by_key(|w| Reverse(*w));
 
@@ -1,25 +1,29 @@
-use std::{num::ParseIntError, str::FromStr};
-
-use aoc_runner_derive::aoc;
-
+use aoc_runner_derive::{aoc, aoc_generator};
+use nom::{branch::alt, combinator::map};
+use serde::Serialize;
+
+use crate::{
+    diagnostic::{check, Diagnostic},
+    util::parse::{self, expect, lines, number, tag, IResult},
+    Error,
+};
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "lowercase")]
 enum Instruction {
     Noop,
     AddX(i32),
 }
 
-impl FromStr for Instruction {
-    type Err = ParseIntError;
+fn instruction(input: &str) -> IResult<'_, Instruction> {
+    let (input, op) = expect(
+        "an instruction (noop or addx)",
+        alt((tag("noop"), tag("addx "))),
+    )(input)?;
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        if s == "noop" {
-            return Ok(Instruction::Noop);
-        }
-
-        if let Some(num) = s.strip_prefix("addx ") {
-            return Ok(Instruction::AddX(num.parse::<i32>()?));
-        }
-
-        panic!("Unknown instruction: {}", s);
+    match op {
+        "noop" => Ok((input, Instruction::Noop)),
+        _ => map(number, Instruction::AddX)(input),
     }
 }
 
@@ -72,37 +76,52 @@ impl Cpu {
     }
 }
 
-#[aoc(day10, part1)]
-pub fn part1(input: &str) -> Result<i32, ParseIntError> {
-    let instructions = input.lines().map(|line| line.parse::<Instruction>());
-
-    let mut cpu = Cpu::new();
+/// Checks that the program runs long enough to draw the whole CRT image.
+pub fn validate(input: &str) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    let mut cycles = 0;
+
+    for line in input.lines() {
+        cycles += match check(&mut diagnostics, input, parse::all(line, instruction)) {
+            Some(Instruction::Noop) => 1,
+            Some(Instruction::AddX(_)) => 2,
+            None => continue,
+        };
+    }
 
-    for instruction in instructions {
-        cpu.run(&instruction?);
+    if diagnostics.is_empty() && cycles < 240 {
+        diagnostics.push(Diagnostic::warning(format!(
+            "The program runs for {cycles} cycles, too short to draw all 240 pixels"
+        )));
     }
 
-    Ok(cpu.part_1_result)
+    diagnostics
 }
 
-#[aoc(day10, part2)]
-pub fn part2(input: &str) -> Result<String, ParseIntError> {
-    let instructions = input.lines().map(|line| line.parse::<Instruction>());
+#[derive(Debug, Serialize)]
+pub struct Program(Vec<Instruction>);
 
+#[aoc_generator(day10)]
+pub fn parse(input: &str) -> Result<Program, Error> {
+    parse::all(input, lines(instruction)).map(Program)
+}
+
+fn run(program: &Program) -> Cpu {
     let mut cpu = Cpu::new();
 
-    for instruction in instructions {
-        cpu.run(&instruction?);
+    for instruction in &program.0 {
+        cpu.run(instruction);
     }
 
-    Ok(cpu.part_2_result)
+    cpu
 }
 
-#[cfg(test)]
-mod tests {
-    #[test]
-    fn test_case_1() {
-        let result = super::part1("addx 15\naddx -11\naddx 6\naddx -3\naddx 5\naddx -1\naddx -8\naddx 13\naddx 4\nnoop\naddx -1\naddx 5\naddx -1\naddx 5\naddx -1\naddx 5\naddx -1\naddx 5\naddx -1\naddx -35\naddx 1\naddx 24\naddx -19\naddx 1\naddx 16\naddx -11\nnoop\nnoop\naddx 21\naddx -15\nnoop\nnoop\naddx -3\naddx 9\naddx 1\naddx -3\naddx 8\naddx 1\naddx 5\nnoop\nnoop\nnoop\nnoop\nnoop\naddx -36\nnoop\naddx 1\naddx 7\nnoop\nnoop\nnoop\naddx 2\naddx 6\nnoop\nnoop\nnoop\nnoop\nnoop\naddx 1\nnoop\nnoop\naddx 7\naddx 1\nnoop\naddx -13\naddx 13\naddx 7\nnoop\naddx 1\naddx -33\nnoop\nnoop\nnoop\naddx 2\nnoop\nnoop\nnoop\naddx 8\nnoop\naddx -1\naddx 2\naddx 1\nnoop\naddx 17\naddx -9\naddx 1\naddx 1\naddx -3\naddx 11\nnoop\nnoop\naddx 1\nnoop\naddx 1\nnoop\nnoop\naddx -13\naddx -19\naddx 1\naddx 3\naddx 26\naddx -30\naddx 12\naddx -1\naddx 3\naddx 1\nnoop\nnoop\nnoop\naddx -9\naddx 18\naddx 1\naddx 2\nnoop\nnoop\naddx 9\nnoop\nnoop\nnoop\naddx -1\naddx 2\naddx -37\naddx 1\naddx 3\nnoop\naddx 15\naddx -21\naddx 22\naddx -6\naddx 1\nnoop\naddx 2\naddx 1\nnoop\naddx -10\nnoop\nnoop\naddx 20\naddx 1\naddx 2\naddx 2\naddx -6\naddx -11\nnoop\nnoop\nnoop");
-        assert_eq!(result, Ok(13140));
-    }
+#[aoc(day10, part1)]
+pub fn part1(program: &Program) -> i32 {
+    run(program).part_1_result
+}
+
+#[aoc(day10, part2)]
+pub fn part2(program: &Program) -> String {
+    run(program).part_2_result
 }
@@ -1,8 +1,25 @@
-use std::{cmp::Reverse, collections::HashSet, num::ParseIntError, str::FromStr};
-
-use aoc_runner_derive::aoc;
-
-#[derive(Debug)]
+use std::{cmp::Reverse, collections::HashSet};
+
+use aoc_runner_derive::{aoc, aoc_generator};
+use nom::{
+    branch::alt,
+    character::complete::space0,
+    combinator::{consumed, map},
+    sequence::{pair, preceded},
+};
+use serde::Serialize;
+
+use crate::{
+    diagnostic::{check, Diagnostic},
+    util::{
+        cycle,
+        parse::{self, blocks, expect, fail, line, list, number, tag, IResult},
+    },
+    Error,
+};
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "lowercase")]
 enum Operation {
     Add(u64),
     Multiply(u64),
@@ -10,36 +27,31 @@ enum Operation {
 }
 
 impl Operation {
-    fn apply(&self, x: u64) -> u64 {
+    /// The new worry level, or `None` if it doesn't fit a `u64`.
+    fn apply(&self, x: u64) -> Option<u64> {
         match self {
-            Self::Add(y) => x + y,
-            Self::Multiply(y) => x * y,
-            Self::Square => x * x,
+            Self::Add(y) => x.checked_add(*y),
+            Self::Multiply(y) => x.checked_mul(*y),
+            Self::Square => x.checked_mul(x),
         }
     }
 }
 
-impl FromStr for Operation {
-    type Err = ParseIntError;
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let Some(op) = s.trim().strip_prefix("Operation: new = ") else {
-            panic!("Malformed input");
-        };
+fn operation(input: &str) -> IResult<'_, Operation> {
+    let (input, op) = expect(
+        "\"old * old\", \"old * N\" or \"old + N\"",
+        alt((tag("old * old"), tag("old * "), tag("old + "))),
+    )(input)?;
 
-        if op == "old * old" {
-            Ok(Operation::Square)
-        } else if let Some(op) = op.strip_prefix("old * ") {
-            Ok(Operation::Multiply(op.parse()?))
-        } else if let Some(op) = op.strip_prefix("old + ") {
-            Ok(Operation::Add(op.parse()?))
-        } else {
-            panic!("Malformed input");
-        }
+    match op {
+        "old * old" => Ok((input, Operation::Square)),
+        "old * " => map(number, Operation::Multiply)(input),
+        _ => map(number, Operation::Add)(input),
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "snake_case")]
 enum Test {
     DivisibleBy(u64),
 }
@@ -47,30 +59,31 @@ enum Test {
 impl Test {
     fn test(&self, x: u64) -> bool {
         match self {
-            Self::DivisibleBy(y) => x % y == 0,
+            Self::DivisibleBy(y) => x.is_multiple_of(*y),
         }
     }
 }
 
-impl FromStr for Test {
-    type Err = ParseIntError;
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let Some(x) = s.trim().strip_prefix("Test: divisible by ") else {
-            panic!("Malformed input");
-        };
+fn divisor(input: &str) -> IResult<'_, Test> {
+    let (rest, x) = number(input)?;
 
-        Ok(Test::DivisibleBy(x.parse()?))
+    match x {
+        0 => fail(&input[..input.len() - rest.len()], "a non-zero divisor"),
+        x => Ok((rest, Test::DivisibleBy(x))),
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize)]
 struct Monkey {
     items: Vec<u64>,
+    #[serde(rename = "operation")]
     op: Operation,
     test: Test,
+    #[serde(rename = "if_true")]
     true_target: usize,
+    #[serde(rename = "if_false")]
     false_target: usize,
+    #[serde(skip)]
     inspected: u64,
 }
 
@@ -84,130 +97,250 @@ impl Monkey {
     }
 }
 
-trait Parsing {
-    type Err;
-
-    fn parse_starting_items(&self) -> Result<Vec<u64>, Self::Err>;
-    fn parse_target(&self) -> Result<usize, Self::Err>;
+/// The next line, indented by any number of spaces, up to the end of `label`.
+fn field<'a>(label: &'static str) -> impl FnMut(&'a str) -> IResult<'a, &'a str> {
+    preceded(pair(tag("\n"), space0), tag(label))
 }
 
-impl Parsing for str {
-    type Err = ParseIntError;
+/// A monkey, along with the text of both of its targets, which can only be checked against the
+/// number of monkeys once all of them are known.
+fn monkey(input: &str) -> IResult<'_, (Monkey, [&str; 2])> {
+    // The header is only checked by `validate`, as the monkeys are numbered in order anyway
+    let (input, _) = line(input)?;
+    let (input, items) = preceded(field("Starting items: "), list(number))(input)?;
+    let (input, op) = preceded(field("Operation: new = "), operation)(input)?;
+    let (input, test) = preceded(field("Test: divisible by "), divisor)(input)?;
+    let (input, (true_text, true_target)) =
+        preceded(field("If true: throw to monkey "), consumed(number))(input)?;
+    let (input, (false_text, false_target)) =
+        preceded(field("If false: throw to monkey "), consumed(number))(input)?;
+
+    let monkey = Monkey {
+        items,
+        op,
+        test,
+        true_target,
+        false_target,
+        inspected: 0,
+    };
+
+    Ok((input, (monkey, [true_text, false_text])))
+}
 
-    fn parse_starting_items(&self) -> Result<Vec<u64>, Self::Err> {
-        let Some(items) = self.trim().strip_prefix("Starting items: ") else {
-            panic!("Malformed input");
-        };
+/// At least two monkeys, which only throw to monkeys that exist.
+fn monkeys(input: &str) -> IResult<'_, Monkeys> {
+    let (rest, monkeys) = blocks(monkey)(input)?;
 
-        items.split(',').map(|s| s.trim().parse::<u64>()).collect()
+    if monkeys.len() < 2 {
+        return fail(&rest[..0], "at least two monkeys for monkey business");
     }
 
-    fn parse_target(&self) -> Result<usize, Self::Err> {
-        if let Some(target) = self.trim().strip_prefix("If true: throw to monkey ") {
-            return target.parse();
+    for (monkey, texts) in &monkeys {
+        for (target, text) in [monkey.true_target, monkey.false_target]
+            .into_iter()
+            .zip(texts)
+        {
+            if target >= monkeys.len() {
+                return fail(
+                    text,
+                    format!("one of the {} monkeys to throw to", monkeys.len()),
+                );
+            }
         }
+    }
 
-        if let Some(target) = self.trim().strip_prefix("If false: throw to monkey ") {
-            return target.parse();
-        }
+    Ok((
+        rest,
+        Monkeys(monkeys.into_iter().map(|(monkey, _)| monkey).collect()),
+    ))
+}
 
-        panic!("Malformed input");
+fn too_worrying(what: String) -> Error {
+    Error::NoSolution {
+        reason: format!("{what} doesn't fit in 64 bits"),
     }
 }
 
-impl FromStr for Monkey {
-    type Err = ParseIntError;
+fn monkey_business(mut inspected: Vec<u64>) -> Result<u64, Error> {
+    inspected.sort_by_key(|w| Reverse(*w));
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let lines = s.lines().skip(1).collect::<Vec<_>>();
+    inspected[0]
+        .checked_mul(inspected[1])
+        .ok_or_else(|| too_worrying("The monkey business".to_string()))
+}
 
-        Ok(Self {
-            items: lines[0].parse_starting_items()?,
-            op: lines[1].parse()?,
-            test: lines[2].parse()?,
-            true_target: lines[3].parse_target()?,
-            false_target: lines[4].parse_target()?,
-            inspected: 0,
-        })
+/// Every monkey inspects and throws all of its items, which become less worrying by `relief`.
+fn round(monkeys: &mut [Monkey], relief: impl Fn(u64) -> u64) -> Result<(), Error> {
+    for source in 0..monkeys.len() {
+        let queue = std::mem::take(&mut monkeys[source].items);
+
+        for item in queue {
+            let item = monkeys[source].op.apply(item).ok_or_else(|| {
+                too_worrying(format!(
+                    "The worry level of monkey {source} inspecting {item}"
+                ))
+            })?;
+            let item = relief(item);
+            let target = monkeys[source].target(item);
+
+            monkeys[source].inspected += 1;
+            monkeys[target].items.push(item);
+        }
     }
+
+    Ok(())
 }
 
-fn monkey_business(monkeys: &[Monkey]) -> u64 {
-    let mut inspected = monkeys.iter().map(|m| m.inspected).collect::<Vec<_>>();
+/// The monkey business after `rounds` rounds, which skips the rounds that only repeat earlier ones
+/// once the monkeys hold the same items as before.
+fn play(monkeys: &Monkeys, rounds: usize, relief: impl Fn(u64) -> u64) -> Result<u64, Error> {
+    let mut result = Ok(());
+
+    let history = cycle::detect(
+        monkeys.0.clone(),
+        rounds,
+        |monkeys| {
+            // Leaves the monkeys alone after an error, which ends the simulation as a cycle.
+            if result.is_ok() {
+                result = round(monkeys, &relief);
+            }
+        },
+        |monkeys| monkeys.iter().map(|m| m.items.clone()).collect::<Vec<_>>(),
+        |monkeys| monkeys.iter().map(|m| m.inspected).collect::<Vec<_>>(),
+    );
 
-    inspected.sort_by_key(|w| Reverse(*w));
+    result?;
 
-    inspected[0] * inspected[1]
+    monkey_business(history.after(rounds).unwrap())
 }
 
-#[aoc(day11, part1)]
-pub fn part1(input: &str) -> Result<u64, ParseIntError> {
-    let mut monkeys = input
-        .split("\n\n")
-        .map(|input| input.parse::<Monkey>())
-        .collect::<Result<Vec<_>, _>>()?;
-
-    for _ in 0..20 {
-        for source in 0..monkeys.len() {
-            let queue = std::mem::take(&mut monkeys[source].items);
-
-            for item in queue {
-                let item = monkeys[source].op.apply(item) / 3;
-                let target = monkeys[source].target(item);
-
-                monkeys[source].inspected += 1;
-                monkeys[target].items.push(item);
-            }
+/// Tunables of the puzzle, see [`crate::params`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Params {
+    pub part1_rounds: usize,
+    pub part2_rounds: usize,
+}
+
+impl Default for Params {
+    fn default() -> Self {
+        Params {
+            part1_rounds: 20,
+            part2_rounds: 10000,
+        }
+    }
+}
+
+/// Checks that the monkeys are numbered in order and only throw to other monkeys that exist.
+pub fn validate(input: &str) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    let blocks = input.split("\n\n").collect::<Vec<_>>();
+
+    if blocks.len() < 2 {
+        diagnostics.push(Diagnostic::error(
+            "Monkey business needs at least two monkeys",
+        ));
+    }
+
+    for (idx, block) in blocks.iter().enumerate() {
+        let header = block.lines().next().unwrap_or_default();
+
+        if header != format!("Monkey {idx}:") {
+            diagnostics.push(
+                Diagnostic::warning(format!("Expected \"Monkey {idx}:\", found {header:?}"))
+                    .at(input, header),
+            );
+        }
+
+        let Some((monkey, _)) = check(&mut diagnostics, input, parse::all(block, monkey)) else {
+            continue;
+        };
+
+        for target in [monkey.true_target, monkey.false_target] {
+            let diagnostic = if target == idx {
+                // The item is inspected again in the next round, which the solver handles.
+                Diagnostic::warning(format!("Monkey {idx} throws to itself"))
+            } else if target >= blocks.len() {
+                Diagnostic::error(format!(
+                    "Monkey {idx} throws to monkey {target}, but there are only {}",
+                    blocks.len()
+                ))
+            } else {
+                continue;
+            };
+
+            diagnostics.push(diagnostic.at(input, block));
         }
     }
 
-    Ok(monkey_business(&monkeys))
+    diagnostics
+}
+
+/// The monkeys in the order of their numbers, holding their starting items.
+#[derive(Debug, Serialize)]
+pub struct Monkeys(Vec<Monkey>);
+
+#[aoc_generator(day11)]
+pub fn parse(input: &str) -> Result<Monkeys, Error> {
+    parse::all(input, monkeys)
+}
+
+#[aoc(day11, part1)]
+pub fn part1(monkeys: &Monkeys) -> Result<u64, Error> {
+    part1_with(monkeys, &Params::default())
+}
+
+pub fn part1_with(monkeys: &Monkeys, params: &Params) -> Result<u64, Error> {
+    play(monkeys, params.part1_rounds, |item| item / 3)
 }
 
 #[aoc(day11, part2)]
-pub fn part2(input: &str) -> Result<u64, ParseIntError> {
-    let mut monkeys = input
-        .split("\n\n")
-        .map(|input| input.parse::<Monkey>())
-        .collect::<Result<Vec<_>, _>>()?;
+pub fn part2(monkeys: &Monkeys) -> Result<u64, Error> {
+    part2_with(monkeys, &Params::default())
+}
 
+pub fn part2_with(monkeys: &Monkeys, params: &Params) -> Result<u64, Error> {
     let max_value = monkeys
+        .0
         .iter()
         .map(|m| match m.test {
             Test::DivisibleBy(x) => x,
         })
         .collect::<HashSet<_>>()
-        .iter()
-        .product::<u64>();
-
-    for _ in 0..10000 {
-        for source in 0..monkeys.len() {
-            let queue = std::mem::take(&mut monkeys[source].items);
-
-            for item in queue {
-                let item = monkeys[source].op.apply(item) % max_value;
-                let target = monkeys[source].target(item);
-
-                monkeys[source].inspected += 1;
-                monkeys[target].items.push(item);
-            }
-        }
-    }
+        .into_iter()
+        .try_fold(1u64, u64::checked_mul)
+        .ok_or_else(|| too_worrying("The product of the divisors".to_string()))?;
 
-    Ok(monkey_business(&monkeys))
+    play(monkeys, params.part2_rounds, |item| item % max_value)
 }
 
 #[cfg(test)]
 mod tests {
-    #[test]
-    fn test_case_1() {
-        let result = super::part1("Monkey 0:\n  Starting items: 79, 98\n  Operation: new = old * 19\n  Test: divisible by 23\n    If true: throw to monkey 2\n    If false: throw to monkey 3\n\nMonkey 1:\n  Starting items: 54, 65, 75, 74\n  Operation: new = old + 6\n  Test: divisible by 19\n    If true: throw to monkey 2\n    If false: throw to monkey 0\n\nMonkey 2:\n  Starting items: 79, 60, 97\n  Operation: new = old * old\n  Test: divisible by 13\n    If true: throw to monkey 1\n    If false: throw to monkey 3\n\nMonkey 3:\n  Starting items: 74\n  Operation: new = old + 3\n  Test: divisible by 17\n    If true: throw to monkey 0\n    If false: throw to monkey 1");
-        assert_eq!(result, Ok(10605));
-    }
+    use crate::{Error, Severity};
 
     #[test]
-    fn test_case_2() {
-        let result = super::part2("Monkey 0:\n  Starting items: 79, 98\n  Operation: new = old * 19\n  Test: divisible by 23\n    If true: throw to monkey 2\n    If false: throw to monkey 3\n\nMonkey 1:\n  Starting items: 54, 65, 75, 74\n  Operation: new = old + 6\n  Test: divisible by 19\n    If true: throw to monkey 2\n    If false: throw to monkey 0\n\nMonkey 2:\n  Starting items: 79, 60, 97\n  Operation: new = old * old\n  Test: divisible by 13\n    If true: throw to monkey 1\n    If false: throw to monkey 3\n\nMonkey 3:\n  Starting items: 74\n  Operation: new = old + 3\n  Test: divisible by 17\n    If true: throw to monkey 0\n    If false: throw to monkey 1");
-        assert_eq!(result, Ok(2713310158));
+    fn test_case_1() {
+        // Monkey 0 squares 2^32 and keeps the item, which is more than 64 bits can hold.
+        let input = "Monkey 0:\n  Starting items: 4294967296\n  Operation: new = old * old\n  \
+                     Test: divisible by 2\n    If true: throw to monkey 0\n    If false: throw to \
+                     monkey 1\n\nMonkey 1:\n  Starting items: 1\n  Operation: new = old + 1\n  \
+                     Test: divisible by 2\n    If true: throw to monkey 0\n    If false: throw to \
+                     monkey 0";
+
+        let monkeys = super::parse(input).unwrap();
+
+        assert!(matches!(
+            super::part1(&monkeys),
+            Err(Error::NoSolution { .. })
+        ));
+        assert!(matches!(
+            super::part2(&monkeys),
+            Err(Error::NoSolution { .. })
+        ));
+
+        let diagnostics = super::validate(input);
+
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].severity, Severity::Warning);
+        assert_eq!(diagnostics[0].message, "Monkey 0 throws to itself");
     }
 }
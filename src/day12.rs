@@ -1,157 +1,180 @@
-use std::{
-    collections::{HashSet, VecDeque},
-    num::ParseIntError,
+use aoc_runner_derive::{aoc, aoc_generator};
+use serde::{Serialize, Serializer};
+
+use crate::{
+    diagnostic::Diagnostic,
+    util::{
+        grid::{Grid, Pos},
+        parse::{self, grid},
+        search::bfs,
+    },
+    Error, Stats,
 };
 
-use aoc_runner_derive::aoc;
-
-struct HeightMap<'a> {
-    data: &'a [u8],
-    end: (usize, usize),
-    start: (usize, usize),
-    stride: usize,
+#[derive(Debug)]
+pub struct HeightMap {
+    grid: Grid<u8>,
+    end: Pos,
+    start: Pos,
 }
 
-impl<'a> HeightMap<'a> {
-    fn new(data: &'a str) -> Self {
-        assert!(data.is_ascii());
-
-        let data = data.as_bytes();
-        let stride = data.iter().position(|&c| c == b'\n').unwrap() + 1;
-
-        let end = data.iter().position(|&c| c == b'E').unwrap();
-        let end = (end % stride, end / stride);
-
-        let start = data.iter().position(|&c| c == b'S').unwrap();
-        let start = (start % stride, start / stride);
-
-        HeightMap {
-            data,
-            end,
-            start,
-            stride,
+/// Serializes the heights row by row with `S` and `E` replaced by their heights, along with where
+/// they were.
+impl Serialize for HeightMap {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        #[derive(Serialize)]
+        struct Map {
+            heights: Vec<String>,
+            start: Pos,
+            end: Pos,
         }
-    }
-
-    fn width(&self) -> usize {
-        self.stride - 1
-    }
 
-    fn height(&self) -> usize {
-        (self.data.len() + 1) / self.stride
+        Map {
+            heights: self
+                .grid
+                .rows()
+                .map(|row| row.iter().map(|&c| c as char).collect())
+                .collect(),
+            start: self.start,
+            end: self.end,
+        }
+        .serialize(serializer)
     }
+}
 
-    fn get(&self, (x, y): (usize, usize)) -> u8 {
-        assert!(x < self.stride);
-        assert!(y * self.stride + x < self.data.len());
-
-        let result = self.data[y * self.stride + x];
-
-        match result {
+impl HeightMap {
+    fn new(input: &str) -> Result<Self, Error> {
+        let raw = parse::all(
+            input,
+            grid("a height (a-z), S or E", |c| match c {
+                'a'..='z' | 'S' | 'E' => Some(c as u8),
+                _ => None,
+            }),
+        )?;
+
+        let Some(end) = raw.position(|&c| c == b'E') else {
+            return Err(Error::parse(input, "a map with an end position (E)"));
+        };
+
+        let Some(start) = raw.position(|&c| c == b'S') else {
+            return Err(Error::parse(input, "a map with a start position (S)"));
+        };
+
+        let grid = raw.map(|&c| match c {
             b'S' => b'a',
             b'E' => b'z',
-            _ => result,
-        }
-    }
-
-    fn neighbors(&'a self, (x, y): (usize, usize)) -> impl Iterator<Item = (usize, usize)> {
-        let mut result = Vec::new();
-
-        if x > 0 {
-            result.push((x - 1, y));
-        }
-
-        if x < self.width() - 1 {
-            result.push((x + 1, y));
-        }
+            _ => c,
+        });
 
-        if y > 0 {
-            result.push((x, y - 1));
-        }
+        Ok(HeightMap { grid, end, start })
+    }
 
-        if y < self.height() - 1 {
-            result.push((x, y + 1));
-        }
+    fn get(&self, pos: Pos) -> u8 {
+        self.grid[pos]
+    }
 
-        result.into_iter()
+    fn neighbors(&self, pos: Pos) -> impl Iterator<Item = Pos> + '_ {
+        self.grid.neighbours4(pos)
     }
 }
 
-#[aoc(day12, part1)]
-pub fn part1(input: &str) -> Result<usize, ParseIntError> {
-    let map = HeightMap::new(input);
-    let mut queue = VecDeque::new();
-    let mut visited = HashSet::new();
-
-    queue.push_back((map.start, 0));
-    visited.insert(map.start);
-
-    while let Some((pos, cost)) = queue.pop_front() {
-        let max_height = map.get(pos) + 1;
-
-        for neighbor in map.neighbors(pos) {
-            if map.get(neighbor) > max_height {
-                continue;
-            }
-
-            if neighbor == map.end {
-                return Ok(cost + 1);
-            }
-
-            if !visited.contains(&neighbor) {
-                queue.push_back((neighbor, cost + 1));
-                visited.insert(neighbor);
-            }
-        }
+/// Checks that the map has exactly one start and one end.
+pub fn validate(input: &str) -> Vec<Diagnostic> {
+    if let Err(err) = HeightMap::new(input) {
+        return vec![Diagnostic::from_error(err, input)];
     }
 
-    panic!("No path found");
+    ['S', 'E']
+        .into_iter()
+        .filter_map(|marker| {
+            let (idx, _) = input.match_indices(marker).nth(1)?;
+
+            Some(
+                Diagnostic::warning(format!(
+                    "More than one {marker} on the map, only the first one is used"
+                ))
+                .at(input, &input[idx..]),
+            )
+        })
+        .collect()
 }
 
-#[aoc(day12, part2)]
-pub fn part2(input: &str) -> Result<usize, ParseIntError> {
-    let map = HeightMap::new(input);
-    let mut queue = VecDeque::new();
-    let mut visited = HashSet::new();
-
-    queue.push_back((map.end, 0));
-    visited.insert(map.end);
-
-    while let Some((pos, cost)) = queue.pop_front() {
-        let min_height = map.get(pos) - 1;
-
-        for neighbor in map.neighbors(pos) {
-            let height = map.get(neighbor);
+#[aoc_generator(day12)]
+pub fn parse(input: &str) -> Result<HeightMap, Error> {
+    HeightMap::new(input)
+}
 
-            if height < min_height {
-                continue;
-            }
+#[aoc(day12, part1)]
+pub fn part1(map: &HeightMap) -> Result<usize, Error> {
+    part1_with_stats(map).map(|(steps, _)| steps)
+}
 
-            if height == b'a' {
-                return Ok(cost + 1);
-            }
+pub fn part1_with_stats(map: &HeightMap) -> Result<(usize, Stats), Error> {
+    let path = bfs(
+        map.start,
+        move |&pos| {
+            let max_height = map.get(pos) + 1;
+
+            map.neighbors(pos)
+                .filter(move |&neighbor| map.get(neighbor) <= max_height)
+        },
+        |&pos| pos == map.end,
+    );
+
+    path.map(|path| (path.cost, path.stats))
+        .ok_or_else(|| Error::NoSolution {
+            reason: "there is no path from S to E".into(),
+        })
+}
 
-            if !visited.contains(&neighbor) {
-                queue.push_back((neighbor, cost + 1));
-                visited.insert(neighbor);
-            }
-        }
-    }
+#[aoc(day12, part2)]
+pub fn part2(map: &HeightMap) -> Result<usize, Error> {
+    part2_with_stats(map).map(|(steps, _)| steps)
+}
 
-    panic!("No path found");
+pub fn part2_with_stats(map: &HeightMap) -> Result<(usize, Stats), Error> {
+    let path = bfs(
+        map.end,
+        move |&pos| {
+            let min_height = map.get(pos) - 1;
+
+            map.neighbors(pos)
+                .filter(move |&neighbor| map.get(neighbor) >= min_height)
+        },
+        |&pos| map.get(pos) == b'a',
+    );
+
+    path.map(|path| (path.cost, path.stats))
+        .ok_or_else(|| Error::NoSolution {
+            reason: "there is no path from any square of height a to E".into(),
+        })
 }
 
 #[cfg(test)]
 mod tests {
     #[test]
     fn test_case_1() {
-        let result = super::part1("Sabqponm\nabcryxxl\naccszExk\nacctuvwj\nabdefghi");
-        assert_eq!(result, Ok(31));
+        let (steps, stats) = super::part1_with_stats(
+            &super::parse(include_str!("../input/2022/examples/day12-example.txt")).unwrap(),
+        )
+        .unwrap();
+
+        assert_eq!(steps, 31);
+        assert!(stats.visited <= 40);
+        assert!(stats.expanded < stats.visited);
     }
 
     #[test]
     fn test_case_2() {
-        let result = super::part2("Sabqponm\nabcryxxl\naccszExk\nacctuvwj\nabdefghi");
-        assert_eq!(result, Ok(29));
+        let map = super::parse("Sbz\nazE").unwrap();
+
+        assert!(matches!(
+            super::part1(&map),
+            Err(crate::Error::NoSolution { .. })
+        ));
+        assert!(matches!(
+            super::part2(&map),
+            Err(crate::Error::NoSolution { .. })
+        ));
     }
 }
@@ -1,29 +1,27 @@
 use std::fmt::Debug;
-use std::num::ParseIntError;
 
-use aoc_runner_derive::aoc;
+use aoc_runner_derive::{aoc, aoc_generator};
 use nom::{
     branch::alt,
-    bytes::complete::tag,
-    character::complete::{char, digit1},
-    combinator::{map, map_res},
+    combinator::map,
     multi::separated_list0,
     sequence::{delimited, separated_pair},
-    IResult,
 };
+use serde::Serialize;
 
-#[derive(Clone)]
+use crate::{
+    diagnostic::{check, Diagnostic},
+    util::parse::{self, blocks, number, tag, IResult},
+    Error,
+};
+
+#[derive(Clone, Serialize)]
+#[serde(untagged)]
 enum Value {
     List(Vec<Value>),
     Number(u64),
 }
 
-impl Value {
-    fn from_int_str(s: &str) -> Result<Self, ParseIntError> {
-        Ok(Value::Number(s.parse::<u64>()?))
-    }
-}
-
 impl Debug for Value {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -56,25 +54,22 @@ impl Eq for Value {}
 
 impl PartialOrd for Value {
     fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        match (self, other) {
-            (Value::List(left), Value::List(right)) => left.partial_cmp(right),
-            (Value::Number(left), Value::Number(right)) => left.partial_cmp(right),
-            (Value::List(left), Value::Number(right)) => {
-                left.partial_cmp(&vec![Value::Number(*right)])
-            }
-            (Value::Number(left), Value::List(right)) => {
-                vec![Value::Number(*left)].partial_cmp(right)
-            }
-        }
+        Some(self.cmp(other))
     }
 }
 
 impl Ord for Value {
     fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-        self.partial_cmp(other).unwrap()
+        match (self, other) {
+            (Value::List(left), Value::List(right)) => left.cmp(right),
+            (Value::Number(left), Value::Number(right)) => left.cmp(right),
+            (Value::List(left), Value::Number(right)) => left.cmp(&vec![Value::Number(*right)]),
+            (Value::Number(left), Value::List(right)) => vec![Value::Number(*left)].cmp(right),
+        }
     }
 }
 
+#[derive(Serialize)]
 struct Pair {
     left: Vec<Value>,
     right: Vec<Value>,
@@ -100,69 +95,83 @@ impl Debug for Pair {
     }
 }
 
-fn parse_value_list(i: &str) -> IResult<&str, Vec<Value>> {
-    delimited(tag("["), separated_list0(tag(","), parse_value), tag("]"))(i)
+fn value_list(input: &str) -> IResult<'_, Vec<Value>> {
+    delimited(tag("["), separated_list0(tag(","), value), tag("]"))(input)
 }
 
-fn parse_value(i: &str) -> IResult<&str, Value> {
-    alt((
-        map(parse_value_list, Value::List),
-        map_res(digit1, Value::from_int_str),
-    ))(i)
+fn value(input: &str) -> IResult<'_, Value> {
+    alt((map(value_list, Value::List), map(number, Value::Number)))(input)
 }
 
-fn parse_pair(i: &str) -> IResult<&str, Pair> {
+fn pair(input: &str) -> IResult<'_, Pair> {
     map(
-        separated_pair(parse_value_list, char('\n'), parse_value_list),
+        separated_pair(value_list, tag("\n"), value_list),
         |(left, right)| Pair { left, right },
-    )(i)
+    )(input)
 }
 
-#[aoc(day13, part1)]
-pub fn part1(input: &str) -> Result<usize, ParseIntError> {
-    let pairs = input.split("\n\n").map(|s| parse_pair(s).unwrap().1);
+/// Checks that the packets come in pairs, and that none of them is mistaken for a divider packet.
+pub fn validate(input: &str) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+
+    for block in input.split("\n\n") {
+        check(&mut diagnostics, input, parse::all(block, pair));
+    }
 
+    for line in input
+        .lines()
+        .filter(|&line| line == "[[2]]" || line == "[[6]]")
+    {
+        diagnostics.push(
+            Diagnostic::warning(format!(
+                "The packet {line} is the same as a divider packet, which one is found first is \
+                 up to the sort"
+            ))
+            .at(input, line),
+        );
+    }
+
+    diagnostics
+}
+
+/// Every pair of packets, in order.
+#[derive(Debug, Serialize)]
+pub struct Pairs(Vec<Pair>);
+
+#[aoc_generator(day13)]
+pub fn parse(input: &str) -> Result<Pairs, Error> {
+    parse::all(input, blocks(pair)).map(Pairs)
+}
+
+#[aoc(day13, part1)]
+pub fn part1(pairs: &Pairs) -> usize {
     let mut result = 0;
 
-    for (pair_idx, pair) in pairs.enumerate() {
+    for (pair_idx, pair) in pairs.0.iter().enumerate() {
         if pair.left < pair.right {
             result += pair_idx + 1;
         }
     }
 
-    Ok(result)
+    result
 }
 
 #[aoc(day13, part2)]
-pub fn part2(input: &str) -> Result<usize, ParseIntError> {
+pub fn part2(pairs: &Pairs) -> usize {
     let start_packet = vec![Value::List(vec![Value::Number(2)])];
     let end_packet = vec![Value::List(vec![Value::Number(6)])];
 
-    let mut packets = input
-        .split_whitespace()
-        .map(|s| parse_value_list(s).unwrap().1)
-        .chain(vec![start_packet.clone(), end_packet.clone()])
+    let mut packets = pairs
+        .0
+        .iter()
+        .flat_map(|pair| [&pair.left, &pair.right])
+        .chain([&start_packet, &end_packet])
         .collect::<Vec<_>>();
 
     packets.sort();
 
-    let start_pos = packets.iter().position(|p| p == &start_packet).unwrap() + 1;
-    let end_pos = packets.iter().position(|p| p == &end_packet).unwrap() + 1;
-
-    Ok(start_pos * end_pos)
-}
-
-#[cfg(test)]
-mod tests {
-    #[test]
-    fn test_case_1() {
-        let result = super::part1("[1,1,3,1,1]\n[1,1,5,1,1]\n\n[[1],[2,3,4]]\n[[1],4]\n\n[9]\n[[8,7,6]]\n\n[[4,4],4,4]\n[[4,4],4,4,4]\n\n[7,7,7,7]\n[7,7,7]\n\n[]\n[3]\n\n[[[]]]\n[[]]\n\n[1,[2,[3,[4,[5,6,7]]]],8,9]\n[1,[2,[3,[4,[5,6,0]]]],8,9]");
-        assert_eq!(result, Ok(13));
-    }
+    let start_pos = packets.iter().position(|&p| p == &start_packet).unwrap() + 1;
+    let end_pos = packets.iter().position(|&p| p == &end_packet).unwrap() + 1;
 
-    #[test]
-    fn test_case_2() {
-        let result = super::part2("[1,1,3,1,1]\n[1,1,5,1,1]\n\n[[1],[2,3,4]]\n[[1],4]\n\n[9]\n[[8,7,6]]\n\n[[4,4],4,4]\n[[4,4],4,4,4]\n\n[7,7,7,7]\n[7,7,7]\n\n[]\n[3]\n\n[[[]]]\n[[]]\n\n[1,[2,[3,[4,[5,6,7]]]],8,9]\n[1,[2,[3,[4,[5,6,0]]]],8,9]");
-        assert_eq!(result, Ok(140));
-    }
+    start_pos * end_pos
 }
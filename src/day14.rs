@@ -1,160 +1,260 @@
-use std::{collections::HashSet, num::ParseIntError, str::FromStr};
+use std::collections::HashSet;
 
-use aoc_runner_derive::aoc;
+use aoc_runner_derive::{aoc, aoc_generator};
 use itertools::Itertools;
+use nom::{
+    combinator::{consumed, map},
+    multi::separated_list1,
+    sequence::separated_pair,
+};
+use serde::{Serialize, Serializer};
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
-struct Coord {
-    x: u32,
-    y: u32,
-}
+use crate::{
+    diagnostic::Diagnostic,
+    util::{
+        geom::{BoundingBox, Point2},
+        grid::Grid,
+        parse::{self, fail, lines, number, tag, IResult},
+    },
+    visualize::{Draw, Recorder, Tile},
+    Error,
+};
 
-impl Coord {
-    fn add(&self, x: i32, y: i32) -> Coord {
-        Coord {
-            x: self.x.checked_add_signed(x).unwrap(),
-            y: self.y.checked_add_signed(y).unwrap(),
-        }
-    }
+fn point(input: &str) -> IResult<'_, Point2> {
+    map(separated_pair(number, tag(","), number), |(x, y)| {
+        Point2::new(x, y)
+    })(input)
 }
 
-impl From<(u32, u32)> for Coord {
-    fn from((x, y): (u32, u32)) -> Self {
-        Coord { x, y }
-    }
+/// A line of rock through the corners of a path.
+fn path(input: &str) -> IResult<'_, Vec<Point2>> {
+    separated_list1(tag(" -> "), point)(input)
 }
 
-impl FromStr for Coord {
-    type Err = ParseIntError;
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let (x, y) = s.split_once(',').unwrap();
+const SOURCE: Point2 = Point2::new(500, 0);
 
-        Ok(Coord {
-            x: x.parse()?,
-            y: y.parse()?,
-        })
-    }
+/// The rock in the cave, and later the sand that came to rest in it as well.
+#[derive(Debug, Clone)]
+pub struct Cave {
+    blocked: HashSet<Point2>,
+    max_y: i32,
+    sand_count: u32,
 }
 
-struct Cave {
-    blocked: HashSet<Coord>,
-    max_y: u32,
-    sand_count: u32,
+/// Serializes the rock, row by row from the top, as only the simulation adds sand to it.
+impl Serialize for Cave {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        #[derive(Serialize)]
+        struct Scan {
+            rock: Vec<Point2>,
+        }
+
+        Scan {
+            rock: self
+                .blocked
+                .iter()
+                .copied()
+                .sorted_by_key(|c| (c.y, c.x))
+                .collect(),
+        }
+        .serialize(serializer)
+    }
 }
 
 impl Cave {
-    fn is_free(&self, pos: Coord) -> bool {
+    fn is_free(&self, pos: Point2) -> bool {
         !self.blocked.contains(&pos)
     }
 
-    fn insert_sand(&mut self, pos: Coord) {
+    fn insert_sand(&mut self, pos: Point2) {
         self.blocked.insert(pos);
         self.sand_count += 1;
     }
-}
 
-impl FromStr for Cave {
-    type Err = ParseIntError;
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut blocked = HashSet::<Coord>::new();
-        let mut max_y = 0;
-
-        for line in s.lines() {
-            let parts = line
-                .split(" -> ")
-                .map(Coord::from_str)
-                .collect::<Result<Vec<_>, _>>()?;
-
-            max_y = max_y.max(parts.iter().map(|c| c.y).max().unwrap());
-
-            for (a, b) in parts.iter().tuple_windows() {
-                if a.x == b.x {
-                    for y in a.y.min(b.y)..=a.y.max(b.y) {
-                        blocked.insert((a.x, y).into());
-                    }
-                } else {
-                    for x in a.x.min(b.x)..=a.x.max(b.x) {
-                        blocked.insert((x, a.y).into());
-                    }
+    /// Lets a unit of sand fall from the source and returns where it comes to rest, or `None` if
+    /// it falls into the abyss. With a `floor` there is no abyss.
+    fn drop_sand(&mut self, floor: bool) -> Option<Point2> {
+        let mut sand = SOURCE;
+
+        loop {
+            if sand.y == self.max_y + 1 {
+                if !floor {
+                    return None;
                 }
+
+                break;
+            }
+
+            match [0, -1, 1]
+                .into_iter()
+                .map(|dx| sand + Point2::new(dx, 1))
+                .find(|&below| self.is_free(below))
+            {
+                Some(below) => sand = below,
+                None => break,
             }
         }
 
-        Ok(Cave {
-            blocked,
-            max_y,
-            sand_count: 0,
-        })
+        self.insert_sand(sand);
+        Some(sand)
     }
 }
 
-#[aoc(day14, part1)]
-pub fn part1(input: &str) -> Result<u32, ParseIntError> {
-    let mut cave = input.parse::<Cave>()?;
+const ROCK: Tile = Tile::new('#', [120, 110, 100]);
+const SAND: Tile = Tile::new('o', [230, 190, 90]);
+const AIR: Tile = Tile::new('.', [0, 0, 0]);
 
-    loop {
-        let mut sand = Coord { x: 500, y: 0 };
+/// A cave along with the rock it started out with, to tell the rock apart from the sand.
+struct Scan {
+    cave: Cave,
+    rock: HashSet<Point2>,
+    floor: bool,
+}
 
-        loop {
-            if sand.y > cave.max_y {
-                return Ok(cave.sand_count);
-            }
+impl Draw for Scan {
+    fn draw(&self) -> Grid<Tile> {
+        let mut bounds =
+            BoundingBox::of(self.cave.blocked.iter().copied().chain([SOURCE])).unwrap();
+        let max_y = self.cave.max_y + if self.floor { 2 } else { 1 };
 
-            if cave.is_free(sand.add(0, 1)) {
-                sand = sand.add(0, 1);
-                continue;
-            }
+        // A column of air to either side, and room for the floor or the abyss below.
+        bounds.include(Point2::new(bounds.min.x - 1, max_y));
+        bounds.include(Point2::new(bounds.max.x + 1, max_y));
+
+        let mut grid = Grid::new(bounds.width(), bounds.height(), AIR);
+
+        for &pos in &self.cave.blocked {
+            let tile = if self.rock.contains(&pos) { ROCK } else { SAND };
+            grid[bounds.grid_pos(pos)] = tile;
+        }
+
+        if self.floor {
+            grid.row_mut(grid.height() - 1)
+                .for_each(|tile| *tile = ROCK);
+        }
+
+        grid[bounds.grid_pos(SOURCE)].symbol = '+';
+        grid
+    }
+}
+
+fn cave(input: &str) -> IResult<'_, Cave> {
+    let (rest, paths) = lines(consumed(path))(input)?;
+    let mut blocked = HashSet::<Point2>::new();
+    let mut max_y = 0;
 
-            if cave.is_free(sand.add(-1, 1)) {
-                sand = sand.add(-1, 1);
-                continue;
+    for (line, parts) in paths {
+        max_y = max_y.max(parts.iter().map(|c| c.y).max().unwrap());
+
+        for (a, b) in parts.iter().tuple_windows() {
+            if a.x != b.x && a.y != b.y {
+                return fail(line, "only horizontal and vertical segments");
             }
 
-            if cave.is_free(sand.add(1, 1)) {
-                sand = sand.add(1, 1);
-                continue;
+            let step = (*b - *a).signum();
+            let mut rock = *a;
+
+            blocked.insert(rock);
+
+            while rock != *b {
+                rock += step;
+                blocked.insert(rock);
             }
+        }
+    }
 
-            cave.insert_sand(sand);
-            break;
+    Ok((
+        rest,
+        Cave {
+            blocked,
+            max_y,
+            sand_count: 0,
+        },
+    ))
+}
+
+/// Checks that the sand source is open, and that the cave is shallow enough for the pile of part 2
+/// to stay clear of x = 0.
+pub fn validate(input: &str) -> Vec<Diagnostic> {
+    let cave = match parse(input) {
+        Ok(cave) => cave,
+        Err(err) => return vec![Diagnostic::from_error(err, input)],
+    };
+
+    let mut diagnostics = Vec::new();
+
+    if !cave.is_free(SOURCE) {
+        diagnostics.push(Diagnostic::error("Rock covers the sand source at 500,0"));
+    }
+
+    // The pile on the floor of part 2 reaches `max_y + 1` to either side of the source.
+    if cave.max_y >= 500 {
+        diagnostics.push(Diagnostic::error(format!(
+            "The cave is {} deep, too deep for the sand of part 2 to stay at x >= 0",
+            cave.max_y
+        )));
+    }
+
+    diagnostics
+}
+
+#[aoc_generator(day14)]
+pub fn parse(input: &str) -> Result<Cave, Error> {
+    parse::all(input, cave)
+}
+
+#[aoc(day14, part1)]
+pub fn part1(cave: &Cave) -> u32 {
+    let mut cave = cave.clone();
+
+    loop {
+        match cave.drop_sand(false) {
+            // A cave that catches all the sand fills up to the source instead.
+            Some(sand) if sand != SOURCE => {}
+            _ => return cave.sand_count,
         }
     }
 }
 
 #[aoc(day14, part2)]
-pub fn part2(input: &str) -> Result<u32, ParseIntError> {
-    let mut cave = input.parse::<Cave>()?;
-    let mut stack = vec![Coord { x: 500, y: 0 }];
+pub fn part2(cave: &Cave) -> u32 {
+    let mut cave = cave.clone();
+    let mut stack = vec![SOURCE];
 
     while let Some(sand) = stack.pop() {
-        for dx in -1..=1 {
-            if cave.is_free(sand.add(dx, 1)) {
-                cave.insert_sand(sand.add(dx, 1));
+        for below in (-1..=1).map(|dx| sand + Point2::new(dx, 1)) {
+            if cave.is_free(below) {
+                cave.insert_sand(below);
 
                 if sand.y != cave.max_y {
-                    stack.push(sand.add(dx, 1));
+                    stack.push(below);
                 }
             }
         }
     }
 
     // Add one extra for the spawn point
-    Ok(cave.sand_count + 1)
+    cave.sand_count + 1
 }
 
-#[cfg(test)]
-mod tests {
-    #[test]
-    fn test_case_1() {
-        let result = super::part1("498,4 -> 498,6 -> 496,6\n503,4 -> 502,4 -> 502,9 -> 494,9");
-        assert_eq!(result, Ok(24));
-    }
+/// Records the cave of the given part every time a unit of sand comes to rest.
+pub fn visualize(cave: &Cave, part: u8, recorder: &mut Recorder) {
+    let cave = cave.clone();
+    let mut scan = Scan {
+        rock: cave.blocked.clone(),
+        cave,
+        floor: part == 2,
+    };
+
+    recorder.record(0, &scan);
 
-    #[test]
-    fn test_case_2() {
-        let result = super::part2("498,4 -> 498,6 -> 496,6\n503,4 -> 502,4 -> 502,9 -> 494,9");
-        assert_eq!(result, Ok(93));
+    while let Some(sand) = scan.cave.drop_sand(scan.floor) {
+        recorder.record(scan.cave.sand_count as usize, &scan);
+
+        if sand == SOURCE {
+            break;
+        }
     }
+
+    recorder.record_last(scan.cave.sand_count as usize, &scan);
 }
@@ -1,209 +1,142 @@
-use std::{cmp::Ordering, num::ParseIntError, ops::RangeInclusive, str::FromStr};
-
-use aoc_runner_derive::aoc;
+use aoc_runner_derive::{aoc, aoc_generator};
 use itertools::Itertools;
-
-#[cfg(test)]
-const TARGET_Y: i32 = 10;
-#[cfg(not(test))]
-const TARGET_Y: i32 = 2000000;
-
-#[cfg(test)]
-const MAX_POS: i32 = 20;
-#[cfg(not(test))]
-const MAX_POS: i32 = 4000000;
-
-#[derive(Debug)]
-struct Coord {
-    x: i32,
-    y: i32,
-}
-
-impl Coord {
-    fn distance_to(&self, other: &Coord) -> u32 {
-        self.x.abs_diff(other.x) + self.y.abs_diff(other.y)
-    }
+use nom::{
+    combinator::map,
+    sequence::{pair, preceded},
+};
+use serde::Serialize;
+
+use crate::{
+    diagnostic::{check, Diagnostic},
+    util::{
+        geom::Point2,
+        interval_set::IntervalSet,
+        parse::{self, coordinates, lines, tag, IResult},
+    },
+    Error,
+};
+
+#[derive(Debug, Serialize)]
+struct Sensor {
+    closest_beacon: Point2,
+    position: Point2,
 }
 
-impl FromStr for Coord {
-    type Err = ParseIntError;
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let (x, y) = s.split_once(',').unwrap();
-
-        Ok(Coord {
-            x: x.trim().strip_prefix("x=").unwrap().parse()?,
-            y: y.trim().strip_prefix("y=").unwrap().parse()?,
-        })
-    }
+fn sensor(input: &str) -> IResult<'_, Sensor> {
+    map(
+        pair(
+            preceded(tag("Sensor at "), coordinates),
+            preceded(tag(": closest beacon is at "), coordinates),
+        ),
+        |(position, closest_beacon)| Sensor {
+            closest_beacon,
+            position,
+        },
+    )(input)
 }
 
-trait MergeRanges {
-    fn merge(&mut self);
+/// Tunables of the puzzle, see [`crate::params`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Params {
+    /// Row to count the positions without a beacon in.
+    pub target_y: i32,
+    /// Largest x and y coordinate the distress beacon can be at.
+    pub max_pos: i32,
 }
 
-impl MergeRanges for Vec<RangeInclusive<i32>> {
-    fn merge(&mut self) {
-        assert!(!self.is_empty());
-
-        self.sort_by(|lhs, rhs| match lhs.start().cmp(rhs.start()) {
-            Ordering::Greater => Ordering::Greater,
-            Ordering::Less => Ordering::Less,
-            Ordering::Equal => lhs.end().cmp(rhs.end()),
-        });
-
-        let mut idx = 1;
-
-        while idx < self.len() {
-            if *self[idx].start() > (self[idx - 1].end() + 1) {
-                idx += 1;
-                continue;
-            }
-
-            let new = RangeInclusive::new(
-                *self[idx - 1].start(),
-                *self[idx].end().max(self[idx - 1].end()),
-            );
-
-            self[idx - 1] = new;
-            self.remove(idx);
+impl Default for Params {
+    fn default() -> Self {
+        Params {
+            target_y: 2000000,
+            max_pos: 4000000,
         }
     }
 }
 
-#[derive(Debug)]
-struct Sensor {
-    closest_beacon: Coord,
-    position: Coord,
-}
-
-impl FromStr for Sensor {
-    type Err = ParseIntError;
+/// Checks that some sensor covers the row of part 1 with the default [`Params`].
+pub fn validate(input: &str) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let (sensor, beacon) = s.split_once(':').unwrap();
+    let sensors = input
+        .lines()
+        .filter_map(|line| check(&mut diagnostics, input, parse::all(line, sensor)))
+        .collect::<Vec<_>>();
 
-        let sensor = sensor.trim().strip_prefix("Sensor at").unwrap();
-        let beacon = beacon.trim().strip_prefix("closest beacon is at").unwrap();
+    let target_y = Params::default().target_y;
 
-        Ok(Sensor {
-            closest_beacon: beacon.parse()?,
-            position: sensor.parse()?,
+    if diagnostics.is_empty()
+        && !sensors.iter().any(|sensor| {
+            sensor.position.y.abs_diff(target_y) <= sensor.position.manhattan(sensor.closest_beacon)
         })
+    {
+        diagnostics.push(Diagnostic::error(format!(
+            "No sensor reaches row y={target_y}"
+        )));
     }
-}
 
-#[aoc(day15, part1)]
-pub fn part1(input: &str) -> Result<usize, ParseIntError> {
-    let sensors = input
-        .lines()
-        .map(|line| line.parse::<Sensor>())
-        .collect::<Result<Vec<_>, _>>()?;
+    diagnostics
+}
 
-    let mut ranges = sensors
+/// The positions in row `y` that are no farther from some sensor than its closest beacon.
+fn covered(sensors: &[Sensor], y: i32) -> IntervalSet<i32> {
+    sensors
         .iter()
         .filter_map(|sensor| {
-            let reach = sensor.position.distance_to(&sensor.closest_beacon);
-            let dy = sensor.position.y.abs_diff(TARGET_Y);
+            let reach = sensor.position.manhattan(sensor.closest_beacon);
+            let dy = sensor.position.y.abs_diff(y);
+            let x = sensor.position.x;
 
-            if dy > reach {
-                return None;
-            }
+            (dy <= reach).then(|| {
+                let half_width = (reach - dy) as i32;
 
-            Some(RangeInclusive::new(
-                sensor.position.x.checked_sub_unsigned(reach - dy).unwrap(),
-                sensor.position.x.checked_add_unsigned(reach - dy).unwrap(),
-            ))
+                x - half_width..=x + half_width
+            })
         })
-        .collect::<Vec<_>>();
+        .collect()
+}
 
-    ranges.merge();
+/// Every sensor along with the beacon closest to it.
+#[derive(Debug, Serialize)]
+pub struct Sensors(Vec<Sensor>);
+
+#[aoc_generator(day15)]
+pub fn parse(input: &str) -> Result<Sensors, Error> {
+    parse::all(input, lines(sensor)).map(Sensors)
+}
+
+#[aoc(day15, part1)]
+pub fn part1(sensors: &Sensors) -> usize {
+    part1_with(sensors, &Params::default())
+}
+
+pub fn part1_with(sensors: &Sensors, params: &Params) -> usize {
+    let covered = covered(&sensors.0, params.target_y);
 
     let beacons_in_target = sensors
+        .0
         .iter()
-        .filter(|sensor| sensor.closest_beacon.y == TARGET_Y)
-        .map(|sensor| &sensor.closest_beacon)
-        .filter(|beacon| ranges.iter().any(|range| range.contains(&beacon.x)))
+        .map(|sensor| sensor.closest_beacon)
+        .filter(|beacon| beacon.y == params.target_y && covered.contains(beacon.x))
         .map(|beacon| beacon.x)
         .unique()
         .count();
 
-    let scanned_positions = ranges
-        .iter()
-        .map(|range| (range.end() - range.start() + 1) as usize)
-        .sum::<usize>();
-
-    Ok(scanned_positions - beacons_in_target)
+    covered.len() as usize - beacons_in_target
 }
 
 #[aoc(day15, part2)]
-pub fn part2(input: &str) -> Result<u64, ParseIntError> {
-    let sensors = input
-        .lines()
-        .map(|line| line.parse::<Sensor>())
-        .collect::<Result<Vec<_>, _>>()?;
-
-    for y in 0..=MAX_POS {
-        let mut ranges = sensors
-            .iter()
-            .filter_map(|sensor| {
-                let reach = sensor.position.distance_to(&sensor.closest_beacon);
-                let dy = sensor.position.y.abs_diff(y);
-
-                if dy > reach {
-                    return None;
-                }
-
-                let start = sensor
-                    .position
-                    .x
-                    .checked_sub_unsigned(reach - dy)
-                    .unwrap()
-                    .max(0);
-
-                if start > MAX_POS {
-                    return None;
-                }
-
-                let end = sensor
-                    .position
-                    .x
-                    .checked_add_unsigned(reach - dy)
-                    .unwrap()
-                    .min(MAX_POS);
-
-                if end < 0 {
-                    return None;
-                }
-
-                Some(RangeInclusive::new(start, end))
-            })
-            .collect::<Vec<_>>();
-
-        ranges.merge();
-
-        if ranges.len() > 1 {
-            let x = (ranges[0].end() + 1) as u64;
-            let y = y as u64;
-
-            return Ok(x * 4000000 + y);
-        }
-    }
-
-    panic!("No hidden beacon found!");
+pub fn part2(sensors: &Sensors) -> Result<u64, Error> {
+    part2_with(sensors, &Params::default())
 }
 
-#[cfg(test)]
-mod tests {
-    #[test]
-    fn test_case_1() {
-        let result = super::part1("Sensor at x=2, y=18: closest beacon is at x=-2, y=15\nSensor at x=9, y=16: closest beacon is at x=10, y=16\nSensor at x=13, y=2: closest beacon is at x=15, y=3\nSensor at x=12, y=14: closest beacon is at x=10, y=16\nSensor at x=10, y=20: closest beacon is at x=10, y=16\nSensor at x=14, y=17: closest beacon is at x=10, y=16\nSensor at x=8, y=7: closest beacon is at x=2, y=10\nSensor at x=2, y=0: closest beacon is at x=2, y=10\nSensor at x=0, y=11: closest beacon is at x=2, y=10\nSensor at x=20, y=14: closest beacon is at x=25, y=17\nSensor at x=17, y=20: closest beacon is at x=21, y=22\nSensor at x=16, y=7: closest beacon is at x=15, y=3\nSensor at x=14, y=3: closest beacon is at x=15, y=3\nSensor at x=20, y=1: closest beacon is at x=15, y=3");
-        assert_eq!(result, Ok(26));
+pub fn part2_with(sensors: &Sensors, params: &Params) -> Result<u64, Error> {
+    for y in 0..=params.max_pos {
+        if let Some(gap) = covered(&sensors.0, y).gaps(0..=params.max_pos).next() {
+            return Ok(*gap.start() as u64 * 4000000 + y as u64);
+        }
     }
 
-    #[test]
-    fn test_case_2() {
-        let result = super::part2("Sensor at x=2, y=18: closest beacon is at x=-2, y=15\nSensor at x=9, y=16: closest beacon is at x=10, y=16\nSensor at x=13, y=2: closest beacon is at x=15, y=3\nSensor at x=12, y=14: closest beacon is at x=10, y=16\nSensor at x=10, y=20: closest beacon is at x=10, y=16\nSensor at x=14, y=17: closest beacon is at x=10, y=16\nSensor at x=8, y=7: closest beacon is at x=2, y=10\nSensor at x=2, y=0: closest beacon is at x=2, y=10\nSensor at x=0, y=11: closest beacon is at x=2, y=10\nSensor at x=20, y=14: closest beacon is at x=25, y=17\nSensor at x=17, y=20: closest beacon is at x=21, y=22\nSensor at x=16, y=7: closest beacon is at x=15, y=3\nSensor at x=14, y=3: closest beacon is at x=15, y=3\nSensor at x=20, y=1: closest beacon is at x=15, y=3");
-        assert_eq!(result, Ok(56000011));
-    }
+    Err(Error::NoSolution {
+        reason: format!("the sensors cover every position up to {}", params.max_pos),
+    })
 }
@@ -1,13 +1,25 @@
 use std::{
+    cmp::Reverse,
     collections::{HashMap, HashSet},
     fmt::Debug,
-    num::ParseIntError,
     str::FromStr,
 };
 
-use aoc_runner_derive::aoc;
-
-use crate::util::priority_queue::{PriorityQueue, PriorityQueueItem};
+use aoc_runner_derive::{aoc, aoc_generator};
+use nom::{
+    combinator::{consumed, opt},
+    sequence::{preceded, tuple},
+};
+use serde::{Serialize, Serializer};
+
+use crate::{
+    diagnostic::{check, Diagnostic},
+    util::{
+        parse::{self, fail, lines, list, number, tag, IResult},
+        search::astar_until,
+    },
+    Budget, Error, Outcome, Stats,
+};
 
 #[derive(Clone, Copy, PartialEq, Eq, Hash)]
 struct ValveId(u16);
@@ -22,74 +34,66 @@ impl Debug for ValveId {
     }
 }
 
+/// Serializes as the two letter name.
+impl Serialize for ValveId {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(&format_args!("{self:?}"))
+    }
+}
+
+fn valve_id(input: &str) -> IResult<'_, ValveId> {
+    let len = input
+        .find(|c: char| !c.is_ascii_alphabetic())
+        .unwrap_or(input.len());
+
+    match input.as_bytes()[..len] {
+        [a, b] => Ok((&input[len..], ValveId(u16::from_be_bytes([a, b])))),
+        _ => fail(&input[..len], "a two letter valve name"),
+    }
+}
+
 impl FromStr for ValveId {
-    type Err = ParseIntError;
+    type Err = Error;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let s = s.as_bytes();
-
-        Ok(ValveId(u16::from_be_bytes([s[0], s[1]])))
+        parse::all(s, valve_id)
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Serialize)]
 struct Valve {
+    #[serde(rename = "name")]
     id: ValveId,
     flow_rate: u8,
     tunnels: Vec<ValveId>,
 }
 
-impl FromStr for Valve {
-    type Err = ParseIntError;
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let Some(s) = s.strip_prefix("Valve ") else {
-            panic!("Invalid input");
-        };
-
-        let (id, s) = s.split_at(2);
-
-        let Some(s) = s.strip_prefix(" has flow rate=") else {
-            panic!("Invalid input");
-        };
-
-        let Some((flow_rate, s)) = s.split_once(';') else {
-            panic!("Invalid input");
-        };
-
-        let Some(s) = s.strip_prefix(" tunnel") else {
-            panic!("Invalid input");
-        };
-
-        let s = s.trim_start_matches('s');
-
-        let Some(s) = s.strip_prefix(" lead") else {
-            panic!("Invalid input");
-        };
-
-        let s = s.trim_start_matches('s');
-
-        let Some(s) = s.strip_prefix(" to valve") else {
-            panic!("Invalid input");
-        };
-
-        let s = s.trim_start_matches('s');
-
-        let tunnels = s
-            .split(',')
-            .map(|s| s.trim().parse::<ValveId>())
-            .collect::<Result<Vec<_>, _>>()?;
-
-        Ok(Valve {
-            id: id.parse()?,
-            flow_rate: flow_rate.parse()?,
+fn valve(input: &str) -> IResult<'_, Valve> {
+    let (input, id) = preceded(tag("Valve "), valve_id)(input)?;
+    let (input, flow_rate) = preceded(tag(" has flow rate="), number)(input)?;
+    let (input, _) = tuple((
+        tag("; tunnel"),
+        opt(tag("s")),
+        tag(" lead"),
+        opt(tag("s")),
+        tag(" to valve"),
+        opt(tag("s")),
+        tag(" "),
+    ))(input)?;
+    let (input, tunnels) = list(valve_id)(input)?;
+
+    Ok((
+        input,
+        Valve {
+            id,
+            flow_rate,
             tunnels,
-        })
-    }
+        },
+    ))
 }
 
 struct Part1Step {
-    open: HashSet<u8>,
+    open: HashSet<ValveId>,
     position: ValveId,
     previous: ValveId,
     score: usize,
@@ -97,13 +101,13 @@ struct Part1Step {
 }
 
 impl Part1Step {
-    fn new() -> Self {
+    fn new(time_left: u8) -> Self {
         Self {
             open: HashSet::new(),
             position: "AA".parse().unwrap(),
             previous: "AA".parse().unwrap(),
             score: 0,
-            time_left: 30,
+            time_left,
         }
     }
 
@@ -111,7 +115,7 @@ impl Part1Step {
         let time_left = self.time_left - 1;
         let mut open = self.open.clone();
 
-        open.insert(valve.flow_rate);
+        open.insert(valve.id);
 
         Self {
             open,
@@ -133,18 +137,21 @@ impl Part1Step {
     }
 
     fn is_closed(&self, valve: &Valve) -> bool {
-        !self.open.contains(&valve.flow_rate)
+        !self.open.contains(&valve.id)
     }
 
-    fn best(&self, sorted_flow_rates: &[u8]) -> usize {
+    /// Upper bound on the score, opening the closed valves of `sorted_valves` in that order.
+    fn best(&self, sorted_valves: &[&Valve]) -> usize {
         let mut time_left = self.time_left as usize;
         let mut score = self.score;
 
-        for &flow_rate in sorted_flow_rates {
-            if self.open.contains(&flow_rate) {
+        for valve in sorted_valves {
+            if self.open.contains(&valve.id) {
                 continue;
             }
 
+            let flow_rate = valve.flow_rate;
+
             time_left -= 1;
             if time_left == 0 {
                 break;
@@ -162,29 +169,160 @@ impl Part1Step {
     }
 }
 
+/// Tunables of the puzzle, see [`crate::params`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Params {
+    /// Minutes until the eruption when working alone.
+    pub part1_minutes: u8,
+    /// Minutes until the eruption when working together with the elephant.
+    pub part2_minutes: u8,
+}
+
+impl Default for Params {
+    fn default() -> Self {
+        Params {
+            part1_minutes: 30,
+            part2_minutes: 26,
+        }
+    }
+}
+
+/// Checks that the tunnels form a graph starting at AA, and that part 2 can keep track of all the
+/// valves with a flow rate.
+pub fn validate(input: &str) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    let mut valves = Vec::<(&str, Valve)>::new();
+
+    for line in input.lines() {
+        if let Some(valve) = check(&mut diagnostics, input, parse::all(line, valve)) {
+            valves.push((line, valve));
+        }
+    }
+
+    let ids = valves
+        .iter()
+        .map(|(_, valve)| valve.id)
+        .collect::<HashSet<_>>();
+
+    if !ids.contains(&"AA".parse().unwrap()) {
+        diagnostics.push(Diagnostic::error("There is no valve AA to start from"));
+    }
+
+    for (line, valve) in &valves {
+        if let Some(tunnel) = valve.tunnels.iter().find(|tunnel| !ids.contains(tunnel)) {
+            diagnostics.push(
+                Diagnostic::error(format!("A tunnel leads to the unknown valve {tunnel:?}"))
+                    .at(input, line),
+            );
+        }
+    }
+
+    let flowing = valves
+        .iter()
+        .filter(|(_, valve)| valve.flow_rate > 0)
+        .count();
+
+    if flowing > MAX_FLOWING {
+        diagnostics.push(Diagnostic::error(format!(
+            "Part 2 keeps track of at most {MAX_FLOWING} valves with a flow rate, found {flowing}"
+        )));
+    }
+
+    diagnostics
+}
+
+/// The valves by their names.
+#[derive(Debug)]
+pub struct Valves(HashMap<ValveId, Valve>);
+
+/// Serializes as the valves ordered by name.
+impl Serialize for Valves {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut valves = self.0.values().collect::<Vec<_>>();
+
+        valves.sort_by_key(|valve| valve.id.0);
+
+        valves.serialize(serializer)
+    }
+}
+
+/// Most valves with a flow rate that part 2 can keep track of, one bit each.
+const MAX_FLOWING: usize = u64::BITS as usize;
+
+/// The valves, which have to include AA to start from, whose tunnels only lead to other valves on
+/// the list, and of which at most [`MAX_FLOWING`] have a flow rate.
+fn valves(input: &str) -> IResult<'_, Valves> {
+    let (rest, lines) = lines(consumed(valve))(input)?;
+    let ids = lines
+        .iter()
+        .map(|(_, valve)| valve.id)
+        .collect::<HashSet<_>>();
+
+    if !ids.contains(&"AA".parse().unwrap()) {
+        return fail(&rest[..0], "a valve AA to start from");
+    }
+
+    if let Some((line, _)) = lines
+        .iter()
+        .find(|(_, valve)| valve.tunnels.iter().any(|tunnel| !ids.contains(tunnel)))
+    {
+        return fail(line, "tunnels to valves on the list");
+    }
+
+    if let Some((line, _)) = lines
+        .iter()
+        .filter(|(_, valve)| valve.flow_rate > 0)
+        .nth(MAX_FLOWING)
+    {
+        return fail(line, "at most 64 valves with a flow rate");
+    }
+
+    Ok((
+        rest,
+        Valves(
+            lines
+                .into_iter()
+                .map(|(_, valve)| (valve.id, valve))
+                .collect(),
+        ),
+    ))
+}
+
+#[aoc_generator(day16)]
+pub fn parse(input: &str) -> Result<Valves, Error> {
+    parse::all(input, valves)
+}
+
 #[aoc(day16, part1)]
-pub fn part1(input: &str) -> Result<usize, ParseIntError> {
-    let valves = input
-        .lines()
-        .map(Valve::from_str)
-        .collect::<Result<Vec<_>, _>>()?
-        .into_iter()
-        .map(|valve| (valve.id, valve))
-        .collect::<HashMap<_, _>>();
+pub fn part1(valves: &Valves) -> usize {
+    part1_with(valves, &Params::default())
+}
+
+pub fn part1_with(valves: &Valves, params: &Params) -> usize {
+    part1_within(valves, params, &Budget::new()).0.answer
+}
+
+/// Like [`part1_with`], along with the work of the search, giving up with the best plan so far when
+/// the `budget` runs out. Steps are pruned by `"best"` when even opening the remaining valves in
+/// the best order can't beat that plan.
+pub fn part1_within(valves: &Valves, params: &Params, budget: &Budget) -> (Outcome<usize>, Stats) {
+    let valves = &valves.0;
 
     let max_open = valves.values().filter(|valve| valve.flow_rate > 0).count();
 
-    let mut sorted_flow_rates = valves
+    let mut sorted_valves = valves
         .values()
-        .map(|valve| valve.flow_rate)
-        .filter(|&flow_rate| flow_rate > 0)
+        .filter(|valve| valve.flow_rate > 0)
         .collect::<Vec<_>>();
 
-    sorted_flow_rates.sort();
-    sorted_flow_rates.reverse();
+    sorted_valves.sort_by_key(|valve| Reverse(valve.flow_rate));
 
     let mut result = 0;
-    let mut queue = vec![Part1Step::new()];
+    let mut queue = vec![Part1Step::new(params.part1_minutes)];
+    let mut stats = Stats::default();
+    let mut pruned_best = 0;
+    let mut meter = budget.meter();
+    let mut is_final = true;
 
     while let Some(step) = queue.pop() {
         if step.time_left == 0 {
@@ -197,10 +335,18 @@ pub fn part1(input: &str) -> Result<usize, ParseIntError> {
             continue;
         }
 
-        if step.best(&sorted_flow_rates) < result {
+        if step.best(&sorted_valves) < result {
+            pruned_best += 1;
             continue;
         }
 
+        if meter.tick(|| result.into()) {
+            is_final = false;
+            break;
+        }
+
+        stats.expanded += 1;
+
         let valve = &valves[&step.position];
 
         if valve.flow_rate > 0 && step.is_closed(valve) {
@@ -212,9 +358,19 @@ pub fn part1(input: &str) -> Result<usize, ParseIntError> {
                 queue.push(step.goto(*tunnel));
             }
         }
+
+        stats.frontier(queue.len());
     }
 
-    Ok(result)
+    stats.prune("best", pruned_best);
+
+    (
+        Outcome {
+            answer: result,
+            is_final,
+        },
+        stats,
+    )
 }
 
 enum Move<'a> {
@@ -222,38 +378,54 @@ enum Move<'a> {
     Open(&'a Valve),
 }
 
+/// Tunnels out of `valve` that don't lead back to a valve in `previous`, since going back and forth
+/// is no better than opening a valve earlier. Dead ends lead back anyway, for lack of a way on.
+fn onward(valve: &Valve, previous: &[ValveId]) -> Vec<ValveId> {
+    let tunnels = valve
+        .tunnels
+        .iter()
+        .copied()
+        .filter(|tunnel| !previous.contains(tunnel))
+        .collect::<Vec<_>>();
+
+    if tunnels.is_empty() {
+        valve.tunnels.clone()
+    } else {
+        tunnels
+    }
+}
+
+/// Valves with a flow rate, highest first, along with the bit that marks them as open.
+type FlowRates = Vec<(u8, u64)>;
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
 struct Part2Step {
-    open: HashSet<u8>,
+    open: u64,
     position: ValveId,
     previous: ValveId,
     elephant_position: ValveId,
     elephant_previous: ValveId,
-    score: usize,
     time_left: u8,
 }
 
 impl Part2Step {
-    fn new() -> Self {
+    fn new(time_left: u8) -> Self {
         Self {
-            open: HashSet::new(),
+            open: 0,
             position: "AA".parse().unwrap(),
             previous: "AA".parse().unwrap(),
             elephant_position: "AA".parse().unwrap(),
             elephant_previous: "AA".parse().unwrap(),
-            score: 0,
-            time_left: 26,
+            time_left,
         }
     }
 
-    fn next(&self, my_move: &Move, elephant_move: &Move) -> Self {
+    fn next(&self, my_move: &Move, elephant_move: &Move, bits: &HashMap<ValveId, u64>) -> Self {
         let mut result = Self {
-            open: self.open.clone(),
-            position: self.position,
             previous: self.position,
-            elephant_position: self.elephant_position,
             elephant_previous: self.elephant_position,
-            score: self.score,
             time_left: self.time_left - 1,
+            ..*self
         };
 
         match my_move {
@@ -261,9 +433,9 @@ impl Part2Step {
                 result.position = *id;
             }
             Move::Open(valve) => {
-                assert!(result.open.insert(valve.flow_rate));
+                assert_eq!(result.open & bits[&valve.id], 0);
+                result.open |= bits[&valve.id];
                 result.position = valve.id;
-                result.score += (valve.flow_rate as usize) * (result.time_left as usize);
             }
         }
 
@@ -272,168 +444,463 @@ impl Part2Step {
                 result.elephant_position = *id;
             }
             Move::Open(valve) => {
-                assert!(result.open.insert(valve.flow_rate));
+                assert_eq!(result.open & bits[&valve.id], 0);
+                result.open |= bits[&valve.id];
                 result.elephant_position = valve.id;
-                result.score += (valve.flow_rate as usize) * (result.time_left as usize);
             }
         }
 
         result
     }
 
-    fn is_closed(&self, valve: &Valve) -> bool {
-        !self.open.contains(&valve.flow_rate)
+    fn is_closed(&self, valve: &Valve, bits: &HashMap<ValveId, u64>) -> bool {
+        bits.get(&valve.id).is_some_and(|bit| self.open & bit == 0)
     }
 
-    fn best(&self, sorted_flow_rates: &[u8]) -> usize {
+    /// Pressure per minute that isn't being released yet.
+    fn closed_flow_rate(&self, flow_rates: &FlowRates) -> usize {
+        flow_rates
+            .iter()
+            .filter(|&(_, bit)| self.open & bit == 0)
+            .map(|&(flow_rate, _)| flow_rate as usize)
+            .sum()
+    }
+
+    /// Upper bound on the pressure that can still be released from here on.
+    fn best(&self, flow_rates: &FlowRates) -> usize {
         let mut time_left = self.time_left;
-        let mut score = self.score;
+        let mut score = 0;
 
-        let sorted_flow_rates = sorted_flow_rates
+        let closed_flow_rates = flow_rates
             .iter()
-            .filter(|flow_rate| !self.open.contains(flow_rate))
+            .filter(|&(_, bit)| self.open & bit == 0)
+            .map(|&(flow_rate, _)| flow_rate)
             .collect::<Vec<_>>();
 
-        for flow_rates in sorted_flow_rates.chunks(2) {
-            time_left -= 1;
-            if time_left == 0 {
+        for flow_rates in closed_flow_rates.chunks(2) {
+            if time_left <= 1 {
                 break;
             }
 
+            time_left -= 1;
+
             for &flow_rate in flow_rates {
-                score += (*flow_rate as usize) * (time_left as usize);
+                score += (flow_rate as usize) * (time_left as usize);
             }
 
-            time_left -= 1;
-            if time_left == 0 {
-                break;
-            }
+            time_left = time_left.saturating_sub(1);
         }
 
         score
     }
-}
 
-impl PriorityQueueItem<Vec<u8>> for Part2Step {
-    fn cost(&self, context: &Vec<u8>) -> usize {
-        usize::MAX - self.best(context)
+    fn successors<'a>(
+        &self,
+        valves: &'a HashMap<ValveId, Valve>,
+        bits: &HashMap<ValveId, u64>,
+    ) -> Vec<Self> {
+        let mut result = Vec::new();
+
+        if self.position == self.elephant_position {
+            let valve = &valves[&self.position];
+
+            let tunnels = onward(valve, &[self.previous, self.elephant_previous]);
+
+            if self.is_closed(valve, bits) {
+                for &tunnel in tunnels.iter() {
+                    result.push(self.next(&Move::Open(valve), &Move::Goto(tunnel), bits));
+                }
+            }
+
+            for (idx, &tunnel_a) in tunnels.iter().enumerate() {
+                for &tunnel_b in tunnels.iter().skip(idx) {
+                    result.push(self.next(&Move::Goto(tunnel_a), &Move::Goto(tunnel_b), bits));
+                }
+            }
+        } else {
+            let valve = &valves[&self.position];
+            let mut moves = Vec::<Move<'a>>::new();
+
+            if self.is_closed(valve, bits) {
+                moves.push(Move::Open(valve));
+            }
+
+            for tunnel in onward(valve, &[self.previous]) {
+                moves.push(Move::Goto(tunnel));
+            }
+
+            let elephant_valve = &valves[&self.elephant_position];
+            let mut elephant_moves = Vec::<Move<'a>>::new();
+
+            if self.is_closed(elephant_valve, bits) {
+                elephant_moves.push(Move::Open(elephant_valve));
+            }
+
+            for tunnel in onward(elephant_valve, &[self.elephant_previous]) {
+                elephant_moves.push(Move::Goto(tunnel));
+            }
+
+            for me in moves.iter() {
+                for elephant in elephant_moves.iter() {
+                    result.push(self.next(me, elephant, bits))
+                }
+            }
+        }
+
+        result
     }
 }
 
+/// Searches for the plan that leaves the least pressure unreleased, where every minute costs the
+/// flow rate of the valves that are still closed during it.
 #[aoc(day16, part2)]
-pub fn part2(input: &str) -> Result<usize, ParseIntError> {
-    let valves = input
-        .lines()
-        .map(Valve::from_str)
-        .collect::<Result<Vec<_>, _>>()?
-        .into_iter()
-        .map(|valve| (valve.id, valve))
-        .collect::<HashMap<_, _>>();
+pub fn part2(valves: &Valves) -> usize {
+    part2_with(valves, &Params::default())
+}
 
-    let max_open = valves.values().filter(|valve| valve.flow_rate > 0).count();
+pub fn part2_with(valves: &Valves, params: &Params) -> usize {
+    part2_within(valves, params, &Budget::new()).0.answer
+}
+
+/// Like [`part2_with`], along with the work of the search, giving up when the `budget` runs out.
+/// The best plan so far is then the one that leaves least pressure unreleased when doing nothing
+/// after reaching any of the steps expanded so far.
+pub fn part2_within(valves: &Valves, params: &Params, budget: &Budget) -> (Outcome<usize>, Stats) {
+    let valves = &valves.0;
 
-    let mut sorted_flow_rates = valves
+    let mut flow_valves = valves
         .values()
-        .map(|valve| valve.flow_rate)
-        .filter(|&flow_rate| flow_rate > 0)
+        .filter(|valve| valve.flow_rate > 0)
         .collect::<Vec<_>>();
 
-    sorted_flow_rates.sort();
-    sorted_flow_rates.reverse();
+    flow_valves.sort_by_key(|valve| Reverse(valve.flow_rate));
 
-    let mut result = 0;
-    let mut queue = PriorityQueue::<_, Part2Step>::new(sorted_flow_rates);
-
-    queue.push(Part2Step::new());
+    let bits = flow_valves
+        .iter()
+        .enumerate()
+        .map(|(idx, valve)| (valve.id, 1u64 << idx))
+        .collect::<HashMap<_, _>>();
 
-    while let Some((cost, step)) = queue.pop() {
-        if (usize::MAX - cost) < result {
-            break;
+    let flow_rates: FlowRates = flow_valves
+        .iter()
+        .map(|valve| (valve.flow_rate, bits[&valve.id]))
+        .collect();
+
+    let start = Part2Step::new(params.part2_minutes);
+    let total = start.closed_flow_rate(&flow_rates) * (start.time_left as usize);
+
+    let mut meter = budget.meter();
+    let mut least_unreleased = total;
+
+    let path = astar_until(
+        start,
+        |step| {
+            let cost = step.closed_flow_rate(&flow_rates);
+
+            step.successors(valves, &bits)
+                .into_iter()
+                .map(move |next| (next, cost))
+        },
+        |step| step.time_left == 0 || step.closed_flow_rate(&flow_rates) == 0,
+        |step| {
+            step.closed_flow_rate(&flow_rates) * (step.time_left as usize) - step.best(&flow_rates)
+        },
+        |step, cost| {
+            let idle = cost + step.closed_flow_rate(&flow_rates) * (step.time_left as usize);
+            least_unreleased = least_unreleased.min(idle);
+
+            meter.tick(|| (total - least_unreleased).into())
+        },
+    );
+
+    match path {
+        Ok(path) => {
+            let path = path.expect("Running out of time always ends the search");
+
+            (
+                Outcome {
+                    answer: total - path.cost,
+                    is_final: true,
+                },
+                path.stats,
+            )
         }
+        Err(stats) => (
+            Outcome {
+                answer: total - least_unreleased,
+                is_final: false,
+            },
+            stats,
+        ),
+    }
+}
 
-        if step.time_left == 0 {
-            result = result.max(step.score);
-            continue;
-        }
+/// Plain searches over every possible plan, to check the pruning of [`Part1Step::best`] and
+/// [`Part2Step::best`] against.
+#[cfg(test)]
+mod reference {
+    use std::collections::HashMap;
 
-        if step.open.len() == max_open {
-            result = result.max(step.score);
-            continue;
-        }
+    use super::{valve, ValveId};
+    use crate::util::parse;
 
-        if step.position == step.elephant_position {
-            let valve = &valves[&step.position];
+    #[derive(Clone, Copy, PartialEq, Eq)]
+    enum Action {
+        Open,
+        Goto(usize),
+    }
 
-            if step.is_closed(valve) {
-                for &tunnel in valve.tunnels.iter() {
-                    if tunnel != step.previous && tunnel != step.elephant_previous {
-                        queue.push(step.next(&Move::Open(valve), &Move::Goto(tunnel)));
-                    }
-                }
+    /// Valves by their index in the input, with valve `idx` open if bit `idx` is set.
+    struct Cave {
+        flow_rates: Vec<usize>,
+        tunnels: Vec<Vec<usize>>,
+        start: usize,
+    }
+
+    impl Cave {
+        fn parse(input: &str) -> Self {
+            let valves = input
+                .lines()
+                .map(|line| parse::all(line, valve).unwrap())
+                .collect::<Vec<_>>();
+
+            let index = |id: &ValveId| valves.iter().position(|valve| valve.id == *id).unwrap();
+
+            Cave {
+                flow_rates: valves
+                    .iter()
+                    .map(|valve| valve.flow_rate as usize)
+                    .collect(),
+                tunnels: valves
+                    .iter()
+                    .map(|valve| valve.tunnels.iter().map(index).collect())
+                    .collect(),
+                start: index(&"AA".parse().unwrap()),
             }
+        }
 
-            for (idx, &tunnel_a) in valve.tunnels.iter().enumerate() {
-                if tunnel_a == step.previous || tunnel_a == step.elephant_previous {
-                    continue;
-                }
+        fn actions(&self, position: usize, open: u64) -> Vec<Action> {
+            let mut actions = self.tunnels[position]
+                .iter()
+                .map(|&next| Action::Goto(next))
+                .collect::<Vec<_>>();
 
-                for &tunnel_b in valve.tunnels.iter().skip(idx) {
-                    if tunnel_b == step.previous || tunnel_b == step.elephant_previous {
-                        continue;
-                    }
+            if self.flow_rates[position] > 0 && open & (1 << position) == 0 {
+                actions.push(Action::Open);
+            }
 
-                    queue.push(step.next(&Move::Goto(tunnel_a), &Move::Goto(tunnel_b)));
-                }
+            actions
+        }
+
+        /// Where an action leads, and the pressure it releases until the eruption.
+        fn apply(&self, position: usize, action: Action, time_left: u8) -> (usize, u64, usize) {
+            match action {
+                Action::Open => (
+                    position,
+                    1 << position,
+                    self.flow_rates[position] * (time_left as usize - 1),
+                ),
+                Action::Goto(next) => (next, 0, 0),
             }
-        } else {
-            let valve = &valves[&step.position];
-            let mut moves = Vec::<Move>::new();
+        }
+    }
 
-            if valve.flow_rate > 0 && step.is_closed(valve) {
-                moves.push(Move::Open(valve));
+    pub fn part1(input: &str, minutes: u8) -> usize {
+        fn released(
+            cave: &Cave,
+            memo: &mut HashMap<(usize, u64, u8), usize>,
+            position: usize,
+            open: u64,
+            time_left: u8,
+        ) -> usize {
+            if time_left == 0 {
+                return 0;
             }
 
-            for &tunnel in valve.tunnels.iter() {
-                if tunnel != step.previous {
-                    moves.push(Move::Goto(tunnel));
-                }
+            if let Some(&result) = memo.get(&(position, open, time_left)) {
+                return result;
             }
 
-            let elephant_valve = &valves[&step.elephant_position];
-            let mut elephant_moves = Vec::<Move>::new();
+            let result = cave
+                .actions(position, open)
+                .into_iter()
+                .map(|action| {
+                    let (next, opened, pressure) = cave.apply(position, action, time_left);
+                    pressure + released(cave, memo, next, open | opened, time_left - 1)
+                })
+                .max()
+                .unwrap_or(0);
+
+            memo.insert((position, open, time_left), result);
+            result
+        }
 
-            if elephant_valve.flow_rate > 0 && step.is_closed(elephant_valve) {
-                elephant_moves.push(Move::Open(elephant_valve));
+        let cave = Cave::parse(input);
+        released(&cave, &mut HashMap::new(), cave.start, 0, minutes)
+    }
+
+    pub fn part2(input: &str, minutes: u8) -> usize {
+        fn released(
+            cave: &Cave,
+            memo: &mut HashMap<(usize, usize, u64, u8), usize>,
+            positions: (usize, usize),
+            open: u64,
+            time_left: u8,
+        ) -> usize {
+            if time_left == 0 {
+                return 0;
             }
 
-            for &tunnel in elephant_valve.tunnels.iter() {
-                if tunnel != step.elephant_previous {
-                    elephant_moves.push(Move::Goto(tunnel));
-                }
+            if let Some(&result) = memo.get(&(positions.0, positions.1, open, time_left)) {
+                return result;
             }
 
-            for me in moves.iter() {
-                for elephant in elephant_moves.iter() {
-                    queue.push(step.next(me, elephant))
+            let mut result = 0;
+
+            for mine in cave.actions(positions.0, open) {
+                for elephants in cave.actions(positions.1, open) {
+                    // A valve can only be opened once.
+                    if positions.0 == positions.1 && mine == Action::Open && mine == elephants {
+                        continue;
+                    }
+
+                    let (me, my_valve, my_pressure) = cave.apply(positions.0, mine, time_left);
+                    let (elephant, elephant_valve, elephant_pressure) =
+                        cave.apply(positions.1, elephants, time_left);
+
+                    result = result.max(
+                        my_pressure
+                            + elephant_pressure
+                            + released(
+                                cave,
+                                memo,
+                                (me, elephant),
+                                open | my_valve | elephant_valve,
+                                time_left - 1,
+                            ),
+                    );
                 }
             }
+
+            memo.insert((positions.0, positions.1, open, time_left), result);
+            result
         }
-    }
 
-    Ok(result)
+        let cave = Cave::parse(input);
+        released(
+            &cave,
+            &mut HashMap::new(),
+            (cave.start, cave.start),
+            0,
+            minutes,
+        )
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    #[test]
-    fn test_case_1() {
-        let result = super::part1("Valve AA has flow rate=0; tunnels lead to valves DD, II, BB\nValve BB has flow rate=13; tunnels lead to valves CC, AA\nValve CC has flow rate=2; tunnels lead to valves DD, BB\nValve DD has flow rate=20; tunnels lead to valves CC, AA, EE\nValve EE has flow rate=3; tunnels lead to valves FF, DD\nValve FF has flow rate=0; tunnels lead to valves EE, GG\nValve GG has flow rate=0; tunnels lead to valves FF, HH\nValve HH has flow rate=22; tunnel leads to valve GG\nValve II has flow rate=0; tunnels lead to valves AA, JJ\nValve JJ has flow rate=21; tunnel leads to valve II");
-        assert_eq!(result, Ok(1651));
+    use std::collections::BTreeSet;
+
+    use itertools::Itertools;
+    use proptest::{collection::vec, prelude::*};
+
+    use super::{reference, Params};
+
+    /// A connected cave of up to 7 valves starting at AA.
+    fn cave() -> impl Strategy<Value = String> {
+        (2..=7usize)
+            .prop_flat_map(|count| {
+                (
+                    // Every valve but AA has a tunnel to an earlier one, so all are reachable.
+                    (1..count).map(|idx| 0..idx).collect::<Vec<_>>(),
+                    vec((0..count, 0..count), 0..count),
+                    vec(1..=25u8, 0..count)
+                        .prop_map(move |mut flow_rates| {
+                            flow_rates.resize(count - 1, 0);
+                            flow_rates
+                        })
+                        .prop_shuffle(),
+                )
+            })
+            .prop_map(|(parents, extra, flow_rates)| {
+                let mut tunnels = vec![BTreeSet::new(); flow_rates.len() + 1];
+
+                for (a, b) in parents
+                    .into_iter()
+                    .enumerate()
+                    .map(|(idx, b)| (idx + 1, b))
+                    .chain(extra)
+                {
+                    if a != b {
+                        tunnels[a].insert(b);
+                        tunnels[b].insert(a);
+                    }
+                }
+
+                let name = |idx: usize| char::from(b'A' + idx as u8).to_string().repeat(2);
+
+                tunnels
+                    .iter()
+                    .enumerate()
+                    .map(|(idx, tunnels)| {
+                        format!(
+                            "Valve {} has flow rate={}; tunnels lead to valves {}",
+                            name(idx),
+                            idx.checked_sub(1).map_or(0, |idx| flow_rates[idx]),
+                            tunnels.iter().map(|&idx| name(idx)).join(", ")
+                        )
+                    })
+                    .join("\n")
+            })
     }
 
     #[test]
-    fn test_case_2() {
-        let result = super::part2("Valve AA has flow rate=0; tunnels lead to valves DD, II, BB\nValve BB has flow rate=13; tunnels lead to valves CC, AA\nValve CC has flow rate=2; tunnels lead to valves DD, BB\nValve DD has flow rate=20; tunnels lead to valves CC, AA, EE\nValve EE has flow rate=3; tunnels lead to valves FF, DD\nValve FF has flow rate=0; tunnels lead to valves EE, GG\nValve GG has flow rate=0; tunnels lead to valves FF, HH\nValve HH has flow rate=22; tunnel leads to valve GG\nValve II has flow rate=0; tunnels lead to valves AA, JJ\nValve JJ has flow rate=21; tunnel leads to valve II");
-        assert_eq!(result, Ok(1707));
+    fn too_many_flowing_valves() {
+        // AA leads to `count` valves with a flow rate, which all lead back.
+        let cave = |count: u8| {
+            let names = (0..count)
+                .map(|idx| {
+                    format!(
+                        "{}{}",
+                        char::from(b'B' + idx / 26),
+                        char::from(b'A' + idx % 26)
+                    )
+                })
+                .collect::<Vec<_>>();
+
+            std::iter::once(format!(
+                "Valve AA has flow rate=0; tunnels lead to valves {}",
+                names.join(", ")
+            ))
+            .chain(
+                names
+                    .iter()
+                    .map(|name| format!("Valve {name} has flow rate=1; tunnel leads to valve AA")),
+            )
+            .join("\n")
+        };
+
+        assert!(super::parse(&cave(64)).is_ok());
+        assert!(super::parse(&cave(65)).is_err());
+    }
+
+    proptest! {
+        #[test]
+        fn part1_matches_reference(input in cave(), minutes in 1..=16u8) {
+            let params = Params { part1_minutes: minutes, ..Params::default() };
+
+            let expected = reference::part1(&input, minutes);
+
+            prop_assert_eq!(super::part1_with(&super::parse(&input).unwrap(), &params), expected);
+        }
+
+        #[test]
+        fn part2_matches_reference(input in cave(), minutes in 1..=12u8) {
+            let params = Params { part2_minutes: minutes, ..Params::default() };
+
+            let expected = reference::part2(&input, minutes);
+
+            prop_assert_eq!(super::part2_with(&super::parse(&input).unwrap(), &params), expected);
+        }
     }
 }
@@ -1,66 +1,72 @@
-use std::{fmt::Debug, num::ParseIntError};
-
-use aoc_runner_derive::aoc;
+use std::fmt::Debug;
+
+use aoc_runner_derive::{aoc, aoc_generator};
+use serde::{Serialize, Serializer};
+
+use crate::{
+    diagnostic::Diagnostic,
+    util::{
+        bits::{BitBoard, BitSet},
+        cycle::{self, Cycle, History},
+        grid::Grid,
+        parse::{self, char_of, fail, line_of, IResult},
+    },
+    visualize::{Draw, Recorder, Tile},
+    Error,
+};
 
 const WIDTH: usize = 7;
 
-const fn mask(value: usize) -> u8 {
-    match value {
-        0 => 0b01000000,
-        1 => 0b00100000,
-        2 => 0b00010000,
-        3 => 0b00001000,
-        4 => 0b00000100,
-        5 => 0b00000010,
-        6 => 0b00000001,
-        _ => panic!("Out of bounds"),
-    }
-}
-
+#[derive(Debug, Clone, Copy)]
 #[repr(u8)]
 enum Steam {
     Left = b'<',
     Right = b'>',
 }
 
-impl From<&u8> for Steam {
-    fn from(value: &u8) -> Self {
-        match value {
-            b'<' => Steam::Left,
-            b'>' => Steam::Right,
-            _ => panic!("Invalid input {value}"),
-        }
-    }
+fn steam(input: &str) -> IResult<'_, Steam> {
+    char_of("a jet of steam (< or >)", |c| match c {
+        '<' => Some(Steam::Left),
+        '>' => Some(Steam::Right),
+        _ => None,
+    })(input)
 }
 
+/// The chamber, with row `0` at the floor and new rows added on top.
 struct Board {
-    data: Vec<u8>,
+    rocks: BitBoard,
 }
 
 impl Board {
     fn new() -> Self {
-        Self { data: vec![] }
+        Self {
+            rocks: BitBoard::new(WIDTH, 0),
+        }
     }
 
     fn max_y(&self) -> usize {
-        self.data.len()
+        self.rocks.height()
     }
 
-    fn is_free(&self, y: usize, mask: u8) -> bool {
-        let Some(row) = self.data.get(y) else {
-            return true;
-        };
+    /// The rocks in row `y`, with the left wall next to the lowest bit.
+    fn row(&self, y: usize) -> u64 {
+        if y < self.max_y() {
+            self.rocks.row(y)[0]
+        } else {
+            0
+        }
+    }
 
-        (row & mask) == 0
+    fn is_free(&self, y: usize, mask: u64) -> bool {
+        self.row(y) & mask == 0
     }
 
-    fn occupy(&mut self, y: usize, mask: u8) {
-        if self.data.len() <= y {
-            self.data.resize(y, 0);
-            self.data.push(mask);
-        } else {
-            self.data[y] |= mask;
+    fn occupy(&mut self, y: usize, mask: u64) {
+        while self.max_y() <= y {
+            self.rocks.push_row();
         }
+
+        self.rocks.row_mut(y)[0] |= mask;
     }
 }
 
@@ -68,24 +74,40 @@ impl Debug for Board {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         writeln!(f, "+-------+")?;
 
-        for y in (0..=self.max_y()).rev() {
-            writeln!(
-                f,
-                "|{}{}{}{}{}{}{}|",
-                if self.is_free(y, mask(0)) { '.' } else { '#' },
-                if self.is_free(y, mask(1)) { '.' } else { '#' },
-                if self.is_free(y, mask(2)) { '.' } else { '#' },
-                if self.is_free(y, mask(3)) { '.' } else { '#' },
-                if self.is_free(y, mask(4)) { '.' } else { '#' },
-                if self.is_free(y, mask(5)) { '.' } else { '#' },
-                if self.is_free(y, mask(6)) { '.' } else { '#' },
-            )?;
+        for y in (0..self.max_y()).rev() {
+            let row = (0..WIDTH)
+                .map(|x| if self.rocks.get((x, y)) { '#' } else { '.' })
+                .collect::<String>();
+
+            writeln!(f, "|{row}|")?;
         }
 
         writeln!(f, "+-------+")
     }
 }
 
+const ROCK: Tile = Tile::new('#', [170, 160, 150]);
+const AIR: Tile = Tile::new('.', [0, 0, 0]);
+const WALL: Tile = Tile::new('|', [70, 70, 70]);
+const FLOOR: Tile = Tile::new('-', [70, 70, 70]);
+
+impl Draw for Board {
+    /// Draws the chamber like its [`Debug`] output, with the floor at the bottom.
+    fn draw(&self) -> Grid<Tile> {
+        let mut grid = Grid::new(WIDTH + 2, self.max_y() + 1, WALL);
+
+        for y in 0..self.max_y() {
+            for x in 0..WIDTH {
+                grid[(x + 1, self.max_y() - 1 - y)] =
+                    if self.rocks.get((x, y)) { ROCK } else { AIR };
+            }
+        }
+
+        grid.row_mut(self.max_y()).for_each(|tile| *tile = FLOOR);
+        grid
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
 enum Shape {
     Horizont,
@@ -96,234 +118,306 @@ enum Shape {
 }
 
 impl Shape {
-    fn all() -> Vec<Shape> {
-        vec![
-            Shape::Horizont,
-            Shape::Plus,
-            Shape::Corner,
-            Shape::Line,
-            Shape::Cube,
-        ]
-    }
-
-    fn can_be_placed(&self, board: &Board, x: usize, y: usize) -> bool {
+    /// The shapes in the order they fall.
+    const ALL: [Shape; 5] = [
+        Shape::Horizont,
+        Shape::Plus,
+        Shape::Corner,
+        Shape::Line,
+        Shape::Cube,
+    ];
+
+    /// The rows of the shape from the bottom up, with its left edge in the lowest bit.
+    fn rows(self) -> &'static [u64] {
         match self {
-            Shape::Horizont => {
-                if x + 4 > WIDTH {
-                    false
-                } else {
-                    board.is_free(y, 0b01111000 >> x)
-                }
-            }
-            Shape::Plus => {
-                if x + 3 > WIDTH {
-                    false
-                } else {
-                    board.is_free(y, 0b00100000 >> x)
-                        && board.is_free(y + 1, 0b01110000 >> x)
-                        && board.is_free(y + 2, 0b00100000 >> x)
-                }
-            }
-            Shape::Corner => {
-                if x + 3 > WIDTH {
-                    false
-                } else {
-                    board.is_free(y, 0b01110000 >> x)
-                        && board.is_free(y + 1, 0b00010000 >> x)
-                        && board.is_free(y + 2, 0b00010000 >> x)
-                }
-            }
-            Shape::Line => {
-                if x + 1 > WIDTH {
-                    false
-                } else {
-                    board.is_free(y, 0b01000000 >> x)
-                        && board.is_free(y + 1, 0b01000000 >> x)
-                        && board.is_free(y + 2, 0b01000000 >> x)
-                        && board.is_free(y + 3, 0b01000000 >> x)
-                }
-            }
-            Shape::Cube => {
-                if x + 2 > WIDTH {
-                    false
-                } else {
-                    board.is_free(y, 0b01100000 >> x) && board.is_free(y + 1, 0b01100000 >> x)
-                }
-            }
+            Shape::Horizont => &[0b1111],
+            Shape::Plus => &[0b010, 0b111, 0b010],
+            Shape::Corner => &[0b111, 0b100, 0b100],
+            Shape::Line => &[0b1, 0b1, 0b1, 0b1],
+            Shape::Cube => &[0b11, 0b11],
         }
     }
 
-    fn place(&self, board: &mut Board, x: usize, y: usize) {
-        match self {
-            Shape::Horizont => {
-                board.occupy(y, 0b01111000 >> x);
-            }
-            Shape::Plus => {
-                board.occupy(y, 0b00100000 >> x);
-                board.occupy(y + 1, 0b01110000 >> x);
-                board.occupy(y + 2, 0b00100000 >> x);
-            }
-            Shape::Corner => {
-                board.occupy(y, 0b01110000 >> x);
-                board.occupy(y + 1, 0b00010000 >> x);
-                board.occupy(y + 2, 0b00010000 >> x);
-            }
-            Shape::Line => {
-                board.occupy(y, 0b01000000 >> x);
-                board.occupy(y + 1, 0b01000000 >> x);
-                board.occupy(y + 2, 0b01000000 >> x);
-                board.occupy(y + 3, 0b01000000 >> x);
-            }
-            Shape::Cube => {
-                board.occupy(y, 0b01100000 >> x);
-                board.occupy(y + 1, 0b01100000 >> x);
-            }
+    fn width(self) -> usize {
+        self.rows()
+            .iter()
+            .map(|row| (u64::BITS - row.leading_zeros()) as usize)
+            .max()
+            .unwrap()
+    }
+
+    fn can_be_placed(self, board: &Board, x: usize, y: usize) -> bool {
+        x + self.width() <= WIDTH
+            && self
+                .rows()
+                .iter()
+                .enumerate()
+                .all(|(dy, row)| board.is_free(y + dy, row << x))
+    }
+
+    fn place(self, board: &mut Board, x: usize, y: usize) {
+        for (dy, row) in self.rows().iter().enumerate() {
+            board.occupy(y + dy, row << x);
         }
     }
 }
 
-fn height_after_n_shapes(input: &str, n_shapes: usize) -> usize {
-    let shapes = Shape::all().into_iter().cycle();
-    let mut steam = input.trim().as_bytes().iter().map(Steam::from).cycle();
+/// How many rows from the top of the tower [`detect_cycle`] compares at first.
+const MIN_WINDOW: usize = 64;
+
+/// The chamber along with which rock and which jet of steam come next.
+struct Chamber<'a> {
+    board: Board,
+    steam: &'a [Steam],
+    /// How many rocks came to rest.
+    rocks: usize,
+    /// The index of the next jet of steam.
+    jet: usize,
+    /// How many rows below the top of the tower the last rock looked into, down to the one it came
+    /// to rest on.
+    fall: usize,
+}
 
-    let mut board = Board::new();
+impl<'a> Chamber<'a> {
+    fn new(steam: &'a [Steam]) -> Self {
+        Chamber {
+            board: Board::new(),
+            steam,
+            rocks: 0,
+            jet: 0,
+            fall: 0,
+        }
+    }
+
+    /// Drops the next rock until it comes to rest.
+    fn drop_rock(&mut self) {
+        let shape = Shape::ALL[self.rocks % Shape::ALL.len()];
+        let board = &mut self.board;
+        let top = board.max_y();
 
-    for shape in shapes.take(n_shapes) {
         let mut x = 2usize;
-        let mut y = board.max_y() + 3;
+        let mut y = top + 3;
+
+        loop {
+            let steam = self.steam[self.jet];
+            self.jet = (self.jet + 1) % self.steam.len();
 
-        for steam in steam.by_ref() {
             match steam {
                 Steam::Left => {
-                    if x > 0 && shape.can_be_placed(&board, x - 1, y) {
+                    if x > 0 && shape.can_be_placed(board, x - 1, y) {
                         x -= 1;
                     }
                 }
                 Steam::Right => {
-                    if shape.can_be_placed(&board, x + 1, y) {
+                    if shape.can_be_placed(board, x + 1, y) {
                         x += 1;
                     }
                 }
             }
 
-            if y > 0 && shape.can_be_placed(&board, x, y - 1) {
+            if y > 0 && shape.can_be_placed(board, x, y - 1) {
                 y -= 1;
             } else {
-                shape.place(&mut board, x, y);
+                shape.place(board, x, y);
+                // The rock ends up no higher than the top, and the floor counts as a row below it.
+                self.fall = top + 1 - y;
                 break;
             }
         }
+
+        self.rocks += 1;
     }
 
-    board.max_y()
-}
+    /// What the following rocks depend on, as long as they don't look deeper than `window` rows:
+    /// the next shape, the next jet and the top of the tower.
+    fn key(&self, window: usize) -> (usize, usize, BitSet) {
+        let height = self.board.max_y();
+        let top = (0..window)
+            .flat_map(|depth| {
+                // The floor is as solid as rock, and so is everything below it.
+                let row = height
+                    .checked_sub(depth + 1)
+                    .map_or(u64::MAX, |y| self.board.row(y));
+
+                (0..WIDTH)
+                    .filter(move |x| row >> x & 1 != 0)
+                    .map(move |x| depth * WIDTH + x)
+            })
+            .collect();
+
+        (self.rocks % Shape::ALL.len(), self.jet, top)
+    }
 
-struct CycleInfo {
-    cycle_count: usize,
-    cycle_height: usize,
-    first_cycle_shapes: usize,
-    last_cycle_shapes: usize,
+    fn height(&self) -> usize {
+        self.board.max_y()
+    }
 }
 
-fn get_cycle_info(input: &str, n_shapes: usize) -> CycleInfo {
-    let shapes = Shape::all().into_iter().cycle();
-    let mut steam = input
-        .trim()
-        .as_bytes()
-        .iter()
-        .map(Steam::from)
-        .cycle()
-        .enumerate();
+/// Drops `n_shapes` rocks into an empty chamber, calling `on_rock` after each one comes to rest.
+fn simulate(steam: &[Steam], n_shapes: usize, mut on_rock: impl FnMut(usize, &Board)) -> Board {
+    let mut chamber = Chamber::new(steam);
 
-    let mut board = Board::new();
-
-    let mut first_cycle_height = Option::<usize>::None;
-    let mut first_cycle_shapes = Option::<usize>::None;
+    while chamber.rocks < n_shapes {
+        chamber.drop_rock();
+        on_rock(chamber.rocks, &chamber.board);
+    }
 
-    for (shape_idx, shape) in shapes.enumerate() {
-        let mut x = 2usize;
-        let mut y = board.max_y() + 3;
+    chamber.board
+}
 
-        for (steam_idx, steam) in steam.by_ref() {
-            if steam_idx == input.len() {
-                first_cycle_height = Some(board.max_y());
-                first_cycle_shapes = Some(shape_idx);
-            }
+fn height_after_n_shapes(steam: &[Steam], n_shapes: usize) -> usize {
+    simulate(steam, n_shapes, |_, _| ()).max_y()
+}
 
-            if steam_idx == input.len() * 2 {
-                let first_cycle_height = first_cycle_height.unwrap();
-                let first_cycle_shapes = first_cycle_shapes.unwrap();
-
-                let cycle_height = board.max_y() - first_cycle_height;
-                let cycle_count =
-                    (n_shapes - first_cycle_shapes) / (shape_idx - first_cycle_shapes);
-                let last_cycle_shapes = n_shapes
-                    - (first_cycle_shapes + ((shape_idx - first_cycle_shapes) * cycle_count));
-
-                return CycleInfo {
-                    cycle_count,
-                    cycle_height,
-                    first_cycle_shapes,
-                    last_cycle_shapes,
-                };
+/// Drops rocks until the chamber repeats itself, or until `n_shapes` of them came to rest.
+///
+/// Chambers are told apart by the top rows of their towers. Once none of the rocks of a cycle
+/// looked any deeper than that, the rocks of the next cycle find the same rows and do the same, so
+/// the cycle goes on forever. Otherwise more rows are compared, until that holds.
+fn detect_cycle(jets: &Jets, n_shapes: usize) -> History<usize> {
+    let mut window = MIN_WINDOW;
+
+    loop {
+        let mut falls = Vec::new();
+        let history = cycle::detect(
+            Chamber::new(&jets.steam),
+            n_shapes,
+            |chamber| {
+                chamber.drop_rock();
+                falls.push(chamber.fall);
+            },
+            |chamber| chamber.key(window),
+            Chamber::height,
+        );
+
+        match history.cycle() {
+            Some(Cycle { prefix, period })
+                if falls[prefix..prefix + period]
+                    .iter()
+                    .any(|&fall| fall > window) =>
+            {
+                window *= 2
             }
+            _ => return history,
+        }
+    }
+}
 
-            match steam {
-                Steam::Left => {
-                    if x > 0 && shape.can_be_placed(&board, x - 1, y) {
-                        x -= 1;
-                    }
-                }
-                Steam::Right => {
-                    if shape.can_be_placed(&board, x + 1, y) {
-                        x += 1;
-                    }
-                }
-            }
+/// Tunables of the puzzle, see [`crate::params`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Params {
+    pub part1_shapes: usize,
+    pub part2_shapes: usize,
+}
 
-            if y > 0 && shape.can_be_placed(&board, x, y - 1) {
-                y -= 1;
-            } else {
-                shape.place(&mut board, x, y);
-                break;
-            }
+impl Default for Params {
+    fn default() -> Self {
+        Params {
+            part1_shapes: 2022,
+            part2_shapes: 1_000_000_000_000,
         }
     }
+}
+
+/// Checks that there are jets to push the rocks around.
+pub fn validate(input: &str) -> Vec<Diagnostic> {
+    match parse(input) {
+        Ok(_) => Vec::new(),
+        Err(err) => vec![Diagnostic::from_error(err, input)],
+    }
+}
 
-    unreachable!("The loop should never terminate");
+/// The jets of steam that push the rocks around.
+#[derive(Debug)]
+pub struct Jets {
+    steam: Vec<Steam>,
+}
+
+/// Serializes as the jets, `<` or `>` each.
+impl Serialize for Jets {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(
+            &self
+                .steam
+                .iter()
+                .map(|&s| s as u8 as char)
+                .collect::<String>(),
+        )
+    }
+}
+
+/// The jets on the first line, of which there has to be at least one for the rocks to fall.
+fn jets(input: &str) -> IResult<'_, Jets> {
+    let (rest, steam) = line_of(steam)(input)?;
+
+    if steam.is_empty() {
+        return fail(&input[..0], "a jet of steam, < or >");
+    }
+
+    Ok((rest, Jets { steam }))
+}
+
+#[aoc_generator(day17)]
+pub fn parse(input: &str) -> Result<Jets, Error> {
+    parse::all(input, jets)
 }
 
 #[aoc(day17, part1)]
-pub fn part1(input: &str) -> Result<usize, ParseIntError> {
-    Ok(height_after_n_shapes(input, 2022))
+pub fn part1(jets: &Jets) -> usize {
+    part1_with(jets, &Params::default())
+}
+
+pub fn part1_with(jets: &Jets, params: &Params) -> usize {
+    height_after_n_shapes(&jets.steam, params.part1_shapes)
 }
 
 #[aoc(day17, part2)]
-pub fn part2(input: &str) -> Result<usize, ParseIntError> {
-    let CycleInfo {
-        cycle_count,
-        cycle_height,
-        first_cycle_shapes,
-        last_cycle_shapes,
-    } = get_cycle_info(input, 1_000_000_000_000);
-
-    Ok(
-        height_after_n_shapes(input, first_cycle_shapes + last_cycle_shapes)
-            + (cycle_height * cycle_count),
-    )
+pub fn part2(jets: &Jets) -> usize {
+    part2_with(jets, &Params::default())
+}
+
+pub fn part2_with(jets: &Jets, params: &Params) -> usize {
+    detect_cycle(jets, params.part2_shapes)
+        .after(params.part2_shapes)
+        .unwrap()
+}
+
+/// Records the chamber every time a rock comes to rest, for as many rocks as the given part drops
+/// one by one. Part 2 skips most of its rocks by repeating cycles, which aren't recorded.
+pub fn visualize(jets: &Jets, part: u8, params: &Params, recorder: &mut Recorder) {
+    let n_shapes = if part == 1 {
+        params.part1_shapes
+    } else {
+        detect_cycle(jets, params.part2_shapes)
+            .equivalent(params.part2_shapes)
+            .unwrap()
+    };
+
+    recorder.record(0, &Board::new());
+
+    let board = simulate(&jets.steam, n_shapes, |rocks, board| {
+        recorder.record(rocks, board)
+    });
+
+    recorder.record_last(n_shapes, &board);
 }
 
 #[cfg(test)]
 mod tests {
-    #[test]
-    fn test_case_1() {
-        let result = super::part1(">>><<><>><<<>><>>><<<>>><<<><<<>><>><<>>");
-        assert_eq!(result, Ok(3068));
-    }
+    use proptest::prelude::*;
+
+    use super::{height_after_n_shapes, Params};
 
-    #[test]
-    fn test_case_2() {
-        let result = super::part2(">>><<><>><<<>><>>><<<>>><<<><<<>><>><<>>");
-        assert_eq!(result, Ok(1514285714288));
+    proptest! {
+        /// Dropping every single rock is the reference for skipping cycles of them.
+        #[test]
+        fn part2_matches_simulation(input in "[<>]{1,40}", n_shapes in 0..5000usize) {
+            let params = Params { part2_shapes: n_shapes, ..Params::default() };
+            let jets = super::parse(&input).unwrap();
+
+            let expected = height_after_n_shapes(&jets.steam, n_shapes);
+
+            prop_assert_eq!(super::part2_with(&jets, &params), expected);
+        }
     }
 }
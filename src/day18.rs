@@ -1,152 +1,144 @@
-use std::{collections::BTreeSet, num::ParseIntError, str::FromStr};
-
-use aoc_runner_derive::aoc;
+use aoc_runner_derive::{aoc, aoc_generator};
+use nom::{
+    combinator::{consumed, map},
+    sequence::tuple,
+};
+use serde::{Serialize, Serializer};
+
+use crate::{
+    diagnostic::{check, Diagnostic},
+    util::{
+        bits::BitVolume,
+        geom::Point3,
+        parse::{self, lines, number, tag, IResult},
+    },
+    Error,
+};
+
+/// How many cubes the scanned volume spans along every axis.
+const SIZE: usize = 32;
+
+/// Largest coordinate that still leaves a layer of air between the cubes and the edge of the
+/// scanned volume, once offset by three.
+const MAX_COORDINATE: u16 = 26;
+
+/// How far every coordinate is moved into the scanned volume, to leave room for air around the
+/// cubes.
+const OFFSET: Point3 = Point3::new(3, 3, 3);
+
+fn cube(input: &str) -> IResult<'_, Point3> {
+    let coordinate = |input| map(number::<u16>, i32::from)(input);
+
+    map(
+        tuple((coordinate, tag(","), coordinate, tag(","), coordinate)),
+        |(x, _, y, _, z)| Point3::new(x, y, z),
+    )(input)
+}
 
-#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
-struct Pos(u16);
+/// Checks that every coordinate fits into the scanned volume.
+pub fn validate(input: &str) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
 
-impl Pos {
-    fn new(x: u16, y: u16, z: u16) -> Pos {
-        Pos(x | y << 5 | z << 10)
-    }
+    for line in input.lines() {
+        if check(&mut diagnostics, input, parse::all(line, cube)).is_none() {
+            continue;
+        }
 
-    fn neighbours(&self) -> Vec<Pos> {
-        vec![
-            Pos(self.0 - 1),
-            Pos(self.0 + 1),
-            Pos(self.0 - (1 << 5)),
-            Pos(self.0 + (1 << 5)),
-            Pos(self.0 - (1 << 10)),
-            Pos(self.0 + (1 << 10)),
-        ]
+        for coordinate in line.split(',') {
+            if coordinate
+                .parse::<u16>()
+                .is_ok_and(|value| value > MAX_COORDINATE)
+            {
+                diagnostics.push(
+                    Diagnostic::error(format!(
+                        "Coordinate {coordinate} doesn't fit into the scanned volume, the most \
+                         that can be handled is {MAX_COORDINATE}"
+                    ))
+                    .at(input, coordinate),
+                );
+            }
+        }
     }
 
-    fn is_edge(&self) -> bool {
-        (self.0 & 0b000000000011111) == 0
-            || (self.0 & 0b000000000011111) == 31
-            || (self.0 & 0b000001111100000) >> 5 == 0
-            || (self.0 & 0b000001111100000) >> 5 == 31
-            || (self.0 & 0b111110000000000) >> 10 == 0
-            || (self.0 & 0b111110000000000) >> 10 == 31
-    }
+    diagnostics
 }
 
-impl FromStr for Pos {
-    type Err = ParseIntError;
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut parts = s.split(',').map(|s| s.parse::<u16>());
+/// The cubes of lava, offset by [`OFFSET`].
+#[derive(Debug)]
+pub struct Droplet(BitVolume);
 
-        // Offset a bit to avoid dealing with 0/1 values in rest of code
-        Ok(Pos::new(
-            parts.next().unwrap()? + 3,
-            parts.next().unwrap()? + 3,
-            parts.next().unwrap()? + 3,
-        ))
+/// Serializes as `[x, y, z]` of every cube, as they were in the input, ordered by `z`, `y` and
+/// then `x`.
+impl Serialize for Droplet {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.0.iter().map(|cube| cube - OFFSET))
     }
 }
 
-#[aoc(day18, part1)]
-pub fn part1(input: &str) -> Result<usize, ParseIntError> {
-    let cubes = input
-        .lines()
-        .map(|s| s.parse::<Pos>())
-        .collect::<Result<BTreeSet<_>, _>>()?;
+#[aoc_generator(day18)]
+pub fn parse(input: &str) -> Result<Droplet, Error> {
+    let mut cubes = BitVolume::new(SIZE, SIZE, SIZE);
+
+    for (line, cube) in parse::all(input, lines(consumed(cube)))? {
+        if [cube.x, cube.y, cube.z]
+            .iter()
+            .any(|&coordinate| coordinate > MAX_COORDINATE.into())
+        {
+            return Err(Error::parse(
+                line,
+                format!("coordinates of at most {MAX_COORDINATE}"),
+            ));
+        }
 
-    let mut result = 0;
+        cubes.set(cube + OFFSET, true);
+    }
 
-    for cube in cubes.iter() {
-        result += 6;
+    Ok(Droplet(cubes))
+}
 
-        for neighbour in cube.neighbours() {
-            if cubes.contains(&neighbour) {
-                result -= 1;
-            }
-        }
-    }
+#[aoc(day18, part1)]
+pub fn part1(droplet: &Droplet) -> usize {
+    let cubes = &droplet.0;
 
-    Ok(result)
+    cubes
+        .iter()
+        .map(|cube| 6 - cubes.count_neighbours(cube))
+        .sum()
 }
 
 #[aoc(day18, part2)]
-pub fn part2(input: &str) -> Result<usize, ParseIntError> {
-    let cubes = input
-        .lines()
-        .map(|s| s.parse::<Pos>())
-        .collect::<Result<BTreeSet<_>, _>>()?;
+pub fn part2(droplet: &Droplet) -> usize {
+    let cubes = &droplet.0;
 
-    let mut outside = BTreeSet::<Pos>::new();
-    let mut queue = Vec::<Pos>::new();
+    let mut outside = BitVolume::new(SIZE, SIZE, SIZE);
+    let mut queue = vec![Point3::ORIGIN];
 
-    outside.insert(Pos::new(1, 1, 1));
-    queue.push(Pos::new(1, 1, 1));
+    outside.set(Point3::ORIGIN, true);
 
     while let Some(pos) = queue.pop() {
-        for neighbour in pos.neighbours() {
-            if outside.contains(&neighbour) {
+        for neighbour in pos.neighbours6() {
+            if !outside.in_bounds(neighbour) || outside.get(neighbour) || cubes.get(neighbour) {
                 continue;
             }
 
-            if cubes.contains(&neighbour) {
-                continue;
-            }
-
-            outside.insert(neighbour);
-
-            if !neighbour.is_edge() {
-                queue.push(neighbour);
-            }
-        }
-    }
-
-    let mut result = 0;
-
-    for cube in cubes.iter() {
-        for neighbour in cube.neighbours() {
-            if outside.contains(&neighbour) {
-                result += 1;
-            }
+            outside.set(neighbour, true);
+            queue.push(neighbour);
         }
     }
 
-    Ok(result)
+    cubes
+        .iter()
+        .map(|cube| outside.count_neighbours(cube))
+        .sum()
 }
 
 #[cfg(test)]
 mod tests {
     #[test]
     fn test_case_1() {
-        let result = super::part1("1,1,1");
-        assert_eq!(result, Ok(6));
-    }
-
-    #[test]
-    fn test_case_2() {
-        let result = super::part1("1,1,1\n2,1,1");
-        assert_eq!(result, Ok(10));
-    }
+        let diagnostics = super::validate("1,1,1\n2,27,1");
 
-    #[test]
-    fn test_case_3() {
-        let result = super::part1("2,2,2\n1,2,2\n3,2,2\n2,1,2\n2,3,2\n2,2,1\n2,2,3\n2,2,4\n2,2,6\n1,2,5\n3,2,5\n2,1,5\n2,3,5\n");
-        assert_eq!(result, Ok(64));
-    }
-
-    #[test]
-    fn test_case_4() {
-        let result = super::part2("1,1,1");
-        assert_eq!(result, Ok(6));
-    }
-
-    #[test]
-    fn test_case_5() {
-        let result = super::part2("1,1,1\n2,1,1");
-        assert_eq!(result, Ok(10));
-    }
-
-    #[test]
-    fn test_case_6() {
-        let result = super::part2("2,2,2\n1,2,2\n3,2,2\n2,1,2\n2,3,2\n2,2,1\n2,2,3\n2,2,4\n2,2,6\n1,2,5\n3,2,5\n2,1,5\n2,3,5\n");
-        assert_eq!(result, Ok(58));
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!((diagnostics[0].line, diagnostics[0].column), (2, 3));
     }
 }
@@ -1,16 +1,22 @@
-use std::num::ParseIntError;
+use std::sync::atomic::{AtomicUsize, Ordering};
 
-use aoc_runner_derive::aoc;
-use nom::{
-    bytes::complete::tag, character::complete::digit1, combinator::map_res, sequence::tuple,
-};
+use aoc_runner_derive::{aoc, aoc_generator};
+use nom::sequence::tuple;
 use rayon::prelude::*;
+use serde::Serialize;
+
+use crate::{
+    budget::CHECK_EVERY,
+    diagnostic::{check, Diagnostic},
+    util::parse::{self, lines, number, tag, IResult},
+    Budget, Error, Outcome, Stats,
+};
 
 fn geode_robot_every_minute(time_left: u8) -> usize {
     (time_left as usize) * (time_left as usize - 1) / 2
 }
 
-#[derive(Debug)]
+#[derive(Debug, Serialize)]
 struct Cost {
     ore: usize,
     clay: usize,
@@ -25,7 +31,7 @@ enum Resource {
     Geode,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Serialize)]
 struct Blueprint {
     id: usize,
     ore_robot_cost: Cost,
@@ -34,29 +40,28 @@ struct Blueprint {
     geode_robot_cost: Cost,
 }
 
-impl Blueprint {
-    fn parse(s: &str) -> Result<Self, nom::Err<nom::error::Error<&str>>> {
-        let (rest, data) = tuple((
-            tag("Blueprint "),
-            map_res(digit1, str::parse::<usize>),
-            tag(": Each ore robot costs "),
-            map_res(digit1, str::parse::<usize>),
-            tag(" ore. Each clay robot costs "),
-            map_res(digit1, str::parse::<usize>),
-            tag(" ore. Each obsidian robot costs "),
-            map_res(digit1, str::parse::<usize>),
-            tag(" ore and "),
-            map_res(digit1, str::parse::<usize>),
-            tag(" clay. Each geode robot costs "),
-            map_res(digit1, str::parse::<usize>),
-            tag(" ore and "),
-            map_res(digit1, str::parse::<usize>),
-            tag(" obsidian."),
-        ))(s)?;
-
-        assert_eq!(rest, "");
-
-        Ok(Self {
+fn blueprint(input: &str) -> IResult<'_, Blueprint> {
+    let (rest, data) = tuple((
+        tag("Blueprint "),
+        number,
+        tag(": Each ore robot costs "),
+        number,
+        tag(" ore. Each clay robot costs "),
+        number,
+        tag(" ore. Each obsidian robot costs "),
+        number,
+        tag(" ore and "),
+        number,
+        tag(" clay. Each geode robot costs "),
+        number,
+        tag(" ore and "),
+        number,
+        tag(" obsidian."),
+    ))(input)?;
+
+    Ok((
+        rest,
+        Blueprint {
             id: data.1,
             ore_robot_cost: Cost {
                 ore: data.3,
@@ -78,12 +83,34 @@ impl Blueprint {
                 clay: 0,
                 obsidian: data.13,
             },
-        })
-    }
+        },
+    ))
+}
 
-    fn max_geodes(&self, time: u8) -> usize {
+impl Blueprint {
+    /// Finds the most geodes that can be cracked in `time`, along with the work of the search.
+    ///
+    /// Steps are pruned by `"best"` when even a new geode robot every minute can't beat the best
+    /// result so far, and by `"geode robot every minute"` when that is already affordable, so that
+    /// the result follows without searching further.
+    ///
+    /// `stop` is asked with the best result so far and the number of steps expanded since it was
+    /// last asked, whenever that result improves and otherwise once every [`CHECK_EVERY`] steps,
+    /// and ends the search with that result when it returns true.
+    fn search(
+        &self,
+        time: u8,
+        mut stop: impl FnMut(usize, usize) -> bool,
+    ) -> (Outcome<usize>, Stats) {
         let mut result = 0usize;
         let mut queue = vec![Step::new(time)];
+        let mut expanded = 0;
+        let mut peak_frontier = 0;
+        let mut pruned_best = 0;
+        let mut pruned_every_minute = 0;
+        let mut reported = 0;
+        let mut unreported = 0;
+        let mut is_final = true;
 
         while let Some(step) = queue.pop() {
             if step.time_left == 0 {
@@ -101,13 +128,27 @@ impl Blueprint {
                 };
 
                 result = result.max(score);
+                pruned_every_minute += 1;
                 continue;
             }
 
             if step.best(&self.geode_robot_cost) < result {
+                pruned_best += 1;
                 continue;
             }
 
+            unreported += 1;
+
+            if result != reported || unreported == CHECK_EVERY {
+                reported = result;
+
+                if check_in(&mut stop, result, std::mem::take(&mut unreported)) {
+                    is_final = false;
+                    break;
+                }
+            }
+
+            expanded += 1;
             queue.push(step.buy_nothing());
 
             if step.can_afford(&self.geode_robot_cost) {
@@ -125,12 +166,37 @@ impl Blueprint {
             if step.can_afford(&self.ore_robot_cost) {
                 queue.push(step.buy_robot(Resource::Ore, &self.ore_robot_cost));
             }
+
+            peak_frontier = peak_frontier.max(queue.len());
         }
 
-        result
+        let mut stats = Stats {
+            expanded,
+            peak_frontier,
+            ..Stats::default()
+        };
+
+        stats.prune("best", pruned_best);
+        stats.prune("geode robot every minute", pruned_every_minute);
+
+        (
+            Outcome {
+                answer: result,
+                is_final,
+            },
+            stats,
+        )
     }
 }
 
+/// Asks `stop` in a function of its own, which keeps the rare check out of the hot loop of
+/// [`Blueprint::search`].
+#[cold]
+#[inline(never)]
+fn check_in(stop: &mut impl FnMut(usize, usize) -> bool, result: usize, expanded: usize) -> bool {
+    stop(result, expanded)
+}
+
 struct Step {
     time_left: u8,
     ore: usize,
@@ -223,37 +289,259 @@ impl Step {
     }
 }
 
+/// Tunables of the puzzle, see [`crate::params`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Params {
+    pub part1_minutes: u8,
+    pub part2_minutes: u8,
+    /// Number of blueprints that are left after the elephants ate the rest.
+    pub part2_blueprints: usize,
+}
+
+impl Default for Params {
+    fn default() -> Self {
+        Params {
+            part1_minutes: 24,
+            part2_minutes: 32,
+            part2_blueprints: 3,
+        }
+    }
+}
+
+/// Checks that the blueprints are numbered in order and that there are enough of them for part 2
+/// with the default [`Params`].
+pub fn validate(input: &str) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    let mut count = 0;
+
+    for (idx, line) in input.lines().enumerate() {
+        let Some(blueprint) = check(&mut diagnostics, input, parse::all(line, blueprint)) else {
+            continue;
+        };
+
+        // Quality levels are based on the id, not on the position in the list.
+        if blueprint.id != idx + 1 {
+            diagnostics.push(
+                Diagnostic::warning(format!(
+                    "Blueprint {} is number {} in the list",
+                    blueprint.id,
+                    idx + 1
+                ))
+                .at(input, line),
+            );
+        }
+
+        count += 1;
+    }
+
+    let part2_blueprints = Params::default().part2_blueprints;
+
+    if count < part2_blueprints {
+        diagnostics.push(Diagnostic::warning(format!(
+            "Part 2 looks at the first {part2_blueprints} blueprints, but there are only {count}"
+        )));
+    }
+
+    diagnostics
+}
+
+/// The blueprints in the order of the list.
+#[derive(Debug, Serialize)]
+pub struct Blueprints(Vec<Blueprint>);
+
+#[aoc_generator(day19)]
+pub fn parse(input: &str) -> Result<Blueprints, Error> {
+    parse::all(input, lines(blueprint)).map(Blueprints)
+}
+
 #[aoc(day19, part1)]
-pub fn part1(input: &str) -> Result<usize, ParseIntError> {
-    let blueprints = input
-        .lines()
-        .map(Blueprint::parse)
-        .collect::<Result<Vec<_>, _>>()
-        .unwrap();
-
-    Ok(blueprints
-        .par_iter()
-        .map(|blueprint| blueprint.id * blueprint.max_geodes(24))
-        .sum())
+pub fn part1(blueprints: &Blueprints) -> usize {
+    part1_with(blueprints, &Params::default())
+}
+
+pub fn part1_with(blueprints: &Blueprints, params: &Params) -> usize {
+    part1_within(blueprints, params, &Budget::new()).0.answer
+}
+
+/// Like [`part1_with`], along with the work of the searches over all blueprints, giving up with
+/// the most geodes found so far for every blueprint when the `budget` runs out.
+pub fn part1_within(
+    blueprints: &Blueprints,
+    params: &Params,
+    budget: &Budget,
+) -> (Outcome<usize>, Stats) {
+    search_all(
+        &blueprints.0,
+        params.part1_minutes,
+        budget,
+        0,
+        |blueprint, geodes| blueprint.id * geodes,
+        |a, b| a + b,
+    )
 }
 
 #[aoc(day19, part2)]
-pub fn part2(input: &str) -> Result<usize, ParseIntError> {
-    let blueprints = input
-        .lines()
-        .take(3)
-        .map(Blueprint::parse)
-        .collect::<Result<Vec<_>, _>>()
-        .unwrap();
-
-    Ok(blueprints
+pub fn part2(blueprints: &Blueprints) -> usize {
+    part2_with(blueprints, &Params::default())
+}
+
+pub fn part2_with(blueprints: &Blueprints, params: &Params) -> usize {
+    part2_within(blueprints, params, &Budget::new()).0.answer
+}
+
+/// Like [`part2_with`], along with the work of the searches over the remaining blueprints, giving
+/// up with the most geodes found so far for every blueprint when the `budget` runs out.
+pub fn part2_within(
+    blueprints: &Blueprints,
+    params: &Params,
+    budget: &Budget,
+) -> (Outcome<usize>, Stats) {
+    let count = params.part2_blueprints.min(blueprints.0.len());
+
+    search_all(
+        &blueprints.0[..count],
+        params.part2_minutes,
+        budget,
+        1,
+        |_, geodes| geodes,
+        |a, b| a * b,
+    )
+}
+
+/// Searches every blueprint in parallel, and folds what `score` makes of their geodes with
+/// `combine`, starting from `identity`. Progress reports show the answer so far over all blueprints.
+fn search_all(
+    blueprints: &[Blueprint],
+    time: u8,
+    budget: &Budget,
+    identity: usize,
+    score: impl Fn(&Blueprint, usize) -> usize + Sync,
+    combine: impl Fn(usize, usize) -> usize + Sync,
+) -> (Outcome<usize>, Stats) {
+    let bests = blueprints
+        .iter()
+        .map(|_| AtomicUsize::new(0))
+        .collect::<Vec<_>>();
+
+    let answer = || {
+        blueprints
+            .iter()
+            .zip(&bests)
+            .map(|(blueprint, best)| score(blueprint, best.load(Ordering::Relaxed)))
+            .fold(identity, &combine)
+    };
+
+    blueprints
         .par_iter()
-        .map(|blueprint| blueprint.max_geodes(32))
-        .product())
+        .zip(&bests)
+        .map(|(blueprint, best)| {
+            let mut meter = budget.meter();
+
+            let (geodes, stats) = blueprint.search(time, |geodes, expanded| {
+                best.store(geodes, Ordering::Relaxed);
+                meter.tick_by(expanded, || answer().into())
+            });
+
+            (geodes.map(|geodes| score(blueprint, geodes)), stats)
+        })
+        .reduce(
+            || {
+                (
+                    Outcome {
+                        answer: identity,
+                        is_final: true,
+                    },
+                    Stats::default(),
+                )
+            },
+            |(a, a_stats), (b, b_stats)| (a.zip(b, &combine), a_stats.merge(b_stats)),
+        )
+}
+
+/// A plain search over every order of building robots, to check the pruning of [`Step::best`] and
+/// [`Step::can_afford_always`] against.
+#[cfg(test)]
+mod reference {
+    use std::collections::HashMap;
+
+    use super::{Blueprint, Cost};
+
+    /// Stock of ore, clay and obsidian, followed by the robots collecting them.
+    type State = [usize; 6];
+
+    fn build(state: State, cost: &Cost) -> Option<State> {
+        let [ore, clay, obsidian, ..] = state;
+
+        (ore >= cost.ore && clay >= cost.clay && obsidian >= cost.obsidian).then(|| {
+            let mut state = state;
+            state[0] -= cost.ore;
+            state[1] -= cost.clay;
+            state[2] -= cost.obsidian;
+            state
+        })
+    }
+
+    fn collect(mut state: State) -> State {
+        for idx in 0..3 {
+            state[idx] += state[idx + 3];
+        }
+
+        state
+    }
+
+    fn geodes(
+        blueprint: &Blueprint,
+        memo: &mut HashMap<(State, u8), usize>,
+        state: State,
+        time_left: u8,
+    ) -> usize {
+        if time_left == 0 {
+            return 0;
+        }
+
+        if let Some(&result) = memo.get(&(state, time_left)) {
+            return result;
+        }
+
+        let mut result = geodes(blueprint, memo, collect(state), time_left - 1);
+
+        // A geode robot cracks a geode in every minute after the one it is built in.
+        if let Some(next) = build(state, &blueprint.geode_robot_cost) {
+            result = result.max(
+                time_left as usize - 1 + geodes(blueprint, memo, collect(next), time_left - 1),
+            );
+        }
+
+        for (robot, cost) in [
+            &blueprint.ore_robot_cost,
+            &blueprint.clay_robot_cost,
+            &blueprint.obsidian_robot_cost,
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            if let Some(next) = build(state, cost) {
+                let mut next = collect(next);
+                next[robot + 3] += 1;
+                result = result.max(geodes(blueprint, memo, next, time_left - 1));
+            }
+        }
+
+        memo.insert((state, time_left), result);
+        result
+    }
+
+    pub fn max_geodes(blueprint: &Blueprint, time: u8) -> usize {
+        geodes(blueprint, &mut HashMap::new(), [0, 0, 0, 1, 0, 0], time)
+    }
 }
 
 #[cfg(test)]
 mod tests {
+    use proptest::prelude::*;
+
+    use super::{reference, Blueprint, Cost};
+
     #[test]
     fn geode_robot_every_minute() {
         assert_eq!(super::geode_robot_every_minute(1), 0);
@@ -262,15 +550,45 @@ mod tests {
         assert_eq!(super::geode_robot_every_minute(4), 6);
     }
 
-    #[test]
-    fn test_case_1() {
-        let result = super::part1("Blueprint 1: Each ore robot costs 4 ore. Each clay robot costs 2 ore. Each obsidian robot costs 3 ore and 14 clay. Each geode robot costs 2 ore and 7 obsidian.\nBlueprint 2: Each ore robot costs 2 ore. Each clay robot costs 3 ore. Each obsidian robot costs 3 ore and 8 clay. Each geode robot costs 3 ore and 12 obsidian.");
-        assert_eq!(result, Ok(33));
+    /// A blueprint with cheap robots, so that geodes get cracked within a few minutes.
+    fn blueprint() -> impl Strategy<Value = Blueprint> {
+        (
+            1..=4usize,
+            1..=4usize,
+            (1..=4usize, 1..=8usize),
+            (1..=4usize, 1..=8usize),
+        )
+            .prop_map(
+                |(ore_robot, clay_robot, obsidian_robot, geode_robot)| Blueprint {
+                    id: 1,
+                    ore_robot_cost: Cost {
+                        ore: ore_robot,
+                        clay: 0,
+                        obsidian: 0,
+                    },
+                    clay_robot_cost: Cost {
+                        ore: clay_robot,
+                        clay: 0,
+                        obsidian: 0,
+                    },
+                    obsidian_robot_cost: Cost {
+                        ore: obsidian_robot.0,
+                        clay: obsidian_robot.1,
+                        obsidian: 0,
+                    },
+                    geode_robot_cost: Cost {
+                        ore: geode_robot.0,
+                        clay: 0,
+                        obsidian: geode_robot.1,
+                    },
+                },
+            )
     }
 
-    #[test]
-    fn test_case_2() {
-        let result = super::part2("Blueprint 1: Each ore robot costs 4 ore. Each clay robot costs 2 ore. Each obsidian robot costs 3 ore and 14 clay. Each geode robot costs 2 ore and 7 obsidian.\nBlueprint 2: Each ore robot costs 2 ore. Each clay robot costs 3 ore. Each obsidian robot costs 3 ore and 8 clay. Each geode robot costs 3 ore and 12 obsidian.");
-        assert_eq!(result, Ok(56 * 62));
+    proptest! {
+        #[test]
+        fn max_geodes_matches_reference(blueprint in blueprint(), time in 1..=14u8) {
+            prop_assert_eq!(blueprint.search(time, |_, _| false).0.answer, reference::max_geodes(&blueprint, time));
+        }
     }
 }
@@ -1,8 +1,15 @@
-use itertools::Itertools;
-use std::str::FromStr;
+use aoc_runner_derive::{aoc, aoc_generator};
+use nom::sequence::separated_pair;
+use serde::Serialize;
 
-use aoc_runner_derive::aoc;
+use crate::{
+    diagnostic::{check, Diagnostic},
+    util::parse::{self, char_of, lines, tag, IResult},
+    Error,
+};
 
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "lowercase")]
 enum Shape {
     Rock,
     Paper,
@@ -15,31 +22,50 @@ enum Outcome {
     Win,
 }
 
-impl FromStr for Shape {
-    type Err = ();
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s {
-            "A" => Ok(Shape::Rock),
-            "B" => Ok(Shape::Paper),
-            "C" => Ok(Shape::Scissors),
-            "X" => Ok(Shape::Rock),
-            "Y" => Ok(Shape::Paper),
-            "Z" => Ok(Shape::Scissors),
-            _ => panic!("Invalid shape"),
-        }
-    }
+/// The second column of the strategy guide, which part 1 reads as a shape and part 2 as an outcome.
+#[derive(Debug, Serialize)]
+enum Response {
+    X,
+    Y,
+    Z,
+}
+
+fn shape(input: &str) -> IResult<'_, Shape> {
+    char_of("the opponent's shape (A, B or C)", |c| match c {
+        'A' => Some(Shape::Rock),
+        'B' => Some(Shape::Paper),
+        'C' => Some(Shape::Scissors),
+        _ => None,
+    })(input)
+}
+
+fn response(input: &str) -> IResult<'_, Response> {
+    char_of("a response (X, Y or Z)", |c| match c {
+        'X' => Some(Response::X),
+        'Y' => Some(Response::Y),
+        'Z' => Some(Response::Z),
+        _ => None,
+    })(input)
 }
 
-impl FromStr for Outcome {
-    type Err = ();
+fn round(input: &str) -> IResult<'_, (Shape, Response)> {
+    separated_pair(shape, tag(" "), response)(input)
+}
+
+impl Response {
+    fn shape(&self) -> Shape {
+        match self {
+            Response::X => Shape::Rock,
+            Response::Y => Shape::Paper,
+            Response::Z => Shape::Scissors,
+        }
+    }
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s {
-            "X" => Ok(Outcome::Lose),
-            "Y" => Ok(Outcome::Draw),
-            "Z" => Ok(Outcome::Win),
-            _ => panic!("Invalid outcome"),
+    fn outcome(&self) -> Outcome {
+        match self {
+            Response::X => Outcome::Lose,
+            Response::Y => Outcome::Draw,
+            Response::Z => Outcome::Win,
         }
     }
 }
@@ -78,41 +104,51 @@ impl Outcome {
     }
 }
 
+/// Checks that every round is an opponent's shape followed by X, Y or Z, which part 1 reads as a
+/// shape and part 2 as an outcome.
+pub fn validate(input: &str) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+
+    for line in input.lines() {
+        check(&mut diagnostics, input, parse::all(line, round));
+    }
+
+    diagnostics
+}
+
+/// Every round of the strategy guide.
+#[derive(Debug, Serialize)]
+pub struct Guide(Vec<(Shape, Response)>);
+
+#[aoc_generator(day2)]
+pub fn parse(input: &str) -> Result<Guide, Error> {
+    parse::all(input, lines(round)).map(Guide)
+}
+
 #[aoc(day2, part1)]
-pub fn part1(input: &str) -> u64 {
-    input
-        .split_whitespace()
-        .map(|s| s.parse::<Shape>().unwrap())
-        .tuples()
-        .fold(0, |acc, (opponent, player)| {
-            acc + player.score() + player.outcome(&opponent).score()
-        })
+pub fn part1(guide: &Guide) -> u64 {
+    guide.0.iter().fold(0, |acc, (opponent, response)| {
+        let player = response.shape();
+
+        acc + player.score() + player.outcome(opponent).score()
+    })
 }
 
 #[aoc(day2, part2)]
-pub fn part2(input: &str) -> u64 {
-    input
-        .split_whitespace()
-        .tuples()
-        .map(|(opponent, outcome)| {
-            (
-                opponent.parse::<Shape>().unwrap(),
-                outcome.parse::<Outcome>().unwrap(),
-            )
-        })
-        .fold(0, |acc, (opponent, outcome)| {
-            let player = match (&opponent, &outcome) {
-                (Shape::Rock, Outcome::Lose) => Shape::Scissors,
-                (Shape::Rock, Outcome::Draw) => Shape::Rock,
-                (Shape::Rock, Outcome::Win) => Shape::Paper,
-                (Shape::Paper, Outcome::Lose) => Shape::Rock,
-                (Shape::Paper, Outcome::Draw) => Shape::Paper,
-                (Shape::Paper, Outcome::Win) => Shape::Scissors,
-                (Shape::Scissors, Outcome::Lose) => Shape::Paper,
-                (Shape::Scissors, Outcome::Draw) => Shape::Scissors,
-                (Shape::Scissors, Outcome::Win) => Shape::Rock,
-            };
-
-            acc + player.score() + player.outcome(&opponent).score()
-        })
+pub fn part2(guide: &Guide) -> u64 {
+    guide.0.iter().fold(0, |acc, (opponent, response)| {
+        let player = match (opponent, response.outcome()) {
+            (Shape::Rock, Outcome::Lose) => Shape::Scissors,
+            (Shape::Rock, Outcome::Draw) => Shape::Rock,
+            (Shape::Rock, Outcome::Win) => Shape::Paper,
+            (Shape::Paper, Outcome::Lose) => Shape::Rock,
+            (Shape::Paper, Outcome::Draw) => Shape::Paper,
+            (Shape::Paper, Outcome::Win) => Shape::Scissors,
+            (Shape::Scissors, Outcome::Lose) => Shape::Paper,
+            (Shape::Scissors, Outcome::Draw) => Shape::Scissors,
+            (Shape::Scissors, Outcome::Win) => Shape::Rock,
+        };
+
+        acc + player.score() + player.outcome(opponent).score()
+    })
 }
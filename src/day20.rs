@@ -1,13 +1,18 @@
-use std::{
-    num::ParseIntError,
-    ops::{Index, Range},
-    str::FromStr,
-};
+use std::ops::{Index, Range};
+
+use aoc_runner_derive::{aoc, aoc_generator};
+use nom::combinator::map;
+use serde::{Serialize, Serializer};
 
-use aoc_runner_derive::aoc;
+use crate::{
+    diagnostic::Diagnostic,
+    util::parse::{self, lines, number, IResult},
+    Error,
+};
 
-struct Number {
-    index: u16,
+#[derive(Debug, Clone)]
+pub struct Number {
+    index: usize,
     value: i16,
 }
 
@@ -29,21 +34,27 @@ impl Number {
     }
 }
 
-impl TryFrom<(usize, &str)> for Number {
-    type Error = ParseIntError;
+/// The numbers of the encrypted file, along with their original positions.
+#[derive(Debug, Clone)]
+pub struct File(Vec<Number>);
 
-    fn try_from(value: (usize, &str)) -> Result<Self, Self::Error> {
-        Ok(Self {
-            index: value.0 as u16,
-            value: value.1.parse()?,
+impl File {
+    /// Checks that there is something to mix, and a 0 to count the grove coordinates from.
+    fn mixable(&self) -> Result<(), Error> {
+        let reason = if self.0.len() < 2 {
+            "mixing needs at least two numbers to move around"
+        } else if !self.0.iter().any(Number::is_zero) {
+            "there is no 0 to find the grove coordinates from"
+        } else {
+            return Ok(());
+        };
+
+        Err(Error::NoSolution {
+            reason: reason.to_string(),
         })
     }
-}
 
-struct File(Vec<Number>);
-
-impl File {
-    fn pos(&self, idx: u16) -> usize {
+    fn pos(&self, idx: usize) -> usize {
         self.0.iter().position(|n| n.index == idx).unwrap()
     }
 
@@ -51,8 +62,8 @@ impl File {
         self.0.len() - 1
     }
 
-    fn range(&self) -> Range<u16> {
-        0..(self.0.len() as u16)
+    fn range(&self) -> Range<usize> {
+        0..self.0.len()
     }
 
     fn swap(&mut self, lhs: usize, rhs: usize) {
@@ -68,20 +79,29 @@ impl File {
     }
 }
 
-impl FromStr for File {
-    type Err = ParseIntError;
+/// Serializes as the values in their original order.
+impl Serialize for File {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut numbers = self.0.iter().collect::<Vec<_>>();
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let numbers = s
-            .lines()
-            .enumerate()
-            .map(Number::try_from)
-            .collect::<Result<Vec<_>, _>>()?;
+        numbers.sort_by_key(|n| n.index);
 
-        Ok(Self(numbers))
+        serializer.collect_seq(numbers.iter().map(|n| n.value))
     }
 }
 
+fn file(input: &str) -> IResult<'_, File> {
+    map(lines(number), |values| {
+        File(
+            values
+                .into_iter()
+                .enumerate()
+                .map(|(index, value)| Number { index, value })
+                .collect(),
+        )
+    })(input)
+}
+
 impl Index<usize> for File {
     type Output = Number;
 
@@ -90,11 +110,63 @@ impl Index<usize> for File {
     }
 }
 
+/// Tunables of the puzzle, see [`crate::params`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Params {
+    pub decryption_key: usize,
+    /// Number of times the file is mixed in part 2.
+    pub rounds: usize,
+}
+
+impl Default for Params {
+    fn default() -> Self {
+        Params {
+            decryption_key: 811589153,
+            rounds: 10,
+        }
+    }
+}
+
+/// Checks that the file has a single 0 to start counting from.
+pub fn validate(input: &str) -> Vec<Diagnostic> {
+    let file = match parse(input) {
+        Ok(file) => file,
+        Err(err) => return vec![Diagnostic::from_error(err, input)],
+    };
+
+    let mut diagnostics = Vec::new();
+
+    if file.0.len() < 2 {
+        diagnostics.push(Diagnostic::error(
+            "Mixing needs at least two numbers to move around",
+        ));
+    }
+
+    match file.0.iter().filter(|n| n.is_zero()).count() {
+        0 => diagnostics.push(Diagnostic::error(
+            "There is no 0 to find the grove coordinates from",
+        )),
+        1 => {}
+        zeros => diagnostics.push(Diagnostic::warning(format!(
+            "There are {zeros} zeros, the grove coordinates are counted from the first one"
+        ))),
+    }
+
+    diagnostics
+}
+
+#[aoc_generator(day20)]
+pub fn parse(input: &str) -> Result<File, Error> {
+    parse::all(input, file)
+}
+
 #[aoc(day20, part1)]
-pub fn part1(input: &str) -> Result<i64, ParseIntError> {
+pub fn part1(file: &File) -> Result<i64, Error> {
     const DECRYPTION_KEY: usize = 1;
 
-    let mut file = File::from_str(input)?;
+    file.mixable()?;
+
+    let mut file = file.clone();
     let last_pos = file.last_pos();
 
     for idx in file.range() {
@@ -137,13 +209,17 @@ pub fn part1(input: &str) -> Result<i64, ParseIntError> {
 }
 
 #[aoc(day20, part2)]
-pub fn part2(input: &str) -> Result<i64, ParseIntError> {
-    const DECRYPTION_KEY: usize = 811589153;
+pub fn part2(file: &File) -> Result<i64, Error> {
+    part2_with(file, &Params::default())
+}
+
+pub fn part2_with(file: &File, params: &Params) -> Result<i64, Error> {
+    file.mixable()?;
 
-    let mut file = File::from_str(input)?;
+    let mut file = file.clone();
     let last_pos = file.last_pos();
 
-    for _ in 0..10 {
+    for _ in 0..params.rounds {
         for idx in file.range() {
             let mut pos = file.pos(idx);
             let number = &file[pos];
@@ -153,7 +229,7 @@ pub fn part2(input: &str) -> Result<i64, ParseIntError> {
             }
 
             let direction = number.signum();
-            let distance = number.distance(DECRYPTION_KEY, last_pos);
+            let distance = number.distance(params.decryption_key, last_pos);
 
             for _ in 0..distance {
                 match direction {
@@ -181,20 +257,5 @@ pub fn part2(input: &str) -> Result<i64, ParseIntError> {
         }
     }
 
-    Ok(file.grove_coordinates(DECRYPTION_KEY))
-}
-
-#[cfg(test)]
-mod tests {
-    #[test]
-    fn test_case_1() {
-        let result = super::part1("1\n2\n-3\n3\n-2\n0\n4");
-        assert_eq!(result, Ok(3));
-    }
-
-    #[test]
-    fn test_case_2() {
-        let result = super::part2("1\n2\n-3\n3\n-2\n0\n4");
-        assert_eq!(result, Ok(1623178306));
-    }
+    Ok(file.grove_coordinates(params.decryption_key))
 }
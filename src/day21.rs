@@ -1,24 +1,58 @@
-use std::{collections::BTreeMap, num::ParseIntError, str::FromStr};
+use std::{collections::BTreeMap, fmt::Display};
 
-use aoc_runner_derive::aoc;
+use aoc_runner_derive::{aoc, aoc_generator};
+use nom::{
+    combinator::{consumed, map},
+    sequence::{delimited, separated_pair},
+};
+use serde::{Serialize, Serializer};
+
+use crate::{
+    diagnostic::{check, Diagnostic},
+    util::parse::{self, char_of, fail, lines, number, tag, IResult},
+    Error,
+};
 
 const ROOT: Name = Name(u32::from_be_bytes(*b"root"));
 const ME: Name = Name(u32::from_be_bytes(*b"humn"));
 
-#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 struct Name(u32);
 
-impl FromStr for Name {
-    type Err = ParseIntError;
+impl Display for Name {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&String::from_utf8_lossy(&self.0.to_be_bytes()))
+    }
+}
+
+/// Serializes as the four letter name.
+impl Serialize for Name {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+fn no_solution(reason: impl Into<String>) -> Error {
+    Error::NoSolution {
+        reason: reason.into(),
+    }
+}
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let s = s.as_bytes();
+fn name(input: &str) -> IResult<'_, Name> {
+    let len = input
+        .find(|c: char| !c.is_ascii_alphabetic())
+        .unwrap_or(input.len());
 
-        Ok(Name(u32::from_be_bytes([s[0], s[1], s[2], s[3]])))
+    match <[u8; 4]>::try_from(&input.as_bytes()[..len]) {
+        Ok(bytes) => Ok((&input[len..], Name(u32::from_be_bytes(bytes)))),
+        Err(_) => fail(&input[..len], "a four letter monkey name"),
     }
 }
 
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "lowercase")]
 enum Job {
+    #[serde(rename = "number")]
     Static(u64),
     Add(Name, Name),
     Sub(Name, Name),
@@ -37,173 +71,348 @@ impl Job {
         }
     }
 
-    fn value(&self, known: &BTreeMap<Name, u64>) -> Option<u64> {
-        match self {
-            Job::Static(value) => Some(*value),
-            Job::Add(lhs, rhs) => {
-                Option::zip(known.get(lhs), known.get(rhs)).map(|(lhs, rhs)| lhs + rhs)
-            }
-            Job::Sub(lhs, rhs) => {
-                Option::zip(known.get(lhs), known.get(rhs)).map(|(lhs, rhs)| lhs - rhs)
-            }
-            Job::Mul(lhs, rhs) => {
-                Option::zip(known.get(lhs), known.get(rhs)).map(|(lhs, rhs)| lhs * rhs)
-            }
-            Job::Div(lhs, rhs) => {
-                Option::zip(known.get(lhs), known.get(rhs)).map(|(lhs, rhs)| lhs / rhs)
-            }
-        }
+    /// The number this monkey yells, or `None` while it is still waiting for one of the others.
+    /// Results that aren't a `u64`, like negative numbers or division by zero, are an error.
+    fn value(&self, known: &BTreeMap<Name, u64>) -> Result<Option<u64>, Error> {
+        let (lhs, symbol, rhs, op): (_, _, _, fn(u64, u64) -> Option<u64>) = match self {
+            Job::Static(value) => return Ok(Some(*value)),
+            Job::Add(lhs, rhs) => (lhs, '+', rhs, u64::checked_add),
+            Job::Sub(lhs, rhs) => (lhs, '-', rhs, u64::checked_sub),
+            Job::Mul(lhs, rhs) => (lhs, '*', rhs, u64::checked_mul),
+            Job::Div(lhs, rhs) => (lhs, '/', rhs, u64::checked_div),
+        };
+
+        let Some((&lhs, &rhs)) = Option::zip(known.get(lhs), known.get(rhs)) else {
+            return Ok(None);
+        };
+
+        op(lhs, rhs).map(Some).ok_or_else(|| {
+            no_solution(format!(
+                "{lhs} {symbol} {rhs} isn't an unsigned 64 bit number"
+            ))
+        })
     }
 }
 
-impl FromStr for Job {
-    type Err = ParseIntError;
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        if s.chars().next().unwrap().is_numeric() {
-            Ok(Job::Static(s.parse()?))
-        } else {
-            match s.chars().nth(5).unwrap() {
-                '+' => Ok(Job::Add(s[0..4].parse()?, s[7..11].parse()?)),
-                '-' => Ok(Job::Sub(s[0..4].parse()?, s[7..11].parse()?)),
-                '*' => Ok(Job::Mul(s[0..4].parse()?, s[7..11].parse()?)),
-                '/' => Ok(Job::Div(s[0..4].parse()?, s[7..11].parse()?)),
-                _ => panic!("Invalid input"),
-            }
-        }
+fn job(input: &str) -> IResult<'_, Job> {
+    if input.starts_with(|c: char| c.is_ascii_digit()) {
+        return map(number, Job::Static)(input);
     }
+
+    let operator = char_of("an operator (+, -, * or /)", |c| match c {
+        '+' => Some(Job::Add as fn(Name, Name) -> Job),
+        '-' => Some(Job::Sub),
+        '*' => Some(Job::Mul),
+        '/' => Some(Job::Div),
+        _ => None,
+    });
+
+    let (input, lhs) = name(input)?;
+    let (input, op) = delimited(tag(" "), operator, tag(" "))(input)?;
+    let (input, rhs) = name(input)?;
+
+    Ok((input, op(lhs, rhs)))
 }
 
+#[derive(Debug, Clone, Serialize)]
 struct Monkey {
     name: Name,
     job: Job,
 }
 
-impl FromStr for Monkey {
-    type Err = ParseIntError;
+fn monkey(input: &str) -> IResult<'_, Monkey> {
+    map(separated_pair(name, tag(": "), job), |(name, job)| Monkey {
+        name,
+        job,
+    })(input)
+}
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        Ok(Monkey {
-            name: s[0..=4].parse()?,
-            job: s[6..].parse()?,
-        })
+/// Checks that the monkeys form a tree below root, with the human in it exactly once.
+pub fn validate(input: &str) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    let mut monkeys = BTreeMap::<Name, (&str, Monkey)>::new();
+
+    for line in input.lines() {
+        let Some(monkey) = check(&mut diagnostics, input, parse::all(line, monkey)) else {
+            continue;
+        };
+
+        if monkeys.insert(monkey.name, (line, monkey)).is_some() {
+            diagnostics.push(
+                Diagnostic::error("A monkey with this name was already listed").at(input, line),
+            );
+        }
     }
-}
 
-#[aoc(day21, part1)]
-pub fn part1(input: &str) -> Result<u64, ParseIntError> {
-    let mut monkeys = input
-        .lines()
-        .map(Monkey::from_str)
-        .collect::<Result<Vec<_>, _>>()?;
+    match monkeys.get(&ROOT) {
+        None => diagnostics.push(Diagnostic::error("There is no monkey named root")),
+        Some((line, root)) if root.job.dependencies().is_none() => diagnostics
+            .push(Diagnostic::error("Root must wait for two other monkeys").at(input, line)),
+        _ => {}
+    }
 
-    let mut known = BTreeMap::<Name, u64>::new();
+    if !monkeys.contains_key(&ME) {
+        diagnostics.push(Diagnostic::error("There is no monkey named humn"));
+    }
 
-    loop {
-        monkeys.retain(|monkey| {
-            if let Some(value) = monkey.job.value(&known) {
-                known.insert(monkey.name, value);
-                false
-            } else {
-                true
+    let mut listeners = BTreeMap::<Name, usize>::new();
+
+    for (line, monkey) in monkeys.values() {
+        for name in monkey
+            .job
+            .dependencies()
+            .into_iter()
+            .flat_map(|(a, b)| [a, b])
+        {
+            if !monkeys.contains_key(&name) {
+                diagnostics.push(
+                    Diagnostic::error("Waits for a monkey that isn't listed").at(input, line),
+                );
             }
-        });
 
-        if let Some(result) = known.get(&ROOT) {
-            return Ok(*result);
+            *listeners.entry(name).or_default() += 1;
         }
     }
+
+    // Part 2 works its way back down from root, which only finds a single path to humn in a tree.
+    if listeners.values().any(|&count| count > 1) {
+        diagnostics.push(Diagnostic::error(
+            "Some monkey is waited for by more than one other monkey",
+        ));
+    }
+
+    diagnostics
 }
 
-#[aoc(day21, part2)]
-pub fn part2(input: &str) -> Result<u64, ParseIntError> {
-    let mut monkeys = input
-        .lines()
-        .map(Monkey::from_str)
-        .collect::<Result<Vec<_>, _>>()?;
+/// Every monkey along with its job, in the order of the list.
+#[derive(Debug, Serialize)]
+pub struct Monkeys(Vec<Monkey>);
 
-    let mut known = BTreeMap::<Name, u64>::new();
-    let mut start_reverse_from = Option::<Name>::None;
+/// The monkeys, each listed once, which have to include root waiting for two others and humn, and
+/// which only wait for monkeys on the list.
+fn monkeys(input: &str) -> IResult<'_, Monkeys> {
+    let (rest, lines) = lines(consumed(monkey))(input)?;
+    let mut names = BTreeMap::new();
 
-    while !known.contains_key(&ROOT) {
-        monkeys.retain(|monkey| {
-            if monkey.name == ME {
-                return true;
-            }
+    for (line, monkey) in &lines {
+        if names.insert(monkey.name, &monkey.job).is_some() {
+            return fail(line, "a monkey that isn't listed yet");
+        }
+    }
 
-            if monkey.name == ROOT {
-                let (lhs, rhs) = monkey.job.dependencies().unwrap();
-
-                if let Some(value) = known.get(&lhs).cloned() {
-                    known.insert(ROOT, value);
-                    known.insert(rhs, value);
-                    start_reverse_from = Some(rhs);
-                } else if let Some(value) = known.get(&rhs).cloned() {
-                    known.insert(ROOT, value);
-                    known.insert(lhs, value);
-                    start_reverse_from = Some(lhs);
-                }
+    match names.get(&ROOT) {
+        None => return fail(&rest[..0], "a monkey named root"),
+        Some(job) if job.dependencies().is_none() => {
+            let (line, _) = lines
+                .iter()
+                .find(|(_, monkey)| monkey.name == ROOT)
+                .unwrap();
+            return fail(line, "root waiting for two other monkeys");
+        }
+        _ => {}
+    }
+
+    if !names.contains_key(&ME) {
+        return fail(&rest[..0], "a monkey named humn");
+    }
+
+    if let Some((line, _)) = lines.iter().find(|(_, monkey)| {
+        monkey
+            .job
+            .dependencies()
+            .is_some_and(|(lhs, rhs)| !names.contains_key(&lhs) || !names.contains_key(&rhs))
+    }) {
+        return fail(line, "a job waiting for monkeys on the list");
+    }
 
+    Ok((
+        rest,
+        Monkeys(lines.into_iter().map(|(_, monkey)| monkey).collect()),
+    ))
+}
+
+#[aoc_generator(day21)]
+pub fn parse(input: &str) -> Result<Monkeys, Error> {
+    parse::all(input, monkeys)
+}
+
+/// Works out the numbers of all `monkeys` that aren't `skip`ped and only wait for monkeys that are
+/// `known`, and leaves the rest in `monkeys`. Those are either skipped, wait for a skipped monkey,
+/// or wait for each other in a cycle.
+fn resolve(
+    monkeys: &mut Vec<Monkey>,
+    known: &mut BTreeMap<Name, u64>,
+    skip: impl Fn(Name) -> bool,
+) -> Result<(), Error> {
+    loop {
+        let waiting = monkeys.len();
+        let mut result = Ok(());
+
+        monkeys.retain(|monkey| {
+            if skip(monkey.name) || result.is_err() {
                 return true;
             }
 
-            if let Some(value) = monkey.job.value(&known) {
-                known.insert(monkey.name, value);
-                false
-            } else {
-                true
+            match monkey.job.value(known) {
+                Ok(Some(value)) => {
+                    known.insert(monkey.name, value);
+                    false
+                }
+                Ok(None) => true,
+                Err(err) => {
+                    result = Err(err);
+                    true
+                }
             }
         });
+
+        result?;
+
+        if monkeys.len() == waiting {
+            return Ok(());
+        }
     }
+}
+
+#[aoc(day21, part1)]
+pub fn part1(monkeys: &Monkeys) -> Result<u64, Error> {
+    let mut monkeys = monkeys.0.clone();
+    let mut known = BTreeMap::<Name, u64>::new();
+
+    resolve(&mut monkeys, &mut known, |_| false)?;
+
+    known
+        .get(&ROOT)
+        .copied()
+        .ok_or_else(|| no_solution("root waits for monkeys that wait for each other"))
+}
+
+#[aoc(day21, part2)]
+pub fn part2(monkeys: &Monkeys) -> Result<u64, Error> {
+    let mut monkeys = monkeys.0.clone();
+    let mut known = BTreeMap::<Name, u64>::new();
+
+    // Resolve everything that doesn't depend on me, not just enough for one side of the root, so
+    // that every monkey on the way back down has one side known.
+    resolve(&mut monkeys, &mut known, |name| name == ME || name == ROOT)?;
 
     let monkeys = monkeys
         .into_iter()
         .map(|m| (m.name, m))
         .collect::<BTreeMap<_, _>>();
 
-    let mut current = start_reverse_from.unwrap();
+    let (lhs, rhs) = monkeys
+        .get(&ROOT)
+        .and_then(|root| root.job.dependencies())
+        .ok_or_else(|| no_solution("root doesn't wait for two other monkeys"))?;
+
+    let (mut current, mut result) = match (known.get(&lhs), known.get(&rhs)) {
+        (Some(&value), None) => (rhs, value),
+        (None, Some(&value)) => (lhs, value),
+        (Some(_), Some(_)) => return Err(no_solution("neither side of root depends on humn")),
+        (None, None) => return Err(no_solution("both sides of root depend on humn")),
+    };
 
     while current != ME {
-        let result = known.get(&current).unwrap();
-        let monkey = monkeys.get(&current).unwrap();
-        let (lhs, rhs) = monkey.job.dependencies().unwrap();
-        let (lhs_value, rhs_value) = (known.get(&lhs).cloned(), known.get(&rhs).cloned());
-
-        let value = match (lhs_value, rhs_value, &monkey.job) {
-            (Some(x), None, Job::Add(_, _)) => result - x,
-            (None, Some(x), Job::Add(_, _)) => result - x,
-            (Some(x), None, Job::Sub(_, _)) => x - result,
-            (None, Some(x), Job::Sub(_, _)) => result + x,
-            (Some(x), None, Job::Mul(_, _)) => result / x,
-            (None, Some(x), Job::Mul(_, _)) => result / x,
-            (Some(x), None, Job::Div(_, _)) => x / result,
-            (None, Some(x), Job::Div(_, _)) => x * result,
-            _ => panic!("My algorithm is broken"),
+        let monkey = &monkeys[&current];
+        let (lhs, rhs) = monkey
+            .job
+            .dependencies()
+            .ok_or_else(|| no_solution(format!("{current} doesn't wait for two other monkeys")))?;
+        let (lhs_value, rhs_value) = (known.get(&lhs).copied(), known.get(&rhs).copied());
+
+        let (next, value) = match (lhs_value, rhs_value, &monkey.job) {
+            (Some(x), None, Job::Add(_, _)) => (rhs, result.checked_sub(x)),
+            (None, Some(x), Job::Add(_, _)) => (lhs, result.checked_sub(x)),
+            (Some(x), None, Job::Sub(_, _)) => (rhs, x.checked_sub(result)),
+            (None, Some(x), Job::Sub(_, _)) => (lhs, result.checked_add(x)),
+            (Some(x), None, Job::Mul(_, _)) => (rhs, result.checked_div(x)),
+            (None, Some(x), Job::Mul(_, _)) => (lhs, result.checked_div(x)),
+            (Some(x), None, Job::Div(_, _)) => (rhs, x.checked_div(result)),
+            (None, Some(x), Job::Div(_, _)) => (lhs, x.checked_mul(result)),
+            _ => {
+                return Err(no_solution(format!(
+                    "{current} doesn't wait for exactly one monkey that depends on humn"
+                )))
+            }
         };
 
-        if lhs_value.is_none() {
-            known.insert(lhs, value);
-            current = lhs;
-        } else {
-            known.insert(rhs, value);
-            current = rhs;
-        }
+        result = value.ok_or_else(|| {
+            no_solution(format!(
+                "{current} yells {result}, which {next} can't make an unsigned 64 bit number of"
+            ))
+        })?;
+        known.insert(next, result);
+        current = next;
     }
 
-    Ok(*known.get(&ME).unwrap())
+    Ok(result)
 }
 
 #[cfg(test)]
 mod tests {
+    use crate::Error;
+
     #[test]
     fn test_case_1() {
-        let result = super::part1("root: pppw + sjmn\ndbpl: 5\ncczh: sllz + lgvd\nzczc: 2\nptdq: humn - dvpt\ndvpt: 3\nlfqf: 4\nhumn: 5\nljgn: 2\nsjmn: drzm * dbpl\nsllz: 4\npppw: cczh / lfqf\nlgvd: ljgn * ptdq\ndrzm: hmdt - zczc\nhmdt: 32");
-        assert_eq!(result, Ok(152));
+        let diagnostics = super::validate("root: pppw + sjmn\npppw: 5\nsjm: 3");
+
+        assert_eq!(diagnostics.len(), 3);
+        assert_eq!(
+            diagnostics[0].message,
+            "Expected a four letter monkey name, found \"sjm\""
+        );
+        assert_eq!(diagnostics[1].message, "There is no monkey named humn");
+        assert_eq!(
+            diagnostics[2].message,
+            "Waits for a monkey that isn't listed"
+        );
     }
 
     #[test]
     fn test_case_2() {
-        let result = super::part2("root: pppw + sjmn\ndbpl: 5\ncczh: sllz + lgvd\nzczc: 2\nptdq: humn - dvpt\ndvpt: 3\nlfqf: 4\nhumn: 5\nljgn: 2\nsjmn: drzm * dbpl\nsllz: 4\npppw: cczh / lfqf\nlgvd: ljgn * ptdq\ndrzm: hmdt - zczc\nhmdt: 32");
-        assert_eq!(result, Ok(301));
+        // `cccc` is only known after the root is, but it's needed on the way back down to me.
+        let monkeys = super::parse(
+            "root: aaaa + bbbb\nbbbb: humn + cccc\ncccc: dddd * eeee\ndddd: ffff + gggg\naaaa: 5\n\
+             ffff: 1\ngggg: 1\neeee: 2\nhumn: 0",
+        )
+        .unwrap();
+
+        assert_eq!(super::part2(&monkeys), Ok(1));
+    }
+
+    #[test]
+    fn test_case_3() {
+        assert!(super::parse("aaaa: 5").is_err());
+        assert!(super::parse("root: aaaa + bbbb\naaaa: 5\nhumn: 1").is_err());
+        assert!(super::parse("root: aaaa + aaaa\naaaa: 5\naaaa: 6\nhumn: 1").is_err());
+        assert!(super::parse("root: 5\nhumn: 1").is_err());
+    }
+
+    #[test]
+    fn test_case_4() {
+        let monkeys =
+            super::parse("root: aaaa + bbbb\naaaa: 5\nbbbb: humn - cccc\ncccc: 9\nhumn: 1")
+                .unwrap();
+
+        assert!(matches!(
+            super::part1(&monkeys),
+            Err(Error::NoSolution { .. })
+        ));
+        assert_eq!(super::part2(&monkeys), Ok(14));
+
+        // The two sides of root wait for each other.
+        let monkeys =
+            super::parse("root: aaaa + bbbb\naaaa: bbbb * humn\nbbbb: aaaa - humn\nhumn: 1")
+                .unwrap();
+
+        assert!(matches!(
+            super::part1(&monkeys),
+            Err(Error::NoSolution { .. })
+        ));
+        assert!(matches!(
+            super::part2(&monkeys),
+            Err(Error::NoSolution { .. })
+        ));
     }
 }
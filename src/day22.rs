@@ -1,98 +1,101 @@
-use std::{iter::from_generator, num::ParseIntError, str::FromStr};
-
 use nom::{
-    branch::alt, bytes::complete::tag, character::complete::digit1, combinator::map, multi::many1,
-    IResult,
+    branch::alt,
+    combinator::map,
+    sequence::{separated_pair, terminated},
+};
+use serde::{Serialize, Serializer};
+
+use crate::{
+    diagnostic::{check, Diagnostic},
+    util::{
+        geom::Direction,
+        grid::{Grid, Pos},
+        parse::{self, expect, fail, grid_padded, line_of, number, paragraph, tag, IResult},
+    },
+    Error,
 };
 
-#[derive(Debug, Clone, Copy)]
-#[repr(u8)]
-enum Direction {
-    East = 0,
-    South = 1,
-    West = 2,
-    North = 3,
-}
-
-impl Direction {
-    fn turn_left(&self) -> Self {
-        match self {
-            Direction::East => Direction::North,
-            Direction::South => Direction::East,
-            Direction::West => Direction::South,
-            Direction::North => Direction::West,
-        }
-    }
-
-    fn turn_right(&self) -> Self {
-        match self {
-            Direction::East => Direction::South,
-            Direction::South => Direction::West,
-            Direction::West => Direction::North,
-            Direction::North => Direction::East,
-        }
-    }
+/// The final password, from the row and column counting from 1 and the way the path ends up facing.
+fn password(pos: Pos, dir: Direction) -> usize {
+    let facing = match dir {
+        Direction::East => 0,
+        Direction::South => 1,
+        Direction::West => 2,
+        Direction::North => 3,
+    };
 
-    fn delta(&self) -> (isize, isize) {
-        match self {
-            Direction::East => (1, 0),
-            Direction::South => (0, 1),
-            Direction::West => (-1, 0),
-            Direction::North => (0, -1),
-        }
-    }
+    (pos.1 + 1) * 1000 + (pos.0 + 1) * 4 + facing
 }
 
-#[derive(Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum Cell {
     Void,
     Floor,
     Wall,
 }
 
-impl From<char> for Cell {
-    fn from(c: char) -> Self {
+impl Cell {
+    fn new(c: char) -> Option<Self> {
         match c {
-            ' ' => Cell::Void,
-            '.' => Cell::Floor,
-            '#' => Cell::Wall,
-            _ => panic!("Invalid input"),
+            ' ' => Some(Cell::Void),
+            '.' => Some(Cell::Floor),
+            '#' => Some(Cell::Wall),
+            _ => None,
         }
     }
 }
 
+/// The faces of the cube net in [`Map::wrapping_add_3d`], by their position in 50×50 tiles.
+const FACES: [Pos; 6] = [(1, 0), (2, 0), (1, 1), (0, 2), (1, 2), (0, 3)];
+
+#[derive(Debug)]
 struct Map {
-    data: Vec<Cell>,
-    width: usize,
+    grid: Grid<Cell>,
 }
 
 impl Map {
-    fn start(&self) -> (usize, usize) {
-        let position = self
-            .data
-            .iter()
-            .position(|&cell| cell == Cell::Floor)
-            .unwrap();
-
-        (position % self.width, position / self.width)
+    fn start(&self) -> Pos {
+        self.grid.position(|&cell| cell == Cell::Floor).unwrap()
     }
 
-    fn get(&self, pos: (usize, usize)) -> Cell {
-        self.data[pos.1 * self.width + pos.0]
+    fn get(&self, pos: Pos) -> Cell {
+        self.grid[pos]
     }
 
-    fn height(&self) -> usize {
-        self.data.len() / self.width
+    fn wrapping_add_2d(&self, pos: Pos, delta: (isize, isize)) -> Pos {
+        self.grid.wrapping_add(pos, delta)
     }
 
-    fn wrapping_add_2d(&self, pos: (usize, usize), delta: (isize, isize)) -> (usize, usize) {
-        (
-            pos.0.checked_add_signed(delta.0).unwrap_or(self.width - 1) % self.width,
-            pos.1
-                .checked_add_signed(delta.1)
-                .unwrap_or(self.height() - 1)
-                % self.height(),
-        )
+    /// The first tile that doesn't match the layout of the cube net that
+    /// [`Map::wrapping_add_3d`] is written for, which has to be 150×200 tiles to begin with.
+    fn misplaced(&self) -> Option<Pos> {
+        self.grid.positions().find(|&(x, y)| {
+            let on_face = FACES.contains(&(x / 50, y / 50));
+
+            on_face == (self.get((x, y)) == Cell::Void)
+        })
+    }
+
+    /// Checks that the map is the cube net that [`Map::wrapping_add_3d`] is written for.
+    fn foldable(&self) -> Result<(), Error> {
+        let (width, height) = (self.grid.width(), self.grid.height());
+
+        let reason = if (width, height) != (150, 200) {
+            format!(
+                "part 2 only folds maps of 150×200 tiles into a cube, this one is {width}×{height}"
+            )
+        } else if let Some((x, y)) = self.misplaced() {
+            format!(
+                "part 2 only folds maps laid out like the puzzle input into a cube, the tile at \
+                 {}:{} doesn't match that layout",
+                y + 1,
+                x + 1
+            )
+        } else {
+            return Ok(());
+        };
+
+        Err(Error::NoSolution { reason })
     }
 
     //   ABBH
@@ -103,7 +106,7 @@ impl Map {
     // AGGH
     // AG
     // BH
-    fn wrapping_add_3d(&self, pos: (usize, usize), dir: Direction) -> ((usize, usize), Direction) {
+    fn wrapping_add_3d(&self, pos: Pos, dir: Direction) -> (Pos, Direction) {
         match (dir, pos.0, pos.1) {
             // H - F
             (Direction::East, 149, 0..50) => ((99, 149 - pos.1), Direction::West),
@@ -139,59 +142,129 @@ impl Map {
     }
 }
 
-impl FromStr for Map {
-    type Err = ParseIntError;
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let width = s.lines().map(|l| l.len()).max().unwrap();
-
-        let data = s
-            .lines()
-            .flat_map(|line| {
-                from_generator(|| {
-                    let void = width - line.len();
-
-                    for char in line.chars() {
-                        yield char.into();
-                    }
-
-                    for _ in 0..void {
-                        yield Cell::Void;
-                    }
+/// Serializes as the rows of the map as drawn in the input, padded with spaces to the same width.
+impl Serialize for Map {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.grid.rows().map(|row| {
+            row.iter()
+                .map(|cell| match cell {
+                    Cell::Void => ' ',
+                    Cell::Floor => '.',
+                    Cell::Wall => '#',
                 })
-            })
-            .collect::<Vec<_>>();
+                .collect::<String>()
+        }))
+    }
+}
+
+fn board(input: &str) -> IResult<'_, Map> {
+    let (rest, grid) = grid_padded("a tile (space, . or #)", Cell::Void, Cell::new)(input)?;
 
-        Ok(Map { data, width })
+    if !grid.iter().any(|&cell| cell == Cell::Floor) {
+        return fail(
+            &input[..input.len() - rest.len()],
+            "a map with at least one open tile",
+        );
     }
+
+    Ok((rest, Map { grid }))
 }
 
+#[derive(Debug)]
 enum Instruction {
     Forward(usize),
     TurnLeft,
     TurnRight,
 }
 
-impl Instruction {
-    fn parse(input: &str) -> IResult<&str, Instruction> {
+/// Serializes as the number of tiles to move forward, or as `"L"` or `"R"` for a turn.
+impl Serialize for Instruction {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Instruction::Forward(n) => serializer.serialize_u64(*n as u64),
+            Instruction::TurnLeft => serializer.serialize_str("L"),
+            Instruction::TurnRight => serializer.serialize_str("R"),
+        }
+    }
+}
+
+fn instruction(input: &str) -> IResult<'_, Instruction> {
+    expect(
+        "a number of steps, L or R",
         alt((
-            map(tag("L"), |_| Self::TurnLeft),
-            map(tag("R"), |_| Self::TurnRight),
-            map(digit1, |s: &str| Self::Forward(s.parse().unwrap())),
-        ))(input)
+            map(tag("L"), |_| Instruction::TurnLeft),
+            map(tag("R"), |_| Instruction::TurnRight),
+            map(number, Instruction::Forward),
+        )),
+    )(input)
+}
+
+/// Checks that the map is the cube net that [`Map::wrapping_add_3d`] is written for: six faces of
+/// 50×50 tiles, laid out as drawn there.
+pub fn validate(input: &str) -> Vec<Diagnostic> {
+    let (instructions, map) = match terminated(paragraph, tag("\n\n"))(input) {
+        Ok(parts) => parts,
+        Err(err) => return vec![Diagnostic::from_error(err.into(), input)],
+    };
+
+    let mut diagnostics = Vec::new();
+
+    check(
+        &mut diagnostics,
+        input,
+        parse::all(instructions, line_of(instruction)),
+    );
+
+    let Some(map) = check(&mut diagnostics, input, parse::all(map, board)) else {
+        return diagnostics;
+    };
+
+    let (width, height) = (map.grid.width(), map.grid.height());
+
+    if (width, height) != (150, 200) {
+        diagnostics.push(Diagnostic::error(format!(
+            "Part 2 only folds maps of 150×200 tiles into a cube, this one is {width}×{height}"
+        )));
+
+        return diagnostics;
     }
 
-    fn parse_list(input: &str) -> IResult<&str, Vec<Instruction>> {
-        many1(Instruction::parse)(input)
+    if let Some((x, y)) = map.misplaced() {
+        diagnostics.push(Diagnostic {
+            line: y + 1,
+            column: x + 1,
+            ..Diagnostic::error(
+                "Part 2 only folds maps laid out like the puzzle input into a cube, this tile \
+                 doesn't match that layout",
+            )
+        });
     }
+
+    diagnostics
 }
 
-#[aoc(day22, part1)]
-pub fn part1(input: &str) -> Result<usize, ParseIntError> {
-    let (map, instructions) = input.split_once("\n\n").unwrap();
+/// The map of the board along with the path to follow on it.
+#[derive(Debug, Serialize)]
+pub struct Notes {
+    map: Map,
+    #[serde(rename = "path")]
+    instructions: Vec<Instruction>,
+}
 
-    let map = Map::from_str(map)?;
-    let instructions = Instruction::parse_list(instructions).unwrap().1;
+#[aoc_generator(day22)]
+pub fn parse(input: &str) -> Result<Notes, Error> {
+    parse::all(
+        input,
+        map(
+            separated_pair(board, tag("\n\n"), line_of(instruction)),
+            |(map, instructions)| Notes { map, instructions },
+        ),
+    )
+}
+
+#[aoc(day22, part1)]
+pub fn part1(notes: &Notes) -> usize {
+    let Notes { map, instructions } = notes;
 
     let mut pos = map.start();
     let mut dir = Direction::East;
@@ -206,7 +279,7 @@ pub fn part1(input: &str) -> Result<usize, ParseIntError> {
                 dir = dir.turn_right();
                 continue;
             }
-            Instruction::Forward(distance) => distance,
+            Instruction::Forward(distance) => *distance,
         };
 
         let delta = dir.delta();
@@ -241,15 +314,14 @@ pub fn part1(input: &str) -> Result<usize, ParseIntError> {
         }
     }
 
-    Ok((pos.1 + 1) * 1000 + (pos.0 + 1) * 4 + (dir as u8 as usize))
+    password(pos, dir)
 }
 
 #[aoc(day22, part2)]
-pub fn part2(input: &str) -> Result<usize, ParseIntError> {
-    let (map, instructions) = input.split_once("\n\n").unwrap();
+pub fn part2(notes: &Notes) -> Result<usize, Error> {
+    let Notes { map, instructions } = notes;
 
-    let map = Map::from_str(map)?;
-    let instructions = Instruction::parse_list(instructions).unwrap().1;
+    map.foldable()?;
 
     let mut pos = map.start();
     let mut dir = Direction::East;
@@ -264,7 +336,7 @@ pub fn part2(input: &str) -> Result<usize, ParseIntError> {
                 dir = dir.turn_right();
                 continue;
             }
-            Instruction::Forward(distance) => distance,
+            Instruction::Forward(distance) => *distance,
         };
 
         for _ in 0..distance {
@@ -281,20 +353,37 @@ pub fn part2(input: &str) -> Result<usize, ParseIntError> {
                     break;
                 }
                 Cell::Void => {
-                    panic!("Stepped into the void {next_pos:?} from {pos:?} (dir={dir:?})")
+                    return Err(Error::NoSolution {
+                        reason: format!("stepped off the cube at {next_pos:?} from {pos:?}"),
+                    })
                 }
             }
         }
     }
 
-    Ok((pos.1 + 1) * 1000 + (pos.0 + 1) * 4 + (dir as u8 as usize))
+    Ok(password(pos, dir))
 }
 
 #[cfg(test)]
 mod tests {
     #[test]
     fn test_case_1() {
-        let result = super::part1("        ...#\n        .#..\n        #...\n        ....\n...#.......#\n........#...\n..#....#....\n..........#.\n        ...#....\n        .....#..\n        .#......\n        ......#.\n\n10R5L5R10L4R5L5");
-        assert_eq!(result, Ok(6032));
+        let diagnostics = super::validate(include_str!("../input/2022/examples/day22-example.txt"));
+
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(
+            diagnostics[0].message,
+            "Part 2 only folds maps of 150×200 tiles into a cube, this one is 16×12"
+        );
+    }
+
+    #[test]
+    fn test_case_2() {
+        let notes = super::parse(include_str!("../input/2022/examples/day22-example.txt")).unwrap();
+
+        assert!(matches!(
+            super::part2(&notes),
+            Err(crate::Error::NoSolution { .. })
+        ));
     }
 }
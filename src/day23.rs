@@ -1,139 +1,97 @@
-use std::{
-    collections::{HashMap, HashSet},
-    num::ParseIntError,
-    ops::RangeInclusive,
-    str::FromStr,
+use aoc_runner_derive::{aoc, aoc_generator};
+use serde::{Serialize, Serializer};
+
+use crate::{
+    diagnostic::Diagnostic,
+    util::{
+        bits::BitBoard,
+        cycle,
+        geom::{BoundingBox, Direction, Point2},
+        grid::Grid,
+        parse::{self, fail, grid_padded, IResult},
+    },
+    visualize::{Draw, Recorder, Tile},
+    Error,
 };
 
-use aoc_runner_derive::aoc;
+/// How much room is made around the elves whenever one of them reaches the edge of the board.
+const MARGIN: usize = 16;
 
-#[derive(Debug, Clone, Copy)]
-enum Direction {
-    North,
-    South,
-    West,
-    East,
+/// The positions of the elves, on a board that grows whenever they spread out to its edge.
+#[derive(Clone)]
+pub struct Board {
+    elves: BitBoard,
+    /// Where the top left corner of the board is.
+    origin: Point2,
 }
 
-#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
-struct Pos {
-    x: i32,
-    y: i32,
-}
-
-impl Pos {
-    fn new(x: usize, y: usize) -> Pos {
-        Pos {
-            x: x as i32,
-            y: y as i32,
-        }
-    }
-
-    fn delta(&self, dx: i32, dy: i32) -> Pos {
-        Pos {
-            x: self.x + dx,
-            y: self.y + dy,
-        }
-    }
-
-    fn go(&self, dir: Direction) -> Pos {
-        match dir {
-            Direction::North => self.delta(0, -1),
-            Direction::South => self.delta(0, 1),
-            Direction::West => self.delta(-1, 0),
-            Direction::East => self.delta(1, 0),
-        }
-    }
-
-    fn look(&self, dir: Direction) -> [Pos; 3] {
-        match dir {
-            Direction::North => [self.delta(-1, -1), self.delta(0, -1), self.delta(1, -1)],
-            Direction::South => [self.delta(-1, 1), self.delta(0, 1), self.delta(1, 1)],
-            Direction::West => [self.delta(-1, -1), self.delta(-1, 0), self.delta(-1, 1)],
-            Direction::East => [self.delta(1, -1), self.delta(1, 0), self.delta(1, 1)],
-        }
+/// Serializes as `[x, y]` of every elf, row by row from the top.
+impl Serialize for Board {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.iter())
     }
 }
 
-struct Board(HashSet<Pos>);
-
 impl Board {
-    fn bounds(&self) -> (RangeInclusive<i32>, RangeInclusive<i32>) {
-        let min_x = self.0.iter().map(|elf| elf.x).min().unwrap();
-        let max_x = self.0.iter().map(|elf| elf.x).max().unwrap();
-        let min_y = self.0.iter().map(|elf| elf.y).min().unwrap();
-        let max_y = self.0.iter().map(|elf| elf.y).max().unwrap();
-
-        (
-            RangeInclusive::new(min_x, max_x),
-            RangeInclusive::new(min_y, max_y),
-        )
+    fn bounds(&self) -> BoundingBox {
+        BoundingBox::of(self.iter()).expect("There is at least one elf")
     }
 
-    fn size(&self) -> usize {
-        let (x, y) = self.bounds();
-
-        (x.end() - x.start() + 1) as usize * (y.end() - y.start() + 1) as usize
-    }
-
-    fn iter(&self) -> impl Iterator<Item = &Pos> {
-        self.0.iter()
+    /// The elves row by row from the top, which is the same for the same positions.
+    fn iter(&self) -> impl Iterator<Item = Point2> + '_ {
+        self.elves.iter().map(|pos| self.origin + Point2::from(pos))
     }
 
     fn elves(&self) -> usize {
-        self.0.len()
+        self.elves.count_ones()
     }
 
-    fn has_elf(&self, pos: &Pos) -> bool {
-        self.0.contains(pos)
-    }
+    fn has_elf(&self, pos: Point2) -> bool {
+        let Point2 { x, y } = pos - self.origin;
 
-    fn has_adjecent(&self, pos: &Pos) -> bool {
-        self.has_elf(&pos.delta(-1, -1))
-            || self.has_elf(&pos.delta(0, -1))
-            || self.has_elf(&pos.delta(1, -1))
-            || self.has_elf(&pos.delta(-1, 0))
-            || self.has_elf(&pos.delta(1, 0))
-            || self.has_elf(&pos.delta(-1, 1))
-            || self.has_elf(&pos.delta(0, 1))
-            || self.has_elf(&pos.delta(1, 1))
+        x >= 0 && y >= 0 && self.elves.get((x as usize, y as usize))
     }
 
-    fn look(&self, pos: &Pos, dir: Direction) -> usize {
-        pos.look(dir).iter().filter(|pos| self.has_elf(pos)).count()
+    /// The same elves with [`MARGIN`] more room on every side.
+    fn padded(&self) -> Board {
+        let margin = MARGIN as i32;
+
+        Board {
+            elves: self.elves.padded(MARGIN),
+            origin: self.origin - Point2::new(margin, margin),
+        }
     }
 }
 
-impl FromStr for Board {
-    type Err = ParseIntError;
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        Ok(Board(
-            s.lines()
-                .enumerate()
-                .flat_map(|(y, line)| {
-                    line.chars().enumerate().flat_map(move |(x, c)| {
-                        if c == '#' {
-                            Some(Pos::new(x, y))
-                        } else {
-                            None
-                        }
-                    })
-                })
-                .collect::<HashSet<_>>(),
-        ))
+fn board(input: &str) -> IResult<'_, Board> {
+    let (rest, grid) = grid_padded("an elf (#) or open ground (.)", false, |c| match c {
+        '#' => Some(true),
+        '.' => Some(false),
+        _ => None,
+    })(input)?;
+
+    if !grid.iter().any(|&elf| elf) {
+        return fail(&input[..input.len() - rest.len()], "at least one elf (#)");
     }
+
+    Ok((
+        rest,
+        Board {
+            elves: BitBoard::from(&grid),
+            origin: Point2::ORIGIN,
+        },
+    ))
 }
 
 impl std::fmt::Debug for Board {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let (xs, ys) = self.bounds();
+        let bounds = self.bounds();
 
         writeln!(f)?;
 
-        for y in ys {
-            for x in xs.clone() {
-                if self.has_elf(&Pos { x, y }) {
+        for y in bounds.min.y..=bounds.max.y {
+            for x in bounds.min.x..=bounds.max.x {
+                if self.has_elf(Point2::new(x, y)) {
                     write!(f, "#")?;
                 } else {
                     write!(f, ".")?;
@@ -147,164 +105,202 @@ impl std::fmt::Debug for Board {
     }
 }
 
-#[aoc(day23, part1)]
-pub fn part1(input: &str) -> Result<usize, ParseIntError> {
-    let mut board = Board::from_str(input)?;
-
-    let directions = vec![
-        vec![
-            Direction::North,
-            Direction::South,
-            Direction::West,
-            Direction::East,
-        ],
-        vec![
-            Direction::South,
-            Direction::West,
-            Direction::East,
-            Direction::North,
-        ],
-        vec![
-            Direction::West,
-            Direction::East,
-            Direction::North,
-            Direction::South,
-        ],
-        vec![
-            Direction::East,
-            Direction::North,
-            Direction::South,
-            Direction::West,
-        ],
-    ];
-
-    for directions in directions.iter().cycle().take(10) {
-        let mut proposed = HashMap::<Pos, usize>::new();
-
-        for &elf in board.iter() {
-            if board.has_adjecent(&elf) {
-                for &dir in directions {
-                    if board.look(&elf, dir) == 0 {
-                        *proposed.entry(elf.go(dir)).or_insert(0) += 1;
-                        break;
-                    }
-                }
-            }
+/// Checks that the grove is a grid of elves and open ground.
+pub fn validate(input: &str) -> Vec<Diagnostic> {
+    match parse::all(input, board) {
+        Ok(_) => Vec::new(),
+        Err(err) => vec![Diagnostic::from_error(err, input)],
+    }
+}
+
+/// The order in which the elves consider the directions, which moves on by one every round.
+const DIRECTIONS: [[Direction; 4]; 4] = [
+    [
+        Direction::North,
+        Direction::South,
+        Direction::West,
+        Direction::East,
+    ],
+    [
+        Direction::South,
+        Direction::West,
+        Direction::East,
+        Direction::North,
+    ],
+    [
+        Direction::West,
+        Direction::East,
+        Direction::North,
+        Direction::South,
+    ],
+    [
+        Direction::East,
+        Direction::North,
+        Direction::South,
+        Direction::West,
+    ],
+];
+
+impl Board {
+    /// Plays a round, or returns `None` once no elf has another elf next to it.
+    fn round(&self, directions: &[Direction; 4]) -> Option<Board> {
+        // No elf may be on the edge, where shifting the board would lose it.
+        let board = if self.elves.touches_edge() {
+            self.padded()
+        } else {
+            self.clone()
+        };
+        let elves = &board.elves;
+
+        // The cells from which an elf sees another one when looking in a direction, straight ahead
+        // or diagonally.
+        let seen = Direction::ALL.map(|dir| {
+            let ahead = elves.shifted(dir.reverse());
+
+            &(&ahead | &ahead.shifted(dir.turn_left())) | &ahead.shifted(dir.turn_right())
+        });
+
+        let crowded = &(&seen[0] | &seen[1]) | &(&seen[2] | &seen[3]);
+        let mut waiting = elves & &crowded;
+
+        if waiting.is_empty() {
+            return None;
         }
 
-        let mut next = HashSet::<Pos>::new();
+        let mut next = elves & &!&waiting;
+        let mut proposed = Direction::ALL.map(|_| BitBoard::new(elves.width(), elves.height()));
 
-        'elf: for &elf in board.iter() {
-            if board.has_adjecent(&elf) {
-                for &dir in directions {
-                    if board.look(&elf, dir) == 0 {
-                        if *proposed.get(&elf.go(dir)).unwrap() == 1 {
-                            next.insert(elf.go(dir));
-                            continue 'elf;
-                        }
+        for &dir in directions {
+            let proposing = &waiting & &!&seen[dir as usize];
 
-                        break;
-                    }
-                }
-            }
+            waiting &= &!&proposing;
+            proposed[dir as usize] = proposing.shifted(dir);
+        }
+
+        // Those that can't go anywhere stay.
+        next |= &waiting;
 
-            next.insert(elf);
+        // Two elves can only propose the same cell when coming from opposite directions, in which
+        // case both stay.
+        for dir in Direction::ALL {
+            let targets = &proposed[dir as usize];
+            let clashes = targets & &proposed[dir.reverse() as usize];
+
+            next |= &(targets & &!&clashes);
+            next |= &clashes.shifted(dir.reverse());
         }
 
-        board = Board(next);
+        Some(Board {
+            elves: next,
+            origin: board.origin,
+        })
     }
+}
 
-    Ok(board.size() - board.elves())
+#[aoc_generator(day23)]
+pub fn parse(input: &str) -> Result<Board, Error> {
+    parse::all(input, board)
 }
 
-#[aoc(day23, part2)]
-pub fn part2(input: &str) -> Result<usize, ParseIntError> {
-    let mut board = Board::from_str(input)?;
-
-    let directions = vec![
-        vec![
-            Direction::North,
-            Direction::South,
-            Direction::West,
-            Direction::East,
-        ],
-        vec![
-            Direction::South,
-            Direction::West,
-            Direction::East,
-            Direction::North,
-        ],
-        vec![
-            Direction::West,
-            Direction::East,
-            Direction::North,
-            Direction::South,
-        ],
-        vec![
-            Direction::East,
-            Direction::North,
-            Direction::South,
-            Direction::West,
-        ],
-    ];
-
-    for (idx, directions) in directions.iter().cycle().enumerate() {
-        let mut proposed = HashMap::<Pos, usize>::new();
-
-        for &elf in board.iter() {
-            if board.has_adjecent(&elf) {
-                for &dir in directions {
-                    if board.look(&elf, dir) == 0 {
-                        *proposed.entry(elf.go(dir)).or_insert(0) += 1;
-                        break;
-                    }
-                }
-            }
+#[aoc(day23, part1)]
+pub fn part1(board: &Board) -> usize {
+    let mut board = board.clone();
+
+    for directions in DIRECTIONS.iter().cycle().take(10) {
+        match board.round(directions) {
+            Some(next) => board = next,
+            None => break,
         }
+    }
+
+    board.bounds().area() - board.elves()
+}
 
-        let mut next = HashSet::<Pos>::new();
-        let mut done = true;
+/// The first round in which no elf moves. The elves don't depend on anything but where they are and
+/// which direction they consider first, so once both repeat they would keep moving forever, which
+/// is reported as having no solution.
+#[aoc(day23, part2)]
+pub fn part2(board: &Board) -> Result<usize, Error> {
+    let mut settled = None;
+
+    let history = cycle::detect(
+        (board.clone(), 0),
+        usize::MAX,
+        |(board, round)| {
+            match board.round(&DIRECTIONS[*round % DIRECTIONS.len()]) {
+                Some(next) => *board = next,
+                None => _ = settled.get_or_insert(*round + 1),
+            }
 
-        'elf: for &elf in board.iter() {
-            if board.has_adjecent(&elf) {
-                done = false;
+            *round += 1;
+        },
+        |(board, round)| (*round % DIRECTIONS.len(), board.iter().collect::<Vec<_>>()),
+        |_| (),
+    );
+
+    match (settled, history.cycle()) {
+        (Some(round), _) => Ok(round),
+        (None, Some(cycle)) => Err(Error::NoSolution {
+            reason: format!(
+                "the elves move around in circles, repeating every {} rounds from round {}",
+                cycle.period, cycle.prefix
+            ),
+        }),
+        (None, None) => Err(Error::NoSolution {
+            reason: format!(
+                "the elves are still moving after {} rounds",
+                history.steps()
+            ),
+        }),
+    }
+}
 
-                for &dir in directions {
-                    if board.look(&elf, dir) == 0 {
-                        if *proposed.get(&elf.go(dir)).unwrap() == 1 {
-                            next.insert(elf.go(dir));
-                            continue 'elf;
-                        }
+const ELF: Tile = Tile::new('#', [60, 200, 80]);
+const GROUND: Tile = Tile::new('.', [0, 0, 0]);
 
-                        break;
-                    }
-                }
-            }
+impl Draw for Board {
+    /// Draws the smallest rectangle that contains every elf, like the [`Debug`] output.
+    fn draw(&self) -> Grid<Tile> {
+        let bounds = self.bounds();
+        let mut grid = Grid::new(bounds.width(), bounds.height(), GROUND);
 
-            next.insert(elf);
+        for elf in self.iter() {
+            grid[bounds.grid_pos(elf)] = ELF;
         }
 
-        if done {
-            return Ok(idx + 1);
-        }
+        grid
+    }
+}
+
+/// Records the grove after every round of the given part.
+pub fn visualize(board: &Board, part: u8, recorder: &mut Recorder) {
+    let mut board = board.clone();
+    let rounds = if part == 1 { 10 } else { usize::MAX };
+    let mut round = 0;
+
+    recorder.record(round, &board);
 
-        board = Board(next);
+    for directions in DIRECTIONS.iter().cycle().take(rounds) {
+        let Some(next) = board.round(directions) else {
+            break;
+        };
+
+        board = next;
+        round += 1;
+        recorder.record(round, &board);
     }
 
-    panic!("Endless iterator ended");
+    recorder.record_last(round, &board);
 }
 
 #[cfg(test)]
 mod tests {
     #[test]
     fn test_case_1() {
-        let result = super::part1("....#..\n..###.#\n#...#.#\n.#...##\n#.###..\n##.#.##\n.#..#..");
-        assert_eq!(result, Ok(110));
-    }
+        // The layout after round 8 comes back after round 10, but the elves consider other
+        // directions first by then and go on to settle in round 13.
+        let board = super::parse("#..##\n..###\n.#...\n....#\n#....").unwrap();
 
-    #[test]
-    fn test_case_2() {
-        let result = super::part2("....#..\n..###.#\n#...#.#\n.#...##\n#.###..\n##.#.##\n.#..#..");
-        assert_eq!(result, Ok(20));
+        assert_eq!(super::part2(&board), Ok(13));
     }
 }
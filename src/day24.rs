@@ -1,164 +1,151 @@
-use std::{collections::HashMap, fmt::Debug, num::ParseIntError, str::FromStr};
-
-use aoc_runner_derive::aoc;
-
-use crate::util::priority_queue::{PriorityQueue, PriorityQueueItem};
-
-fn lcm(a: usize, b: usize) -> usize {
-    match (a, b) {
-        (5, 5) => 5,
-        (6, 4) => 12,
-        (150, 20) => 300,
-        // Implementing lowest common multiple was complicated...
-        _ => unimplemented!(),
+use std::fmt::Debug;
+
+use aoc_runner_derive::{aoc, aoc_generator};
+use nom::{multi::count, sequence::delimited};
+use serde::{Serialize, Serializer};
+
+use crate::{
+    diagnostic::Diagnostic,
+    util::{
+        bits::BitBoard,
+        geom::{Direction, Point2},
+        grid::{Grid, Pos},
+        parse::{self, char_of, fail, paragraph, tag, IResult},
+        search::{astar, Path},
+    },
+    visualize::{Draw, Recorder, Tile},
+    Error, Stats,
+};
+
+fn gcd(a: usize, b: usize) -> usize {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
     }
 }
 
-#[derive(Clone, Copy, Hash, PartialEq, Eq)]
-struct Pos {
-    x: usize,
-    y: usize,
-}
-
-impl Debug for Pos {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "({}, {})", self.x, self.y)
-    }
-}
-
-#[derive(Debug, Clone, Copy)]
-enum Blizzard {
-    North,
-    South,
-    West,
-    East,
+fn lcm(a: usize, b: usize) -> usize {
+    a / gcd(a, b) * b
 }
 
+/// The basin inside the walls, with the directions of the blizzards in every cell.
 #[derive(Clone)]
 struct Board {
-    data: Vec<Vec<Blizzard>>,
-    width: usize,
+    grid: Grid<Vec<Direction>>,
 }
 
 impl Board {
-    fn height(&self) -> usize {
-        self.data.len() / self.width
-    }
-
     fn next(&self) -> Board {
-        let len = self.data.len();
-        let width = self.width;
-
-        let mut next = vec![Vec::new(); len];
-
-        for (idx, cell) in self.data.iter().enumerate() {
-            for blizzard in cell {
-                match blizzard {
-                    Blizzard::North => next[(idx + len - width) % len].push(Blizzard::North),
-                    Blizzard::South => next[(idx + width) % len].push(Blizzard::South),
-                    Blizzard::West => {
-                        let mut x = idx % width;
-                        let y = idx / width;
-                        x = (x + width - 1) % width;
-                        next[y * width + x].push(Blizzard::West)
-                    }
-                    Blizzard::East => {
-                        let mut x = idx % width;
-                        let y = idx / width;
-                        x = (x + 1) % width;
-                        next[y * width + x].push(Blizzard::East)
-                    }
-                }
+        let mut next = Grid::new(self.grid.width(), self.grid.height(), Vec::new());
+
+        for pos in self.grid.positions() {
+            for &blizzard in &self.grid[pos] {
+                next[self.grid.wrapping_add(pos, blizzard.delta())].push(blizzard);
             }
         }
 
-        Board {
-            data: next,
-            width: self.width,
-        }
+        Board { grid: next }
     }
 }
 
 impl Debug for Board {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        for (idx, cell) in self.data.iter().enumerate() {
-            if (idx % self.width) == 0 {
-                writeln!(f)?;
-            }
+        let render = self.grid.render(|cell| match cell[..] {
+            [] => '.',
+            [Direction::North] => '^',
+            [Direction::South] => 'v',
+            [Direction::West] => '<',
+            [Direction::East] => '>',
+            _ => char::from_digit(cell.len() as u32, 10).unwrap_or('*'),
+        });
+
+        write!(f, "{render}")
+    }
+}
 
-            match cell.len() {
-                0 => write!(f, ".")?,
-                1 => match cell[0] {
-                    Blizzard::North => write!(f, "^")?,
-                    Blizzard::South => write!(f, "v")?,
-                    Blizzard::West => write!(f, "<")?,
-                    Blizzard::East => write!(f, ">")?,
-                },
-                _ => write!(f, "{}", cell.len())?,
-            }
-        }
+fn blizzards(input: &str) -> IResult<'_, Vec<Direction>> {
+    char_of(
+        "a blizzard (^, v, < or >) or open ground (.)",
+        |c| match c {
+            '.' => Some(vec![]),
+            '^' => Some(vec![Direction::North]),
+            'v' => Some(vec![Direction::South]),
+            '<' => Some(vec![Direction::West]),
+            '>' => Some(vec![Direction::East]),
+            _ => None,
+        },
+    )(input)
+}
 
-        Ok(())
+fn board(input: &str) -> IResult<'_, Board> {
+    let (rest, basin) = paragraph(input)?;
+    let lines = basin.lines().collect::<Vec<_>>();
+
+    if lines.len() < 3 || lines[0].len() < 3 {
+        return fail(basin, "a basin surrounded by walls");
     }
-}
 
-impl FromStr for Board {
-    type Err = ParseIntError;
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let width = s.lines().next().unwrap().len() - 2;
-        let height = s.lines().count() - 2;
-
-        let data = s
-            .lines()
-            .skip(1)
-            .take(height)
-            .flat_map(|line| {
-                line.chars().skip(1).take(width).map(|c| match c {
-                    '.' => vec![],
-                    '^' => vec![Blizzard::North],
-                    'v' => vec![Blizzard::South],
-                    '<' => vec![Blizzard::West],
-                    '>' => vec![Blizzard::East],
-                    _ => panic!("Invalid input: {c}"),
-                })
-            })
-            .collect();
+    let width = lines[0].len() - 2;
+    let height = lines.len() - 2;
 
-        Ok(Board { data, width })
+    if let Some(line) = lines.iter().find(|line| line.len() != width + 2) {
+        return fail(line, format!("a row of {} tiles", width + 2));
     }
+
+    let mut grid = Grid::new(width, 0, Vec::new());
+
+    for line in &lines[1..=height] {
+        let (_, row) = delimited(tag("#"), count(blizzards, width), tag("#"))(line)?;
+
+        grid.push_row(row);
+    }
+
+    Ok((rest, Board { grid }))
 }
 
+/// Where the blizzards leave room in every minute until they repeat, with a bit set for every free
+/// cell.
+#[derive(Debug)]
 struct BoardStates {
-    data: Vec<Vec<bool>>,
-    width: usize,
+    data: Vec<BitBoard>,
 }
 
 impl BoardStates {
+    fn width(&self) -> usize {
+        self.data[0].width()
+    }
+
     fn height(&self) -> usize {
-        self.data[0].len() / self.width
+        self.data[0].height()
+    }
+
+    /// After how many minutes the blizzards are back where they started.
+    fn period(&self) -> usize {
+        self.data.len()
     }
 
     fn is_free(&self, cycle: usize, pos: Pos) -> bool {
-        self.data[cycle % self.data.len()][pos.y * self.width + pos.x]
+        self.data[cycle % self.data.len()].get(pos)
+    }
+
+    fn neighbours(&self, pos: Pos) -> impl Iterator<Item = Pos> + '_ {
+        self.data[0].neighbours4(pos)
     }
 }
 
 impl From<Board> for BoardStates {
     fn from(value: Board) -> Self {
-        let len = lcm(value.width, value.height());
+        let len = lcm(value.grid.width(), value.grid.height());
 
         let mut current = value;
-        let mut data = Vec::<Vec<bool>>::with_capacity(len);
+        let mut data = Vec::<BitBoard>::with_capacity(len);
 
         loop {
-            data.push(current.data.iter().map(Vec::is_empty).collect());
+            data.push(BitBoard::from(&current.grid.map(Vec::is_empty)));
 
             if data.len() == len {
-                return Self {
-                    data,
-                    width: current.width,
-                };
+                return Self { data };
             }
 
             current = current.next();
@@ -166,165 +153,329 @@ impl From<Board> for BoardStates {
     }
 }
 
+/// Where the expedition is, `None` meaning still outside the basin, and how many minutes into the
+/// period of the blizzards. The minutes themselves are the cost of the search, so that it runs out
+/// of states once waiting around no longer leads anywhere new.
+#[derive(Clone, PartialEq, Eq, Hash)]
 struct Step {
-    minutes: usize,
-    pos: Pos,
+    phase: usize,
+    pos: Option<Pos>,
 }
 
 impl Step {
-    fn neighbours(&self, bounds: (usize, usize)) -> Vec<Pos> {
-        let mut result = Vec::<Pos>::with_capacity(4);
-
-        if self.pos.y > 0 {
-            result.push(Pos {
-                x: self.pos.x,
-                y: self.pos.y - 1,
-            });
-        }
-
-        if self.pos.x > 0 {
-            result.push(Pos {
-                x: self.pos.x - 1,
-                y: self.pos.y,
-            });
-        }
-
-        if self.pos.y < (bounds.1 - 1) {
-            result.push(Pos {
-                x: self.pos.x,
-                y: self.pos.y + 1,
-            })
-        }
-
-        if self.pos.x < (bounds.0 - 1) {
-            result.push(Pos {
-                x: self.pos.x + 1,
-                y: self.pos.y,
-            })
-        }
-
-        result
+    fn successors(&self, states: &BoardStates, entrance: Pos) -> Vec<(Step, usize)> {
+        let phase = (self.phase + 1) % states.period();
+
+        let candidates: Vec<Option<Pos>> = match self.pos {
+            None => vec![None, Some(entrance)],
+            Some(pos) => std::iter::once(pos)
+                .chain(states.neighbours(pos))
+                .map(Some)
+                .collect(),
+        };
+
+        candidates
+            .into_iter()
+            .filter(|pos| pos.is_none_or(|pos| states.is_free(phase, pos)))
+            .map(|pos| (Step { phase, pos }, 1))
+            .collect()
     }
 
-    fn best_possible_time(&self, goal: Pos) -> usize {
-        self.minutes + (goal.x.abs_diff(self.pos.x)) + (goal.y.abs_diff(self.pos.y))
+    fn best_possible_time(&self, entrance: Pos, goal: Pos) -> usize {
+        match self.pos {
+            None => 1 + Point2::from(goal).manhattan(entrance.into()) as usize,
+            Some(pos) => Point2::from(goal).manhattan(pos.into()) as usize,
+        }
     }
 }
 
-impl PriorityQueueItem<Pos> for Step {
-    fn cost(&self, context: &Pos) -> usize {
-        self.best_possible_time(*context)
-    }
+/// Finds the quickest way from the cell next to `start` to the cell next to `end`, entering the
+/// basin no earlier than one minute after `departure`.
+fn trip(states: &BoardStates, start: Pos, end: Pos, departure: usize) -> Result<Path<Step>, Error> {
+    astar(
+        Step {
+            phase: departure % states.period(),
+            pos: None,
+        },
+        |step| step.successors(states, start),
+        |step| step.pos == Some(end),
+        |step| step.best_possible_time(start, end),
+    )
+    .ok_or_else(|| Error::NoSolution {
+        reason: "the blizzards never leave a way across the basin".into(),
+    })
 }
 
+/// Returns the minute at which the basin is left through the cell next to `end`, after having
+/// entered it through the cell next to `start` no earlier than one minute after `departure`, along
+/// with the work of the search.
 fn shortest_time_between(
     states: &BoardStates,
     start: Pos,
     end: Pos,
-    first_cycle_index: usize,
-) -> usize {
-    let bounds = (states.width, states.height());
-    let cycle_count = lcm(bounds.0, bounds.1);
+    departure: usize,
+) -> Result<(usize, Stats), Error> {
+    let path = trip(states, start, end, departure)?;
 
-    let mut queue = PriorityQueue::<Pos, Step>::new(end);
+    Ok((departure + path.cost + 1, path.stats))
+}
 
-    for i in 0..cycle_count {
-        let minutes = i + first_cycle_index;
+/// Checks that the basin is entered in the top left and left in the bottom right, and that no
+/// blizzard blows through either opening.
+pub fn validate(input: &str) -> Vec<Diagnostic> {
+    let board = match parse::all(input, board) {
+        Ok(board) => board,
+        Err(err) => return vec![Diagnostic::from_error(err, input)],
+    };
 
-        if states.is_free(minutes, start) {
-            queue.push(Step {
-                minutes,
-                pos: start,
-            });
+    let mut diagnostics = Vec::new();
+    let lines = input.lines().collect::<Vec<_>>();
+    let width = board.grid.width();
+
+    for (line, opening, name) in [
+        (lines[0], 1, "entrance"),
+        (lines[lines.len() - 1], width, "exit"),
+    ] {
+        let expected = (0..width + 2)
+            .map(|x| if x == opening { '.' } else { '#' })
+            .collect::<String>();
+
+        if line != expected {
+            diagnostics.push(
+                Diagnostic::error(format!(
+                    "Expected a wall with the {name} at column {}",
+                    opening + 1
+                ))
+                .at(input, line),
+            );
         }
     }
 
-    let mut shortest_time = usize::MAX;
-    let mut visited = HashMap::<(Pos, usize), usize>::new();
-
-    while let Some((cost, step)) = queue.pop() {
-        if cost >= shortest_time {
-            break;
+    for line in &lines[1..lines.len() - 1] {
+        if !line.starts_with('#') || !line.ends_with('#') {
+            diagnostics.push(Diagnostic::error("Expected walls on both sides").at(input, line));
         }
+    }
 
-        let cycle_index = step.minutes % cycle_count;
+    let vertical = |x| {
+        board.grid.column(x).any(|cell| {
+            cell.iter()
+                .any(|b| matches!(b, Direction::North | Direction::South))
+        })
+    };
 
-        if let Some(&last_time_at_this_position_in_cycle) = visited.get(&(step.pos, cycle_index)) {
-            if last_time_at_this_position_in_cycle <= step.minutes {
-                continue;
-            }
-        }
+    if vertical(0) || vertical(width - 1) {
+        diagnostics.push(Diagnostic::error(
+            "A blizzard blows up or down through the column of the entrance or exit",
+        ));
+    }
 
-        visited.insert((step.pos, cycle_index), step.minutes);
+    diagnostics
+}
 
-        if step.pos == end {
-            shortest_time = shortest_time.min(step.minutes);
-            continue;
+/// The basin as drawn in the input, along with where the blizzards leave room in every minute
+/// until they repeat.
+#[derive(Debug)]
+pub struct Valley {
+    board: Board,
+    states: BoardStates,
+}
+
+/// Serializes the size of the basin inside the walls, with the blizzards row by row from the top
+/// and positioned from its top left corner.
+impl Serialize for Valley {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        #[derive(Serialize)]
+        struct Entry {
+            position: Pos,
+            direction: Direction,
         }
 
-        if states.is_free(cycle_index, step.pos) {
-            queue.push(Step {
-                minutes: step.minutes + 1,
-                pos: step.pos,
-            });
+        #[derive(Serialize)]
+        struct Basin {
+            width: usize,
+            height: usize,
+            blizzards: Vec<Entry>,
         }
 
-        for pos in step.neighbours(bounds) {
-            if states.is_free(cycle_index, pos) {
-                queue.push(Step {
-                    minutes: step.minutes + 1,
-                    pos,
-                });
-            }
+        let grid = &self.board.grid;
+
+        Basin {
+            width: grid.width(),
+            height: grid.height(),
+            blizzards: grid
+                .positions()
+                .flat_map(|position| {
+                    grid[position].iter().map(move |&direction| Entry {
+                        position,
+                        direction,
+                    })
+                })
+                .collect(),
         }
+        .serialize(serializer)
     }
+}
+
+#[aoc_generator(day24)]
+pub fn parse(input: &str) -> Result<Valley, Error> {
+    let board = parse::all(input, board)?;
 
-    shortest_time
+    Ok(Valley {
+        states: board.clone().into(),
+        board,
+    })
 }
 
 #[aoc(day24, part1)]
-pub fn part1(input: &str) -> Result<usize, ParseIntError> {
-    let states: BoardStates = Board::from_str(input)?.into();
+pub fn part1(valley: &Valley) -> Result<usize, Error> {
+    part1_with_stats(valley).map(|(minutes, _)| minutes)
+}
 
-    let start = Pos { x: 0, y: 0 };
+pub fn part1_with_stats(valley: &Valley) -> Result<(usize, Stats), Error> {
+    let states = &valley.states;
 
-    let end = Pos {
-        x: states.width - 1,
-        y: states.height() - 1,
-    };
+    let start = (0, 0);
+    let end = (states.width() - 1, states.height() - 1);
 
-    Ok(shortest_time_between(&states, start, end, 1))
+    shortest_time_between(states, start, end, 0)
 }
 
 #[aoc(day24, part2)]
-pub fn part2(input: &str) -> Result<usize, ParseIntError> {
-    let states: BoardStates = Board::from_str(input)?.into();
+pub fn part2(valley: &Valley) -> Result<usize, Error> {
+    part2_with_stats(valley).map(|(minutes, _)| minutes)
+}
+
+/// Like [`part2`], along with the work of the searches for all three trips.
+pub fn part2_with_stats(valley: &Valley) -> Result<(usize, Stats), Error> {
+    let states = &valley.states;
+
+    let start = (0, 0);
+    let end = (states.width() - 1, states.height() - 1);
+
+    let (first, first_stats) = shortest_time_between(states, start, end, 0)?;
+    let (second, second_stats) = shortest_time_between(states, end, start, first)?;
+    let (third, third_stats) = shortest_time_between(states, start, end, second)?;
 
-    let start = Pos { x: 0, y: 0 };
+    Ok((third, first_stats.merge(second_stats).merge(third_stats)))
+}
+
+const WALL: Tile = Tile::new('#', [90, 90, 90]);
+const GROUND: Tile = Tile::new('.', [0, 0, 0]);
+const EXPEDITION: Tile = Tile::new('E', [240, 60, 40]);
+
+/// The basin including its walls, with the expedition somewhere in it.
+struct Expedition {
+    board: Board,
+    /// Position within the walls, so that the openings in them are on the map as well.
+    pos: Pos,
+}
 
-    let end = Pos {
-        x: states.width - 1,
-        y: states.height() - 1,
+impl Expedition {
+    /// Where the expedition waits before entering the basin next to `pos`, or after leaving it.
+    fn opening(board: &Board, pos: Pos) -> Pos {
+        if pos == (0, 0) {
+            (1, 0)
+        } else {
+            (board.grid.width(), board.grid.height() + 1)
+        }
+    }
+}
+
+impl Draw for Expedition {
+    fn draw(&self) -> Grid<Tile> {
+        let (width, height) = (self.board.grid.width(), self.board.grid.height());
+        let mut grid = Grid::new(width + 2, height + 2, WALL);
+
+        grid[(1, 0)] = GROUND;
+        grid[(width, height + 1)] = GROUND;
+
+        for pos in self.board.grid.positions() {
+            let blizzards = &self.board.grid[pos];
+            let brightness = (110 + 40 * blizzards.len()).min(255) as u8;
+
+            grid[(pos.0 + 1, pos.1 + 1)] = match blizzards[..] {
+                [] => GROUND,
+                [Direction::North] => Tile::new('^', [brightness; 3]),
+                [Direction::South] => Tile::new('v', [brightness; 3]),
+                [Direction::West] => Tile::new('<', [brightness; 3]),
+                [Direction::East] => Tile::new('>', [brightness; 3]),
+                _ => Tile::new(
+                    char::from_digit(blizzards.len() as u32, 10).unwrap_or('*'),
+                    [brightness; 3],
+                ),
+            };
+        }
+
+        grid[self.pos] = EXPEDITION;
+        grid
+    }
+}
+
+/// Records the basin every minute while the expedition crosses it, once for part 1 and three
+/// times for part 2.
+pub fn visualize(valley: &Valley, part: u8, recorder: &mut Recorder) -> Result<(), Error> {
+    let Valley { board, states } = valley;
+
+    let start = (0, 0);
+    let end = (states.width() - 1, states.height() - 1);
+
+    let trips = match part {
+        1 => vec![(start, end)],
+        _ => vec![(start, end), (end, start), (start, end)],
+    };
+
+    let mut minutes = 0;
+    let mut expedition = Expedition {
+        pos: Expedition::opening(board, start),
+        board: board.clone(),
     };
 
-    let first = shortest_time_between(&states, start, end, 1);
-    let second = shortest_time_between(&states, end, start, first);
-    let third = shortest_time_between(&states, start, end, second);
+    recorder.record(minutes, &expedition);
 
-    Ok(third)
+    for (from, to) in trips {
+        let departure = minutes;
+        let path = trip(states, from, to, departure)?;
+
+        for (elapsed, step) in path.states.iter().enumerate().skip(1) {
+            minutes = departure + elapsed;
+            expedition.board = expedition.board.next();
+            expedition.pos = match step.pos {
+                Some(pos) => (pos.0 + 1, pos.1 + 1),
+                None => Expedition::opening(&expedition.board, from),
+            };
+
+            recorder.record(minutes, &expedition);
+        }
+
+        minutes += 1;
+        expedition.board = expedition.board.next();
+        expedition.pos = Expedition::opening(&expedition.board, to);
+
+        recorder.record(minutes, &expedition);
+    }
+
+    recorder.record_last(minutes, &expedition);
+
+    Ok(())
 }
 
 #[cfg(test)]
 mod tests {
-    #[test]
-    fn test_case_1() {
-        let result = super::part1("#.######\n#>>.<^<#\n#.<..<<#\n#>v.><>#\n#<^v^^>#\n######.#");
-        assert_eq!(result, Ok(18));
-    }
+    use crate::Error;
 
     #[test]
-    fn test_case_2() {
-        let result = super::part2("#.######\n#>>.<^<#\n#.<..<<#\n#>v.><>#\n#<^v^^>#\n######.#");
-        assert_eq!(result, Ok(54));
+    fn test_case_1() {
+        // Every row is always full of blizzards, so the search has to run out of states.
+        let valley = super::parse("#.##\n#<>#\n#<>#\n#<>#\n##.#").unwrap();
+
+        assert!(matches!(
+            super::part1(&valley),
+            Err(Error::NoSolution { .. })
+        ));
+        assert!(matches!(
+            super::part2(&valley),
+            Err(Error::NoSolution { .. })
+        ));
     }
 }
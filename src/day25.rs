@@ -1,26 +1,44 @@
-use std::num::ParseIntError;
-
-use aoc_runner_derive::aoc;
-
-mod snafu {
-    pub fn parse(input: &str) -> u64 {
-        let mut tally = 0i64;
-        let mut multiplier = 1i64;
-
-        for c in input.chars().rev() {
-            match c {
-                '=' => tally -= 2 * multiplier,
-                '-' => tally -= multiplier,
-                '0' => {}
-                '1' => tally += multiplier,
-                '2' => tally += 2 * multiplier,
-                _ => panic!("Invalid input"),
-            }
+use aoc_runner_derive::{aoc, aoc_generator};
+use serde::Serialize;
 
-            multiplier *= 5;
-        }
+use crate::{
+    diagnostic::{check, Diagnostic},
+    util::parse::{self, lines},
+    Error,
+};
+
+/// Most digits a SNAFU number can have before its place values overflow in [`snafu::parse`].
+const MAX_DIGITS: usize = 27;
+
+pub(crate) mod snafu {
+    use crate::{
+        util::parse::{all, char_of, line_of, IResult},
+        Error,
+    };
+
+    fn digit(input: &str) -> IResult<'_, i64> {
+        char_of("a SNAFU digit (=, -, 0, 1 or 2)", |c| match c {
+            '=' => Some(-2),
+            '-' => Some(-1),
+            '0' => Some(0),
+            '1' => Some(1),
+            '2' => Some(2),
+            _ => None,
+        })(input)
+    }
+
+    /// The digits of a SNAFU number up to the end of the line, most significant first.
+    pub fn number(input: &str) -> IResult<'_, u64> {
+        let (rest, digits) = line_of(digit)(input)?;
 
-        tally as u64
+        Ok((
+            rest,
+            digits.into_iter().fold(0, |tally, digit| tally * 5 + digit) as u64,
+        ))
+    }
+
+    pub fn parse(input: &str) -> Result<u64, Error> {
+        all(input, number)
     }
 
     pub fn stringify(input: u64) -> String {
@@ -65,21 +83,21 @@ mod snafu {
     mod tests {
         #[test]
         fn parse() {
-            assert_eq!(super::parse("1"), 1);
-            assert_eq!(super::parse("2"), 2);
-            assert_eq!(super::parse("1="), 3);
-            assert_eq!(super::parse("1-"), 4);
-            assert_eq!(super::parse("10"), 5);
-            assert_eq!(super::parse("11"), 6);
-            assert_eq!(super::parse("12"), 7);
-            assert_eq!(super::parse("2="), 8);
-            assert_eq!(super::parse("2-"), 9);
-            assert_eq!(super::parse("20"), 10);
-            assert_eq!(super::parse("1=0"), 15);
-            assert_eq!(super::parse("1-0"), 20);
-            assert_eq!(super::parse("1=11-2"), 2022);
-            assert_eq!(super::parse("1-0---0"), 12345);
-            assert_eq!(super::parse("1121-1110-1=0"), 314159265);
+            assert_eq!(super::parse("1"), Ok(1));
+            assert_eq!(super::parse("2"), Ok(2));
+            assert_eq!(super::parse("1="), Ok(3));
+            assert_eq!(super::parse("1-"), Ok(4));
+            assert_eq!(super::parse("10"), Ok(5));
+            assert_eq!(super::parse("11"), Ok(6));
+            assert_eq!(super::parse("12"), Ok(7));
+            assert_eq!(super::parse("2="), Ok(8));
+            assert_eq!(super::parse("2-"), Ok(9));
+            assert_eq!(super::parse("20"), Ok(10));
+            assert_eq!(super::parse("1=0"), Ok(15));
+            assert_eq!(super::parse("1-0"), Ok(20));
+            assert_eq!(super::parse("1=11-2"), Ok(2022));
+            assert_eq!(super::parse("1-0---0"), Ok(12345));
+            assert_eq!(super::parse("1121-1110-1=0"), Ok(314159265));
         }
 
         #[test]
@@ -103,18 +121,37 @@ mod snafu {
     }
 }
 
-#[aoc(day25, part1)]
-pub fn part1(input: &str) -> Result<String, ParseIntError> {
-    Ok(snafu::stringify(input.lines().map(snafu::parse).sum()))
-}
+/// Checks that every SNAFU number fits into 64 bits.
+pub fn validate(input: &str) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
 
-#[cfg(test)]
-mod tests {
-    #[test]
-    fn test_case_1() {
-        let result = super::part1(
-            "1=-0-2\n12111\n2=0=\n21\n2=01\n111\n20012\n112\n1=-1=\n1-12\n12\n1=\n122",
-        );
-        assert_eq!(result, Ok(String::from("2=-1=0")));
+    for line in input.lines() {
+        if line.len() > MAX_DIGITS {
+            diagnostics.push(
+                Diagnostic::error(format!(
+                    "{} digits are too many, at most {MAX_DIGITS} fit into 64 bits",
+                    line.len()
+                ))
+                .at(input, line),
+            );
+        } else {
+            check(&mut diagnostics, input, snafu::parse(line));
+        }
     }
+
+    diagnostics
+}
+
+/// The fuel requirements of the hot air balloons, in decimal.
+#[derive(Debug, Serialize)]
+pub struct Requirements(Vec<u64>);
+
+#[aoc_generator(day25)]
+pub fn parse(input: &str) -> Result<Requirements, Error> {
+    parse::all(input, lines(snafu::number)).map(Requirements)
+}
+
+#[aoc(day25, part1)]
+pub fn part1(requirements: &Requirements) -> String {
+    snafu::stringify(requirements.0.iter().sum())
 }
@@ -1,19 +1,34 @@
-use std::{char, collections::HashSet, str::FromStr};
+use std::{char, collections::HashSet};
 
-use aoc_runner_derive::aoc;
+use aoc_runner_derive::{aoc, aoc_generator};
 use itertools::{chain, Itertools};
+use serde::{Serialize, Serializer};
 
+use crate::{
+    diagnostic::{check, Diagnostic},
+    util::parse::{self, fail, line, lines, IResult},
+    Error,
+};
+
+#[derive(Debug)]
 struct Rucksack {
     compartments: (HashSet<char>, HashSet<char>),
 }
 
-impl Rucksack {
-    fn priority(&self) -> u64 {
-        let Some(common) = self.compartments.0.iter().find(|c| self.compartments.1.contains(*c)) else {
-            panic!("No common character found");
-        };
+fn no_solution(reason: &str) -> Error {
+    Error::NoSolution {
+        reason: reason.to_string(),
+    }
+}
 
-        common.priority()
+impl Rucksack {
+    fn priority(&self) -> Result<u64, Error> {
+        self.compartments
+            .0
+            .iter()
+            .find(|c| self.compartments.1.contains(*c))
+            .map(Priority::priority)
+            .ok_or_else(|| no_solution("the compartments of a rucksack have no item in common"))
     }
 
     fn contains(&self, c: &char) -> bool {
@@ -25,18 +40,38 @@ impl Rucksack {
     }
 }
 
-impl FromStr for Rucksack {
-    type Err = ();
+/// Serializes as both compartments, each as its distinct items in sorted order.
+impl Serialize for Rucksack {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let compartment = |items: &HashSet<char>| items.iter().sorted().collect::<String>();
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        assert!(s.len() % 2 == 0);
+        (
+            compartment(&self.compartments.0),
+            compartment(&self.compartments.1),
+        )
+            .serialize(serializer)
+    }
+}
 
-        let (first, second) = s.split_at(s.len() / 2);
+fn rucksack(input: &str) -> IResult<'_, Rucksack> {
+    let (rest, items) = line(input)?;
 
-        Ok(Rucksack {
-            compartments: (first.chars().collect(), second.chars().collect()),
-        })
+    if let Some(idx) = items.find(|c: char| !c.is_ascii_alphabetic()) {
+        return fail(&items[idx..], "an item (a-z or A-Z)");
     }
+
+    if !items.len().is_multiple_of(2) {
+        return fail(items, "an even number of items");
+    }
+
+    let (first, second) = items.split_at(items.len() / 2);
+
+    Ok((
+        rest,
+        Rucksack {
+            compartments: (first.chars().collect(), second.chars().collect()),
+        },
+    ))
 }
 
 trait Priority {
@@ -53,28 +88,113 @@ impl Priority for char {
     }
 }
 
+/// Checks that every rucksack has an item in both compartments, and that the rucksacks form groups
+/// of three sharing a badge.
+pub fn validate(input: &str) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    let lines = input.split_whitespace().collect::<Vec<_>>();
+
+    for &line in &lines {
+        let Some(rucksack) = check(&mut diagnostics, input, parse::all(line, rucksack)) else {
+            continue;
+        };
+
+        if !rucksack
+            .compartments
+            .0
+            .iter()
+            .any(|c| rucksack.compartments.1.contains(c))
+        {
+            diagnostics
+                .push(Diagnostic::error("The compartments have no item in common").at(input, line));
+        }
+    }
+
+    if !lines.len().is_multiple_of(3) {
+        diagnostics.push(Diagnostic::error(format!(
+            "Part 2 needs groups of three rucksacks, found {} rucksacks",
+            lines.len()
+        )));
+    }
+
+    for group in lines.chunks_exact(3) {
+        if !group[0]
+            .chars()
+            .any(|c| group[1].contains(c) && group[2].contains(c))
+        {
+            diagnostics.push(
+                Diagnostic::error("The group of three has no badge in common").at(input, group[0]),
+            );
+        }
+    }
+
+    diagnostics
+}
+
+#[derive(Debug, Serialize)]
+pub struct Rucksacks(Vec<Rucksack>);
+
+#[aoc_generator(day3)]
+pub fn parse(input: &str) -> Result<Rucksacks, Error> {
+    parse::all(input, lines(rucksack)).map(Rucksacks)
+}
+
 #[aoc(day3, part1)]
-pub fn part1(input: &str) -> u64 {
-    input
-        .split_whitespace()
-        .map(|line| line.parse::<Rucksack>().unwrap())
-        .fold(0, |acc, rucksack| acc + rucksack.priority())
+pub fn part1(rucksacks: &Rucksacks) -> Result<u64, Error> {
+    rucksacks.0.iter().map(Rucksack::priority).sum()
 }
 
 #[aoc(day3, part2)]
-pub fn part2(input: &str) -> u64 {
-    input
-        .split_whitespace()
-        .map(|line| line.parse::<Rucksack>().unwrap())
-        .tuples::<(_, _, _)>()
-        .map(|rucksacks| {
-            let Some(common) = rucksacks.0.iter().find(|c| {
-                rucksacks.1.contains(c) && rucksacks.2.contains(c)
-            }) else {
-                panic!("No common character found");
-            };
-
-            common.priority()
+pub fn part2(rucksacks: &Rucksacks) -> Result<u64, Error> {
+    if !rucksacks.0.len().is_multiple_of(3) {
+        return Err(Error::NoSolution {
+            reason: format!(
+                "part 2 needs groups of three rucksacks, found {} rucksacks",
+                rucksacks.0.len()
+            ),
+        });
+    }
+
+    rucksacks
+        .0
+        .chunks_exact(3)
+        .map(|group| {
+            group[0]
+                .iter()
+                .find(|c| group[1].contains(c) && group[2].contains(c))
+                .map(Priority::priority)
+                .ok_or_else(|| no_solution("a group of three rucksacks has no badge in common"))
         })
         .sum()
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::Error;
+
+    #[test]
+    fn test_case_1() {
+        let rucksacks = super::parse("ab\ncc\ncd").unwrap();
+
+        assert!(matches!(
+            super::part1(&rucksacks),
+            Err(Error::NoSolution { .. })
+        ));
+        assert!(matches!(
+            super::part2(&rucksacks),
+            Err(Error::NoSolution { .. })
+        ));
+    }
+
+    #[test]
+    fn test_case_2() {
+        // The leftover rucksack doesn't form a group of its own.
+        let rucksacks = super::parse("aa\naa\naa\nbb").unwrap();
+
+        assert_eq!(super::part1(&rucksacks), Ok(5));
+        assert!(matches!(
+            super::part2(&rucksacks),
+            Err(Error::NoSolution { .. })
+        ));
+    }
+}
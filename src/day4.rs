@@ -1,70 +1,88 @@
-use std::{num::ParseIntError, str::FromStr};
+use aoc_runner_derive::{aoc, aoc_generator};
+use nom::{combinator::map, sequence::separated_pair};
+use serde::Serialize;
 
-use aoc_runner_derive::aoc;
+use crate::{
+    diagnostic::{check, Diagnostic},
+    util::{
+        interval_set::IntervalSet,
+        parse::{self, lines, number, tag, IResult},
+    },
+    Error,
+};
 
+#[derive(Debug, Serialize)]
 struct Range {
     start: u64,
     end: u64,
 }
 
-impl FromStr for Range {
-    type Err = ParseIntError;
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let split_pos = s.chars().position(|c| c == '-').unwrap();
-        let (start, end) = s.split_at(split_pos);
+fn range(input: &str) -> IResult<'_, Range> {
+    map(separated_pair(number, tag("-"), number), |(start, end)| {
+        Range { start, end }
+    })(input)
+}
 
-        Ok(Range {
-            start: start.parse()?,
-            end: end[1..].parse()?,
-        })
+impl Range {
+    /// The sections in the range, which are none when it is backwards.
+    fn sections(&self) -> IntervalSet<u64> {
+        IntervalSet::from(self.start..=self.end)
     }
 }
 
-#[aoc(day4, part1)]
-pub fn part1(input: &str) -> u64 {
-    input
-        .split_whitespace()
-        .map(|line| {
-            let split_pos = line.chars().position(|c| c == ',').unwrap();
-            let (first, second) = line.split_at(split_pos);
+fn pair(input: &str) -> IResult<'_, (Range, Range)> {
+    separated_pair(range, tag(","), range)(input)
+}
 
-            let first = first.parse::<Range>().unwrap();
-            let second = second[1..].parse::<Range>().unwrap();
+/// Checks that every assignment is a pair of non-empty ranges.
+pub fn validate(input: &str) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
 
-            if first.start <= second.start && first.end >= second.end {
-                return 1;
-            }
+    for line in input.split_whitespace() {
+        let Some(pair) = check(&mut diagnostics, input, parse::all(line, pair)) else {
+            continue;
+        };
 
-            if second.start <= first.start && second.end >= first.end {
-                return 1;
+        for range in [pair.0, pair.1] {
+            if range.start > range.end {
+                diagnostics.push(
+                    Diagnostic::warning(format!(
+                        "The range {}-{} is backwards and contains no sections",
+                        range.start, range.end
+                    ))
+                    .at(input, line),
+                );
             }
+        }
+    }
 
-            0
-        })
-        .sum()
+    diagnostics
 }
 
-#[aoc(day4, part2)]
-pub fn part2(input: &str) -> u64 {
-    input
-        .split_whitespace()
-        .map(|line| {
-            let split_pos = line.chars().position(|c| c == ',').unwrap();
-            let (first, second) = line.split_at(split_pos);
-
-            let first = first.parse::<Range>().unwrap();
-            let second = second[1..].parse::<Range>().unwrap();
+/// The two section assignments of every pair of elves.
+#[derive(Debug, Serialize)]
+pub struct Pairs(Vec<(Range, Range)>);
 
-            if first.start <= second.end && first.end >= second.start {
-                return 1;
-            }
+#[aoc_generator(day4)]
+pub fn parse(input: &str) -> Result<Pairs, Error> {
+    parse::all(input, lines(pair)).map(Pairs)
+}
 
-            if second.start <= first.end && second.end >= first.start {
-                return 1;
-            }
+#[aoc(day4, part1)]
+pub fn part1(pairs: &Pairs) -> usize {
+    pairs
+        .0
+        .iter()
+        .map(|(first, second)| (first.sections(), second.sections()))
+        .filter(|(first, second)| first.is_subset(second) || second.is_subset(first))
+        .count()
+}
 
-            0
-        })
-        .sum()
+#[aoc(day4, part2)]
+pub fn part2(pairs: &Pairs) -> usize {
+    pairs
+        .0
+        .iter()
+        .filter(|(first, second)| !first.sections().intersection(&second.sections()).is_empty())
+        .count()
 }
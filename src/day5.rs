@@ -1,32 +1,64 @@
-use std::{num::ParseIntError, str::FromStr};
-
-use aoc_runner_derive::aoc;
-
+use aoc_runner_derive::{aoc, aoc_generator};
+use nom::{
+    combinator::consumed,
+    sequence::{preceded, separated_pair, terminated},
+};
+use serde::{Serialize, Serializer};
+
+use crate::{
+    diagnostic::{check, Diagnostic},
+    util::parse::{self, fail, lines, number, paragraph, tag, IResult},
+    Error,
+};
+
+#[derive(Debug, Clone, Copy)]
 struct Instruction {
     n: usize,
     from: usize,
     to: usize,
 }
 
-impl FromStr for Instruction {
-    type Err = ParseIntError;
+/// Serializes with the stacks numbered from 1, like in the input.
+impl Serialize for Instruction {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        #[derive(Serialize)]
+        struct Move {
+            count: usize,
+            from: usize,
+            to: usize,
+        }
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut words = s.split_whitespace();
+        Move {
+            count: self.n,
+            from: self.from + 1,
+            to: self.to + 1,
+        }
+        .serialize(serializer)
+    }
+}
 
-        assert!(words.next().unwrap() == "move");
-        let n = words.next().unwrap().parse::<usize>()?;
+fn stack_number(input: &str) -> IResult<'_, usize> {
+    let (rest, n) = number::<usize>(input)?;
 
-        assert!(words.next().unwrap() == "from");
-        let from = words.next().unwrap().parse::<usize>()? - 1;
+    match n {
+        0 => fail(
+            &input[..input.len() - rest.len()],
+            "a stack number starting from 1",
+        ),
+        n => Ok((rest, n - 1)),
+    }
+}
 
-        assert!(words.next().unwrap() == "to");
-        let to = words.next().unwrap().parse::<usize>()? - 1;
+fn instruction(input: &str) -> IResult<'_, Instruction> {
+    let (input, n) = preceded(tag("move "), number)(input)?;
+    let (input, from) = preceded(tag(" from "), stack_number)(input)?;
+    let (input, to) = preceded(tag(" to "), stack_number)(input)?;
 
-        Ok(Instruction { n, from, to })
-    }
+    Ok((input, Instruction { n, from, to }))
 }
 
+#[derive(Debug, Clone, Serialize)]
+#[serde(transparent)]
 struct Ship {
     stacks: Vec<Vec<char>>,
 }
@@ -43,80 +75,278 @@ impl Ship {
     }
 }
 
-impl FromStr for Ship {
-    type Err = ParseIntError;
+fn ship(input: &str) -> IResult<'_, Ship> {
+    let (rest, drawing) = paragraph(input)?;
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut boxes = Vec::<&str>::new();
-        let mut width: usize = 0;
+    let mut boxes = Vec::<&str>::new();
+    let mut width: usize = 0;
 
-        for line in s.lines() {
-            if line.starts_with(" 1") {
-                assert!((line.len() + 1) % 4 == 0);
-                width = (line.len() + 1) / 4;
-            } else {
-                boxes.push(line);
+    for line in drawing.lines() {
+        if line.starts_with(" 1") {
+            // The last label may or may not be followed by a space
+            let len = line.trim_end().len() + 2;
+
+            if !len.is_multiple_of(4) {
+                return fail(line, "stack labels four characters apart");
             }
+
+            width = len / 4;
+        } else {
+            boxes.push(line);
         }
+    }
 
-        boxes.reverse();
+    if width == 0 {
+        return fail(drawing, "a line of stack labels");
+    }
 
-        let mut stacks = vec![Vec::<char>::new(); width];
+    boxes.reverse();
 
-        for line in boxes {
-            for (idx, c) in line.char_indices().filter(|(i, _)| i % 4 == 1) {
-                if c == ' ' {
-                    continue;
-                }
+    let mut stacks = vec![Vec::<char>::new(); width];
 
-                stacks[idx / 4].push(c)
+    for line in boxes {
+        for (idx, c) in line.char_indices().filter(|(i, _)| i % 4 == 1) {
+            if c == ' ' {
+                continue;
             }
+
+            let Some(stack) = stacks.get_mut(idx / 4) else {
+                return fail(&line[idx..], "a crate within the labelled stacks");
+            };
+
+            stack.push(c)
         }
+    }
 
-        Ok(Ship { stacks })
+    Ok((rest, Ship { stacks }))
+}
+
+/// Checks that the drawing lines up with stacks four characters apart, and replays the moves to
+/// make sure no crate is taken from an empty stack.
+pub fn validate(input: &str) -> Vec<Diagnostic> {
+    let (instructions, drawing) = match terminated(paragraph, tag("\n\n"))(input) {
+        Ok(parts) => parts,
+        Err(err) => return vec![Diagnostic::from_error(err.into(), input)],
+    };
+
+    let mut diagnostics = Vec::new();
+
+    for line in drawing.lines().filter(|line| !line.starts_with(" 1")) {
+        if !(line.len() + 1).is_multiple_of(4) {
+            diagnostics.push(
+                Diagnostic::error(format!(
+                    "The drawing is {} characters wide, which is not a whole number of stacks \
+                     four characters apart",
+                    line.len()
+                ))
+                .at(input, line),
+            );
+        }
+    }
+
+    let Some(ship) = check(&mut diagnostics, input, parse::all(drawing, ship)) else {
+        return diagnostics;
+    };
+
+    let mut heights = ship.stacks.iter().map(Vec::len).collect::<Vec<_>>();
+
+    for line in instructions.lines() {
+        let Some(instruction) = check(&mut diagnostics, input, parse::all(line, instruction))
+        else {
+            continue;
+        };
+
+        if instruction.from.max(instruction.to) >= heights.len() {
+            diagnostics.push(
+                Diagnostic::error(format!("There are only {} stacks", heights.len()))
+                    .at(input, line),
+            );
+            continue;
+        }
+
+        let available = heights[instruction.from];
+
+        if available < instruction.n {
+            diagnostics.push(
+                Diagnostic::error(format!(
+                    "Moves {} crates from stack {}, which only holds {available}",
+                    instruction.n,
+                    instruction.from + 1
+                ))
+                .at(input, line),
+            );
+        }
+
+        let moved = available.min(instruction.n);
+
+        heights[instruction.from] -= moved;
+        heights[instruction.to] += moved;
     }
+
+    for (idx, _) in heights
+        .iter()
+        .enumerate()
+        .filter(|(_, &height)| height == 0)
+    {
+        diagnostics.push(Diagnostic::error(format!(
+            "Stack {} ends up empty, so it has no crate on top",
+            idx + 1
+        )));
+    }
+
+    diagnostics
 }
 
-#[aoc(day5, part1)]
-pub fn part1(input: &str) -> Result<String, ParseIntError> {
-    let split_pos = input.find("\n\n").unwrap() + 2;
-    let (ship, instructions) = input.split_at(split_pos);
+/// The drawing of the stacks, along with the moves of the crane.
+#[derive(Debug, Serialize)]
+pub struct Procedure {
+    #[serde(rename = "stacks")]
+    ship: Ship,
+    #[serde(rename = "moves")]
+    instructions: Vec<Instruction>,
+}
 
-    let mut ship = ship.parse::<Ship>()?;
+/// The drawing and the moves, which have to stay within the stacks, never take more crates than a
+/// stack holds and leave a crate on top of every stack.
+fn procedure(input: &str) -> IResult<'_, Procedure> {
+    let (rest, (ship, moves)) =
+        separated_pair(ship, tag("\n\n"), lines(consumed(instruction)))(input)?;
 
-    for instruction in instructions.lines() {
-        let instruction = instruction.parse::<Instruction>()?;
+    let mut heights = ship.stacks.iter().map(Vec::len).collect::<Vec<_>>();
+    let mut emptied_by = vec![&rest[..0]; heights.len()];
 
-        for _ in 0..instruction.n {
-            let c = ship.stacks[instruction.from].pop().unwrap();
+    for &(line, Instruction { n, from, to }) in &moves {
+        if from.max(to) >= heights.len() {
+            return fail(line, format!("a move between the {} stacks", heights.len()));
+        }
 
-            ship.stacks[instruction.to].push(c);
+        if heights[from] < n {
+            return fail(
+                line,
+                format!(
+                    "a move of at most the {} crates on stack {}",
+                    heights[from],
+                    from + 1
+                ),
+            );
+        }
+
+        // Crates put back where they were taken from don't go anywhere.
+        if from == to {
+            continue;
+        }
+
+        heights[from] -= n;
+        heights[to] += n;
+
+        if heights[from] == 0 {
+            emptied_by[from] = line;
         }
     }
 
-    let result = ship.result();
+    if let Some(idx) = heights.iter().position(|&height| height == 0) {
+        return fail(
+            emptied_by[idx],
+            format!("a crate left on top of stack {}", idx + 1),
+        );
+    }
 
-    assert_ne!(result, "GGNPJBTTR");
-    assert_eq!(result, "QNNTGTPFN");
+    let instructions = moves
+        .into_iter()
+        .map(|(_, instruction)| instruction)
+        .collect();
 
-    Ok(result)
+    Ok((rest, Procedure { ship, instructions }))
 }
 
-#[aoc(day5, part2)]
-pub fn part2(input: &str) -> Result<String, ParseIntError> {
-    let split_pos = input.find("\n\n").unwrap() + 2;
-    let (ship, instructions) = input.split_at(split_pos);
+#[aoc_generator(day5)]
+pub fn parse(input: &str) -> Result<Procedure, Error> {
+    parse::all(input, procedure)
+}
+
+#[aoc(day5, part1)]
+pub fn part1(procedure: &Procedure) -> String {
+    let mut ship = procedure.ship.clone();
+
+    for instruction in &procedure.instructions {
+        for _ in 0..instruction.n {
+            let c = ship.stacks[instruction.from].pop().unwrap();
 
-    let mut ship = ship.parse::<Ship>()?;
+            ship.stacks[instruction.to].push(c);
+        }
+    }
 
-    for instruction in instructions.lines() {
-        let instruction = instruction.parse::<Instruction>()?;
+    ship.result()
+}
 
+#[aoc(day5, part2)]
+pub fn part2(procedure: &Procedure) -> String {
+    let mut ship = procedure.ship.clone();
+
+    for instruction in procedure
+        .instructions
+        .iter()
+        .filter(|instruction| instruction.from != instruction.to)
+    {
         let from_len = ship.stacks[instruction.from].len();
         let boxes = ship.stacks[instruction.from].split_off(from_len - instruction.n);
 
         ship.stacks[instruction.to].extend(boxes);
     }
 
-    Ok(ship.result())
+    ship.result()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Severity;
+
+    const EXAMPLE: &str = include_str!("../input/2022/examples/day5-example.txt");
+
+    #[test]
+    fn test_case_1() {
+        assert_eq!(super::validate(EXAMPLE), []);
+    }
+
+    #[test]
+    fn test_case_2() {
+        let diagnostics =
+            super::validate("[N] [C]\n[Z] [M] [P]\n 1   2   3 \n\nmove 3 from 1 to 3");
+
+        assert_eq!(diagnostics.len(), 2);
+        assert_eq!(diagnostics[0].severity, Severity::Error);
+        assert_eq!(diagnostics[0].line, 5);
+        assert_eq!(
+            diagnostics[1].message,
+            "Stack 1 ends up empty, so it has no crate on top"
+        );
+    }
+
+    #[test]
+    fn test_case_3() {
+        let error = |input| match super::parse(input) {
+            Err(crate::Error::Parse(err)) => err.expected,
+            _ => panic!("Expected a parse error"),
+        };
+
+        assert_eq!(
+            error("[A] [B]\n 1   2 \n\nmove 1 from 3 to 1"),
+            "a move between the 2 stacks"
+        );
+        assert_eq!(
+            error("[A] [B]\n 1   2 \n\nmove 2 from 1 to 2"),
+            "a move of at most the 1 crates on stack 1"
+        );
+
+        assert_eq!(
+            error("[A] [B]\n 1   2 \n\nmove 2 from 1 to 1"),
+            "a move of at most the 1 crates on stack 1"
+        );
+
+        // Moving crates onto the stack they came from leaves them where they are.
+        let procedure = super::parse("[A] [B]\n[C] [D]\n 1   2 \n\nmove 2 from 1 to 1").unwrap();
+
+        assert_eq!(super::part1(&procedure), "AB");
+        assert_eq!(super::part2(&procedure), "AB");
+    }
 }
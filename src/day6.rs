@@ -1,6 +1,9 @@
 use std::collections::{HashSet, VecDeque};
 
-use aoc_runner_derive::aoc;
+use aoc_runner_derive::{aoc, aoc_generator};
+use serde::{Serialize, Serializer};
+
+use crate::{diagnostic::Diagnostic, Error};
 use itertools::Itertools;
 
 struct MarkerFinder {
@@ -44,99 +47,77 @@ impl MarkerFinder {
     }
 }
 
-#[aoc(day6, part1)]
-pub fn part1(input: &str) -> usize {
-    let pos = input.chars().tuple_windows().find_position(|(a, b, c, d)| {
-        if a == b || a == c || a == d {
-            return false;
-        }
-
-        if b == c || b == d {
-            return false;
-        }
-
-        if c == d {
-            return false;
-        }
-
-        true
-    });
-
-    pos.unwrap().0 + 4
+/// Checks that the datastream contains both markers.
+pub fn validate(input: &str) -> Vec<Diagnostic> {
+    [(4, "start-of-packet"), (14, "start-of-message")]
+        .into_iter()
+        .filter(|&(size, _)| {
+            !input
+                .as_bytes()
+                .windows(size)
+                .any(|window| window.iter().all_unique())
+        })
+        .map(|(size, marker)| {
+            Diagnostic::error(format!("No {marker} marker of {size} different characters"))
+        })
+        .collect()
 }
 
-#[aoc(day6, part2)]
-pub fn part2(input: &str) -> usize {
-    let mut finder = MarkerFinder::new(14);
+/// The characters the device received.
+#[derive(Debug)]
+pub struct Datastream(Vec<char>);
 
-    for c in input.chars() {
-        if let Some(result) = finder.feed(c) {
-            return result;
-        }
+/// Serializes as the received characters.
+impl Serialize for Datastream {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(&self.0.iter().collect::<String>())
     }
-
-    panic!("No result found")
 }
 
-#[cfg(test)]
-mod tests {
-    #[test]
-    fn test_case_1() {
-        let result = super::part1("mjqjpqmgbljsphdztnvjfqwrcgsmlb");
-        assert_eq!(result, 7);
-    }
-
-    #[test]
-    fn test_case_2() {
-        let result = super::part1("bvwbjplbgvbhsrlpgdmjqwftvncz");
-        assert_eq!(result, 5);
-    }
+#[aoc_generator(day6)]
+pub fn parse(input: &str) -> Datastream {
+    Datastream(input.trim_end().chars().collect())
+}
 
-    #[test]
-    fn test_case_3() {
-        let result = super::part1("nppdvjthqldpwncqszvftbrmjlhg");
-        assert_eq!(result, 6);
-    }
+#[aoc(day6, part1)]
+pub fn part1(datastream: &Datastream) -> Result<usize, Error> {
+    let pos = datastream
+        .0
+        .iter()
+        .tuple_windows()
+        .find_position(|(a, b, c, d)| {
+            if a == b || a == c || a == d {
+                return false;
+            }
 
-    #[test]
-    fn test_case_4() {
-        let result = super::part1("nznrnfrfntjfmvfwmzdfjlvtqnbhcprsg");
-        assert_eq!(result, 10);
-    }
+            if b == c || b == d {
+                return false;
+            }
 
-    #[test]
-    fn test_case_5() {
-        let result = super::part1("zcfzfwzzqfrljwzlrfnpqdbhtmscgvjw");
-        assert_eq!(result, 11);
-    }
+            if c == d {
+                return false;
+            }
 
-    #[test]
-    fn test_case_6() {
-        let result = super::part2("mjqjpqmgbljsphdztnvjfqwrcgsmlb");
-        assert_eq!(result, 19);
-    }
+            true
+        });
 
-    #[test]
-    fn test_case_7() {
-        let result = super::part2("bvwbjplbgvbhsrlpgdmjqwftvncz");
-        assert_eq!(result, 23);
-    }
+    pos.map(|(pos, _)| pos + 4)
+        .ok_or_else(|| Error::NoSolution {
+            reason: "there is no start-of-packet marker".to_string(),
+        })
+}
 
-    #[test]
-    fn test_case_8() {
-        let result = super::part2("nppdvjthqldpwncqszvftbrmjlhg");
-        assert_eq!(result, 23);
-    }
+#[aoc(day6, part2)]
+pub fn part2(datastream: &Datastream) -> Result<usize, Error> {
+    let mut finder = MarkerFinder::new(14);
 
-    #[test]
-    fn test_case_9() {
-        let result = super::part2("nznrnfrfntjfmvfwmzdfjlvtqnbhcprsg");
-        assert_eq!(result, 29);
+    for &c in &datastream.0 {
+        if let Some(result) = finder.feed(c) {
+            return Ok(result);
+        }
     }
 
-    #[test]
-    fn test_case_10() {
-        let result = super::part2("zcfzfwzzqfrljwzlrfnpqdbhtmscgvjw");
-        assert_eq!(result, 26);
-    }
+    Err(Error::NoSolution {
+        reason: "there is no start-of-message marker".to_string(),
+    })
 }
@@ -1,13 +1,43 @@
-use std::{num::ParseIntError, str::FromStr};
-
-use aoc_runner_derive::aoc;
-
-struct Dir {
+use aoc_runner_derive::{aoc, aoc_generator};
+use nom::{
+    branch::alt,
+    combinator::{consumed, map, value},
+    sequence::{preceded, separated_pair},
+};
+use serde::Serialize;
+
+use crate::{
+    diagnostic::Diagnostic,
+    util::parse::{self, fail, lines, number, tag, word, IResult},
+    Error,
+};
+
+/// A directory, with the total size of the files in it and in all of its subdirectories.
+#[derive(Debug, Serialize)]
+pub struct Dir {
+    name: String,
     children: Vec<Dir>,
+    files: Vec<File>,
     files_size: u64,
 }
 
+/// A file listed directly in a directory.
+#[derive(Debug, Serialize)]
+struct File {
+    name: String,
+    size: u64,
+}
+
 impl Dir {
+    fn new(name: &str) -> Self {
+        Dir {
+            name: name.to_string(),
+            children: Vec::new(),
+            files: Vec::new(),
+            files_size: 0,
+        }
+    }
+
     fn visit_all<T: FnMut(&Dir)>(&self, fun: &mut T) {
         fun(self);
 
@@ -17,69 +47,126 @@ impl Dir {
     }
 }
 
-impl FromStr for Dir {
-    type Err = ParseIntError;
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut stack = Vec::<Dir>::new();
+/// A line of the terminal output.
+#[derive(Debug, Clone, Copy)]
+enum Line<'a> {
+    Cd(&'a str),
+    Ls,
+    Dir,
+    File(u64, &'a str),
+}
 
-        for line in s.lines() {
-            if line == "$ cd /" {
-                assert_eq!(stack.len(), 0);
+fn line(input: &str) -> IResult<'_, Line<'_>> {
+    alt((
+        map(preceded(tag("$ cd "), word), Line::Cd),
+        value(Line::Ls, tag("$ ls")),
+        value(Line::Dir, preceded(tag("dir "), word)),
+        map(separated_pair(number, tag(" "), word), |(size, name)| {
+            Line::File(size, name)
+        }),
+    ))(input)
+}
 
-                stack.push(Dir {
-                    children: Vec::new(),
-                    files_size: 0,
-                });
+fn root(input: &str) -> IResult<'_, Dir> {
+    let (rest, lines) = lines(consumed(line))(input)?;
+    let mut stack = Vec::<Dir>::new();
 
-                continue;
-            }
+    for (text, line) in lines {
+        match line {
+            Line::Cd("/") => {
+                if !stack.is_empty() {
+                    return fail(text, "\"$ cd /\" only as the first command");
+                }
 
-            if line == "$ ls" {
-                continue;
+                stack.push(Dir::new("/"));
             }
+            Line::Ls => {}
+            Line::Cd("..") => {
+                if stack.len() < 2 {
+                    return fail(text, "a subdirectory to leave");
+                }
 
-            if line == "$ cd .." {
                 let dir = stack.pop().unwrap();
                 stack.last_mut().unwrap().children.push(dir);
-                continue;
             }
-
-            if let Some(_dir) = line.strip_prefix("$ cd ") {
-                stack.push(Dir {
-                    children: Vec::new(),
-                    files_size: 0,
+            _ if stack.is_empty() => return fail(text, "\"$ cd /\""),
+            Line::Cd(name) => stack.push(Dir::new(name)),
+            Line::Dir => {}
+            Line::File(size, name) => {
+                for dir in stack.iter_mut() {
+                    dir.files_size += size;
+                }
+
+                stack.last_mut().unwrap().files.push(File {
+                    name: name.to_string(),
+                    size,
                 });
-                continue;
-            }
-
-            if let Some(_dir) = line.strip_prefix("dir ") {
-                continue;
             }
+        }
+    }
 
-            let split_pos = line.chars().position(|c| c == ' ').unwrap();
-            let (size, _) = line.split_at(split_pos);
+    while stack.len() > 1 {
+        let dir = stack.pop().unwrap();
+        stack.last_mut().unwrap().children.push(dir);
+    }
 
-            let size = size.parse::<u64>()?;
+    match stack.pop() {
+        Some(root) => Ok((rest, root)),
+        None => fail(input, "\"$ cd /\""),
+    }
+}
 
-            for mut dir in stack.iter_mut() {
-                dir.files_size += size;
-            }
-        }
+/// Tunables of the puzzle, see [`crate::params`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Params {
+    /// Size of the whole disk.
+    pub disk_size: u64,
+    /// Free space the update needs.
+    pub required_space: u64,
+}
 
-        while stack.len() > 1 {
-            let dir = stack.pop().unwrap();
-            stack.last_mut().unwrap().children.push(dir);
+impl Default for Params {
+    fn default() -> Self {
+        Params {
+            disk_size: 70_000_000,
+            required_space: 30_000_000,
         }
+    }
+}
 
-        Ok(stack.pop().unwrap())
+/// Checks that the filesystem fits on the disk with the default [`Params`], but not alongside the
+/// update.
+pub fn validate(input: &str) -> Vec<Diagnostic> {
+    let root = match parse(input) {
+        Ok(root) => root,
+        Err(err) => return vec![Diagnostic::from_error(err, input)],
+    };
+
+    let params = Params::default();
+
+    if root.files_size > params.disk_size {
+        vec![Diagnostic::error(format!(
+            "The files take up {} bytes, more than the disk size of {}",
+            root.files_size, params.disk_size
+        ))]
+    } else if params.disk_size - root.files_size >= params.required_space {
+        vec![Diagnostic::error(format!(
+            "There are already {} bytes free, so part 2 has nothing to delete",
+            params.disk_size - root.files_size
+        ))]
+    } else {
+        Vec::new()
     }
 }
 
-#[aoc(day7, part1)]
-pub fn part1(input: &str) -> Result<u64, ParseIntError> {
-    let root = input.parse::<Dir>()?;
+/// Reads the root directory from the terminal output.
+#[aoc_generator(day7)]
+pub fn parse(input: &str) -> Result<Dir, Error> {
+    parse::all(input, root)
+}
 
+#[aoc(day7, part1)]
+pub fn part1(root: &Dir) -> u64 {
     let mut result = 0;
 
     root.visit_all(&mut |dir| {
@@ -88,41 +175,58 @@ pub fn part1(input: &str) -> Result<u64, ParseIntError> {
         }
     });
 
-    Ok(result)
+    result
 }
 
 #[aoc(day7, part2)]
-pub fn part2(input: &str) -> Result<u64, ParseIntError> {
-    let root = input.parse::<Dir>()?;
+pub fn part2(root: &Dir) -> Result<u64, Error> {
+    part2_with(root, &Params::default())
+}
 
-    let disk_size = 70_000_000u64;
-    let free_space = disk_size - root.files_size;
+/// The size of the smallest directory to delete, which is nothing when the update already fits.
+pub fn part2_with(root: &Dir, params: &Params) -> Result<u64, Error> {
+    let free_space = params.disk_size.saturating_sub(root.files_size);
 
-    let target_space = 30_000_000u64;
-    let need_to_free = target_space - free_space;
+    let need_to_free = match params.required_space.checked_sub(free_space) {
+        None | Some(0) => return Ok(0),
+        Some(need_to_free) => need_to_free,
+    };
 
-    let mut result = target_space;
+    let mut result = None;
 
     root.visit_all(&mut |dir| {
-        if dir.files_size >= need_to_free && result > dir.files_size {
-            result = dir.files_size;
+        if dir.files_size >= need_to_free && result.is_none_or(|size| size > dir.files_size) {
+            result = Some(dir.files_size);
         }
     });
 
-    Ok(result)
+    result.ok_or_else(|| Error::NoSolution {
+        reason: format!(
+            "deleting every file frees {} bytes, but the update needs {need_to_free} more",
+            root.files_size
+        ),
+    })
 }
 
 #[cfg(test)]
 mod tests {
-    #[test]
-    fn test_case_1() {
-        let result = super::part1("$ cd /\n$ ls\ndir a\n14848514 b.txt\n8504156 c.dat\ndir d\n$ cd a\n$ ls\ndir e\n29116 f\n2557 g\n62596 h.lst\n$ cd e\n$ ls\n584 i\n$ cd ..\n$ cd ..\n$ cd d\n$ ls\n4060174 j\n8033020 d.log\n5626152 d.ext\n7214296 k");
-        assert_eq!(result, Ok(95437));
-    }
+    use super::Params;
 
     #[test]
-    fn test_case_2() {
-        let result = super::part2("$ cd /\n$ ls\ndir a\n14848514 b.txt\n8504156 c.dat\ndir d\n$ cd a\n$ ls\ndir e\n29116 f\n2557 g\n62596 h.lst\n$ cd e\n$ ls\n584 i\n$ cd ..\n$ cd ..\n$ cd d\n$ ls\n4060174 j\n8033020 d.log\n5626152 d.ext\n7214296 k");
-        assert_eq!(result, Ok(24933642));
+    fn test_case_1() {
+        let root = super::parse(include_str!("../input/2022/examples/day7-example.txt")).unwrap();
+        let with = |disk_size, required_space| {
+            super::part2_with(
+                &root,
+                &Params {
+                    disk_size,
+                    required_space,
+                },
+            )
+        };
+
+        assert_eq!(with(70_000_000, 1), Ok(0));
+        assert_eq!(with(1, 30_000_000), Ok(48381165));
+        assert!(with(1, 60_000_000).is_err());
     }
 }
@@ -1,86 +1,57 @@
-use std::{num::ParseIntError, str::FromStr};
-
-use aoc_runner_derive::aoc;
-
+use aoc_runner_derive::{aoc, aoc_generator};
+use serde::{Serialize, Serializer};
+
+use crate::{
+    diagnostic::Diagnostic,
+    util::{
+        grid::Grid,
+        parse::{self, grid},
+    },
+    Error,
+};
+
+#[derive(Debug, Clone)]
 struct Tree {
     height: i8,
     visible: bool,
 }
 
-impl FromStr for Tree {
-    type Err = ParseIntError;
+/// Serializes as the height alone, as visibility is only worked out while solving.
+impl Serialize for Tree {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_i8(self.height)
+    }
+}
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        Ok(Tree {
-            height: s.parse()?,
+impl Tree {
+    fn new(c: char) -> Option<Self> {
+        Some(Tree {
+            height: c.to_digit(10)? as i8,
             visible: false,
         })
     }
 }
 
-struct Forest {
-    trees: Vec<Tree>,
-    width: usize,
-}
-
-impl Forest {
-    fn height(&self) -> usize {
-        self.trees.len() / self.width
-    }
-
-    fn width(&self) -> usize {
-        self.width
-    }
-
-    fn iter(&self) -> impl DoubleEndedIterator<Item = &Tree> {
-        self.trees.iter()
-    }
-
-    fn iter_col_mut(&mut self, col: usize) -> impl DoubleEndedIterator<Item = &mut Tree> {
-        assert!(col < self.width());
-
-        self.trees.iter_mut().skip(col).step_by(self.width)
-    }
-
-    fn iter_row_mut(&mut self, row: usize) -> impl DoubleEndedIterator<Item = &mut Tree> {
-        assert!(row < self.height());
+#[derive(Debug, Serialize)]
+pub struct Forest(Grid<Tree>);
 
-        self.trees
-            .iter_mut()
-            .skip(row * self.width)
-            .take(self.width)
-    }
-
-    fn iter_pos(&self) -> impl DoubleEndedIterator<Item = (usize, usize)> + '_ {
-        (0..self.height()).flat_map(|y| (0..self.width()).map(move |x| (x, y)))
-    }
-
-    fn get(&self, x: usize, y: usize) -> &Tree {
-        assert!(x < self.width());
-        assert!(y < self.height());
-
-        &self.trees[y * self.width + x]
-    }
+#[aoc_generator(day8)]
+pub fn parse(input: &str) -> Result<Forest, Error> {
+    parse::all(input, grid("a tree height (0-9)", Tree::new)).map(Forest)
 }
 
-impl FromStr for Forest {
-    type Err = ParseIntError;
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let width = s.find('\n').unwrap_or(s.len());
-
-        let trees = s
-            .lines()
-            .flat_map(|line| line.chars().map(|c| String::from(c).parse::<Tree>()))
-            .collect::<Result<Vec<_>, _>>()?;
-
-        Ok(Forest { trees, width })
+/// Checks that the forest is a rectangle of tree heights.
+pub fn validate(input: &str) -> Vec<Diagnostic> {
+    match parse(input) {
+        Ok(forest) if forest.0.width() == 0 => vec![Diagnostic::warning("The forest is empty")],
+        Ok(_) => Vec::new(),
+        Err(err) => vec![Diagnostic::from_error(err, input)],
     }
 }
 
 #[aoc(day8, part1)]
-pub fn part1(input: &str) -> Result<u64, ParseIntError> {
-    let mut forest = input.parse::<Forest>()?;
+pub fn part1(forest: &Forest) -> u64 {
+    let mut forest = forest.0.clone();
 
     let width = forest.width();
     let height = forest.height();
@@ -88,7 +59,7 @@ pub fn part1(input: &str) -> Result<u64, ParseIntError> {
     for x in 0..width {
         let mut h = -1;
 
-        for tree in forest.iter_col_mut(x) {
+        for tree in forest.column_mut(x) {
             if tree.height > h {
                 tree.visible = true;
                 h = tree.height;
@@ -97,7 +68,7 @@ pub fn part1(input: &str) -> Result<u64, ParseIntError> {
 
         let mut h = -1;
 
-        for tree in forest.iter_col_mut(x).rev() {
+        for tree in forest.column_mut(x).rev() {
             if tree.height > h {
                 tree.visible = true;
                 h = tree.height;
@@ -108,7 +79,7 @@ pub fn part1(input: &str) -> Result<u64, ParseIntError> {
     for y in 0..height {
         let mut h = -1;
 
-        for tree in forest.iter_row_mut(y) {
+        for tree in forest.row_mut(y) {
             if tree.height > h {
                 tree.visible = true;
                 h = tree.height;
@@ -117,7 +88,7 @@ pub fn part1(input: &str) -> Result<u64, ParseIntError> {
 
         let mut h = -1;
 
-        for tree in forest.iter_row_mut(y).rev() {
+        for tree in forest.row_mut(y).rev() {
             if tree.height > h {
                 tree.visible = true;
                 h = tree.height;
@@ -125,26 +96,26 @@ pub fn part1(input: &str) -> Result<u64, ParseIntError> {
         }
     }
 
-    Ok(forest.iter().map(|t| t.visible as u64).sum())
+    forest.iter().map(|t| t.visible as u64).sum()
 }
 
 #[aoc(day8, part2)]
-pub fn part2(input: &str) -> Result<u64, ParseIntError> {
-    let forest = input.parse::<Forest>()?;
+pub fn part2(forest: &Forest) -> u64 {
+    let forest = &forest.0;
 
     let width = forest.width();
     let height = forest.height();
 
-    Ok(forest
-        .iter_pos()
+    forest
+        .positions()
         .map(|(x, y)| {
-            let house_height = forest.get(x, y).height;
+            let house_height = forest[(x, y)].height;
             let mut score = (0u64, 0u64, 0u64, 0u64);
 
             for delta in 1..=x {
                 score.0 += 1;
 
-                if forest.get(x - delta, y).height >= house_height {
+                if forest[(x - delta, y)].height >= house_height {
                     break;
                 }
             }
@@ -152,7 +123,7 @@ pub fn part2(input: &str) -> Result<u64, ParseIntError> {
             for delta in 1..(width - x) {
                 score.1 += 1;
 
-                if forest.get(x + delta, y).height >= house_height {
+                if forest[(x + delta, y)].height >= house_height {
                     break;
                 }
             }
@@ -160,7 +131,7 @@ pub fn part2(input: &str) -> Result<u64, ParseIntError> {
             for delta in 1..=y {
                 score.2 += 1;
 
-                if forest.get(x, y - delta).height >= house_height {
+                if forest[(x, y - delta)].height >= house_height {
                     break;
                 }
             }
@@ -168,7 +139,7 @@ pub fn part2(input: &str) -> Result<u64, ParseIntError> {
             for delta in 1..(height - y) {
                 score.3 += 1;
 
-                if forest.get(x, y + delta).height >= house_height {
+                if forest[(x, y + delta)].height >= house_height {
                     break;
                 }
             }
@@ -176,20 +147,5 @@ pub fn part2(input: &str) -> Result<u64, ParseIntError> {
             score.0 * score.1 * score.2 * score.3
         })
         .max()
-        .unwrap_or(0))
-}
-
-#[cfg(test)]
-mod tests {
-    #[test]
-    fn test_case_1() {
-        let result = super::part1("30373\n25512\n65332\n33549\n35390");
-        assert_eq!(result, Ok(21));
-    }
-
-    #[test]
-    fn test_case_2() {
-        let result = super::part2("30373\n25512\n65332\n33549\n35390");
-        assert_eq!(result, Ok(8));
-    }
+        .unwrap_or(0)
 }
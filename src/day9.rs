@@ -1,7 +1,22 @@
-use std::{cmp, collections::HashSet, num::ParseIntError, str::FromStr};
-
-use aoc_runner_derive::aoc;
-
+use std::collections::HashSet;
+
+use aoc_runner_derive::{aoc, aoc_generator};
+use nom::sequence::preceded;
+use serde::Serialize;
+
+use crate::{
+    diagnostic::{check, Diagnostic},
+    util::{
+        geom::{BoundingBox, Direction, Point2},
+        grid::Grid,
+        parse::{self, char_of, lines, number, tag, IResult},
+    },
+    visualize::{Draw, Recorder, Tile},
+    Error,
+};
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "lowercase")]
 enum Move {
     Down(i32),
     Left(i32),
@@ -10,12 +25,12 @@ enum Move {
 }
 
 impl Move {
-    fn direction(&self) -> (i32, i32) {
+    fn direction(&self) -> Direction {
         match self {
-            Move::Down(_) => (0, -1),
-            Move::Left(_) => (-1, 0),
-            Move::Right(_) => (1, 0),
-            Move::Up(_) => (0, 1),
+            Move::Down(_) => Direction::South,
+            Move::Left(_) => Direction::West,
+            Move::Right(_) => Direction::East,
+            Move::Up(_) => Direction::North,
         }
     }
 
@@ -29,120 +44,143 @@ impl Move {
     }
 }
 
-impl FromStr for Move {
-    type Err = ParseIntError;
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let (dir, dist) = s.split_once(' ').unwrap();
-        let dist = dist.parse::<i32>()?;
-
-        Ok(match dir {
-            "D" => Move::Down(dist),
-            "L" => Move::Left(dist),
-            "R" => Move::Right(dist),
-            "U" => Move::Up(dist),
-            _ => panic!("Invalid direction"),
-        })
-    }
+fn motion(input: &str) -> IResult<'_, Move> {
+    let (input, dir) = char_of("a direction (D, L, R or U)", |c| match c {
+        'D' => Some(Move::Down as fn(i32) -> Move),
+        'L' => Some(Move::Left),
+        'R' => Some(Move::Right),
+        'U' => Some(Move::Up),
+        _ => None,
+    })(input)?;
+    let (input, dist) = preceded(tag(" "), number)(input)?;
+
+    Ok((input, dir(dist)))
 }
 
-#[aoc(day9, part1)]
-pub fn part1(input: &str) -> Result<usize, ParseIntError> {
-    let moves = input
-        .lines()
-        .map(|s| s.parse::<Move>())
-        .collect::<Result<Vec<_>, _>>()?;
+/// Checks that every move goes somewhere.
+pub fn validate(input: &str) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
 
-    let mut visited = HashSet::<(i32, i32)>::new();
+    for line in input.lines() {
+        let Some(m) = check(&mut diagnostics, input, parse::all(line, motion)) else {
+            continue;
+        };
 
-    let mut head = (0, 0);
-    let mut tail = (0, 0);
+        if m.distance() <= 0 {
+            diagnostics.push(
+                Diagnostic::warning(format!("Moves {} steps, which does nothing", m.distance()))
+                    .at(input, line),
+            );
+        }
+    }
 
-    visited.insert(tail);
+    diagnostics
+}
 
-    for m in moves {
-        let distance = m.distance();
-        let (dx, dy) = m.direction();
+/// A rope of knots, where every knot follows the one before it and the first one is the head.
+struct Rope {
+    knots: Vec<Point2>,
+    visited: HashSet<Point2>,
+}
 
-        for _ in 0..distance {
-            head.0 += dx;
-            head.1 += dy;
+impl Rope {
+    fn new(knots: usize) -> Self {
+        Rope {
+            knots: vec![Point2::ORIGIN; knots],
+            visited: HashSet::from([Point2::ORIGIN]),
+        }
+    }
 
-            let touching = cmp::max((head.0 - tail.0).abs(), (head.1 - tail.1).abs()) <= 1;
+    /// Moves the head one step in `direction`, and the other knots after it.
+    fn step(&mut self, direction: Direction) {
+        self.knots[0] = self.knots[0].step(direction);
 
-            if touching {
-                continue;
-            }
+        for i in 1..self.knots.len() {
+            let (head, tail) = (self.knots[i - 1], self.knots[i]);
 
-            tail.0 += (head.0 - tail.0).signum();
-            tail.1 += (head.1 - tail.1).signum();
+            if head.chebyshev(tail) <= 1 {
+                break;
+            }
 
-            visited.insert(tail);
+            self.knots[i] += (head - tail).signum();
         }
-    }
 
-    Ok(visited.len())
+        self.visited.insert(*self.knots.last().unwrap());
+    }
 }
 
-#[aoc(day9, part2)]
-pub fn part2(input: &str) -> Result<usize, ParseIntError> {
-    let moves = input
-        .lines()
-        .map(|s| s.parse::<Move>())
-        .collect::<Result<Vec<_>, _>>()?;
+const VISITED: Tile = Tile::new('#', [90, 90, 90]);
+const UNVISITED: Tile = Tile::new('.', [0, 0, 0]);
 
-    let mut visited = HashSet::<(i32, i32)>::new();
+impl Draw for Rope {
+    /// Draws the places the tail has been to and the knots on top, with up at the top.
+    fn draw(&self) -> Grid<Tile> {
+        let bounds = BoundingBox::of(self.visited.iter().chain(&self.knots).copied()).unwrap();
+        let mut grid = Grid::new(bounds.width(), bounds.height(), UNVISITED);
 
-    let mut rope = vec![(0, 0); 10];
+        for &visited in &self.visited {
+            grid[bounds.grid_pos(visited)] = VISITED;
+        }
 
-    visited.insert(rope[9]);
+        // Later knots go first, so that the head ends up on top.
+        for (idx, &knot) in self.knots.iter().enumerate().rev() {
+            let symbol = match idx {
+                0 => 'H',
+                _ if self.knots.len() == 2 => 'T',
+                _ => char::from_digit(idx as u32, 36).unwrap(),
+            };
+            let brightness = 255 - (150 * idx / self.knots.len()) as u8;
 
-    for m in moves {
-        let distance = m.distance();
-        let (dx, dy) = m.direction();
+            grid[bounds.grid_pos(knot)] = Tile::new(symbol, [brightness, brightness / 3, 0]);
+        }
 
-        for _ in 0..distance {
-            rope[0].0 += dx;
-            rope[0].1 += dy;
+        grid
+    }
+}
 
-            for i in 1..10 {
-                let touching = cmp::max(
-                    (rope[i - 1].0 - rope[i].0).abs(),
-                    (rope[i - 1].1 - rope[i].1).abs(),
-                ) <= 1;
+/// The moves of the head of the rope.
+#[derive(Debug, Serialize)]
+pub struct Motions(Vec<Move>);
 
-                if touching {
-                    continue;
-                }
+#[aoc_generator(day9)]
+pub fn parse(input: &str) -> Result<Motions, Error> {
+    parse::all(input, lines(motion)).map(Motions)
+}
 
-                rope[i].0 += (rope[i - 1].0 - rope[i].0).signum();
-                rope[i].1 += (rope[i - 1].1 - rope[i].1).signum();
-            }
+fn simulate(motions: &Motions, knots: usize, mut on_step: impl FnMut(&Rope)) -> Rope {
+    let mut rope = Rope::new(knots);
 
-            visited.insert(rope[9]);
+    for m in &motions.0 {
+        for _ in 0..m.distance() {
+            rope.step(m.direction());
+            on_step(&rope);
         }
     }
 
-    Ok(visited.len())
+    rope
 }
 
-#[cfg(test)]
-mod tests {
-    #[test]
-    fn test_case_1() {
-        let result = super::part1("R 4\nU 4\nL 3\nD 1\nR 4\nD 1\nL 5\nR 2\n");
-        assert_eq!(result, Ok(13));
-    }
+#[aoc(day9, part1)]
+pub fn part1(motions: &Motions) -> usize {
+    simulate(motions, 2, |_| ()).visited.len()
+}
 
-    #[test]
-    fn test_case_2() {
-        let result = super::part2("R 4\nU 4\nL 3\nD 1\nR 4\nD 1\nL 5\nR 2\n");
-        assert_eq!(result, Ok(1));
-    }
+#[aoc(day9, part2)]
+pub fn part2(motions: &Motions) -> usize {
+    simulate(motions, 10, |_| ()).visited.len()
+}
 
-    #[test]
-    fn test_case_3() {
-        let result = super::part2("R 5\nU 8\nL 8\nD 3\nR 17\nD 10\nL 25\nU 20\n");
-        assert_eq!(result, Ok(36));
-    }
+/// Records the rope of the given part after every step of the head.
+pub fn visualize(motions: &Motions, part: u8, recorder: &mut Recorder) {
+    let knots = if part == 1 { 2 } else { 10 };
+    let mut step = 0;
+
+    recorder.record(step, &Rope::new(knots));
+
+    let rope = simulate(motions, knots, |rope| {
+        step += 1;
+        recorder.record(step, rope);
+    });
+
+    recorder.record_last(step, &rope);
 }
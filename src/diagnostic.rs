@@ -0,0 +1,127 @@
+use std::fmt::Display;
+
+use crate::Error;
+
+/// How bad a [`Diagnostic`] is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+    /// The input is unusual, but the solvers still give correct answers for it.
+    Warning,
+    /// At least one part can't be solved, or would be solved wrongly.
+    Error,
+}
+
+/// A problem with a puzzle input, found by [`crate::validate`] without solving the puzzle.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    /// 1-based line the problem was found on, or 0 if it concerns the input as a whole.
+    pub line: usize,
+    /// 1-based column within `line`, or 0 if it concerns the input as a whole.
+    pub column: usize,
+    pub message: String,
+}
+
+impl Diagnostic {
+    pub(crate) fn error(message: impl Into<String>) -> Self {
+        Diagnostic {
+            severity: Severity::Error,
+            line: 0,
+            column: 0,
+            message: message.into(),
+        }
+    }
+
+    pub(crate) fn warning(message: impl Into<String>) -> Self {
+        Diagnostic {
+            severity: Severity::Warning,
+            ..Diagnostic::error(message)
+        }
+    }
+
+    /// Points the diagnostic at `found`, which must be a slice of `input`.
+    pub(crate) fn at(self, input: &str, found: &str) -> Self {
+        match Error::parse(found, "").locate(0, input) {
+            Error::Parse(err) => Diagnostic {
+                line: err.line,
+                column: err.column,
+                ..self
+            },
+            _ => self,
+        }
+    }
+
+    /// Reports a failure to parse `input` at the position it was found.
+    pub(crate) fn from_error(err: Error, input: &str) -> Self {
+        match err.locate(0, input) {
+            Error::Parse(err) => Diagnostic {
+                line: err.line,
+                column: err.column,
+                ..Diagnostic::error(format!("Expected {}, found {:?}", err.expected, err.found))
+            },
+            err => Diagnostic::error(err.to_string()),
+        }
+    }
+}
+
+/// Keeps the value of a successful parse, and records why it failed otherwise.
+pub(crate) fn check<T>(
+    diagnostics: &mut Vec<Diagnostic>,
+    input: &str,
+    result: Result<T, Error>,
+) -> Option<T> {
+    result
+        .map_err(|err| diagnostics.push(Diagnostic::from_error(err, input)))
+        .ok()
+}
+
+impl Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Severity::Warning => write!(f, "warning"),
+            Severity::Error => write!(f, "error"),
+        }
+    }
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.line == 0 {
+            write!(f, "{}: {}", self.severity, self.message)
+        } else {
+            write!(
+                f,
+                "{} at {}:{}: {}",
+                self.severity, self.line, self.column, self.message
+            )
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Diagnostic, Severity};
+    use crate::Error;
+
+    #[test]
+    fn from_error() {
+        let input = "1,2\n3;4";
+        let diagnostic = Diagnostic::from_error(Error::parse(&input[5..], "\",\""), input);
+
+        assert_eq!((diagnostic.line, diagnostic.column), (2, 2));
+        assert_eq!(diagnostic.severity, Severity::Error);
+        assert_eq!(
+            diagnostic.to_string(),
+            "error at 2:2: Expected \",\", found \";4\""
+        );
+    }
+
+    #[test]
+    fn at() {
+        let input = "abc\ndef";
+        let diagnostic = Diagnostic::warning("odd").at(input, &input[4..]);
+
+        assert_eq!(diagnostic.to_string(), "warning at 2:1: odd");
+        assert_eq!(Diagnostic::warning("odd").to_string(), "warning: odd");
+    }
+}
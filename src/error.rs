@@ -0,0 +1,203 @@
+use std::fmt::{Debug, Display};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    UnknownDay {
+        day: u8,
+    },
+    UnknownPuzzle {
+        day: u8,
+        part: u8,
+    },
+    UnknownParam {
+        name: String,
+    },
+    InvalidParam {
+        name: String,
+        value: String,
+    },
+    Parse(ParseError),
+    /// The input parsed fine, but the puzzle has no answer for it.
+    NoSolution {
+        reason: String,
+    },
+}
+
+/// Describes the first piece of input a parser could not make sense of.
+///
+/// `line` and `column` are 1-based, and together with `day` they are filled in by [`Error::locate`]
+/// once the failing text can be related back to the complete puzzle input.
+#[derive(Clone, Eq)]
+pub struct ParseError {
+    pub day: u8,
+    pub line: usize,
+    pub column: usize,
+    pub found: String,
+    pub expected: String,
+    /// Address of the first byte of `found` in the original input, until it has been located.
+    /// Left out of comparisons and debug output, which would otherwise depend on where the input
+    /// happens to live in memory.
+    address: usize,
+}
+
+impl PartialEq for ParseError {
+    fn eq(&self, other: &Self) -> bool {
+        (
+            self.day,
+            self.line,
+            self.column,
+            &self.found,
+            &self.expected,
+        ) == (
+            other.day,
+            other.line,
+            other.column,
+            &other.found,
+            &other.expected,
+        )
+    }
+}
+
+impl Debug for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ParseError")
+            .field("day", &self.day)
+            .field("line", &self.line)
+            .field("column", &self.column)
+            .field("found", &self.found)
+            .field("expected", &self.expected)
+            .finish()
+    }
+}
+
+impl Error {
+    /// Creates a parse error for `found`, which must be a slice of the puzzle input.
+    pub(crate) fn parse(found: &str, expected: impl Into<String>) -> Self {
+        let found_text = found.lines().next().unwrap_or_default();
+
+        Error::Parse(ParseError {
+            day: 0,
+            line: 0,
+            column: 0,
+            found: found_text.to_string(),
+            expected: expected.into(),
+            address: found.as_ptr() as usize,
+        })
+    }
+
+    /// Resolves the line and column of a parse error against the input it was produced from.
+    pub(crate) fn locate(self, day: u8, input: &str) -> Self {
+        let Error::Parse(mut err) = self else {
+            return self;
+        };
+
+        let start = input.as_ptr() as usize;
+
+        err.day = day;
+
+        if (start..=start + input.len()).contains(&err.address) {
+            let offset = err.address - start;
+            let before = &input[..offset];
+
+            err.line = before.matches('\n').count() + 1;
+            err.column = before.len() - before.rfind('\n').map_or(0, |idx| idx + 1) + 1;
+        }
+
+        Error::Parse(err)
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::UnknownDay { day } => write!(f, "No puzzles for day {day}"),
+            Error::UnknownPuzzle { day, part } => {
+                write!(f, "No solution for day {day} part {part}")
+            }
+            Error::UnknownParam { name } => write!(f, "No puzzle parameter named {name:?}"),
+            Error::InvalidParam { name, value } => {
+                write!(f, "Invalid value {value:?} for puzzle parameter {name:?}")
+            }
+            Error::Parse(err) => write!(f, "{err}"),
+            Error::NoSolution { reason } => write!(f, "No solution: {reason}"),
+        }
+    }
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Malformed input for day {} at {}:{}: expected {}, found {:?}",
+            self.day, self.line, self.column, self.expected, self.found
+        )
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// Lets the parsers of [`crate::util::parse`] fail with errors that point into the input.
+impl<'a> nom::error::ParseError<&'a str> for Error {
+    fn from_error_kind(input: &'a str, kind: nom::error::ErrorKind) -> Self {
+        Error::parse(input, kind.description())
+    }
+
+    fn append(_: &'a str, _: nom::error::ErrorKind, other: Self) -> Self {
+        other
+    }
+}
+
+impl From<nom::Err<Error>> for Error {
+    fn from(value: nom::Err<Error>) -> Self {
+        match value {
+            nom::Err::Incomplete(_) => unreachable!("Only complete parsers are used"),
+            nom::Err::Error(err) | nom::Err::Failure(err) => err,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Error;
+    use crate::util::parse::{all, tag};
+
+    #[test]
+    fn locate() {
+        let input = "R 4\nU x\n";
+        let err = Error::parse(&input[6..7], "a number").locate(9, input);
+
+        let Error::Parse(err) = err else {
+            panic!("Expected a parse error");
+        };
+
+        assert_eq!((err.day, err.line, err.column), (9, 2, 3));
+        assert_eq!(err.found, "x");
+        assert_eq!(err.expected, "a number");
+    }
+
+    #[test]
+    fn locate_missing_delimiter() {
+        let input = "1,2";
+        let err = all(input, tag(" -> ")).unwrap_err().locate(14, input);
+
+        let Error::Parse(err) = err else {
+            panic!("Expected a parse error");
+        };
+
+        assert_eq!((err.line, err.column), (1, 1));
+        assert_eq!(err.found, "1,2");
+    }
+
+    #[test]
+    fn same_error_from_copies_of_the_input() {
+        let (first, second) = ("1,2".to_string(), "1,2".to_string());
+        let err = |input: &str| all(input, tag(" -> ")).unwrap_err();
+
+        assert_eq!(err(&first), err(&second));
+        assert_eq!(
+            err(&first).locate(14, &first),
+            err(&second).locate(14, &second)
+        );
+        assert!(!format!("{:?}", err(&first)).contains("address"));
+    }
+}
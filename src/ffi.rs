@@ -0,0 +1,157 @@
+//! A C interface to [`crate::solve`], for the `cdylib` build of this crate.
+//!
+//! `build.rs` generates the matching header, which is checked in at `include/advent_of_code.h`.
+
+use std::{
+    ffi::{c_char, CString},
+    panic, slice,
+};
+
+use crate::{solve, Error};
+
+/// Outcome of `aoc_solve`.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AocStatus {
+    /// The answer was found.
+    Ok = 0,
+    /// There is no such day or part.
+    UnknownPuzzle = 1,
+    /// The input is not valid UTF-8.
+    InvalidUtf8 = 2,
+    /// The input is not what the puzzle expects.
+    InvalidInput = 3,
+    /// The solver panicked.
+    Panicked = 4,
+    /// `answer` is null, or `input` is null while `len` isn't 0.
+    InvalidArgument = 5,
+}
+
+/// Solves `part` of `day` for the `len` bytes of UTF-8 at `input`, which don't have to end in a
+/// NUL.
+///
+/// Unless the arguments are invalid, `*answer` is set to a NUL terminated string that must be
+/// released with `aoc_free_string`. It holds the answer when the puzzle was solved, and describes
+/// the problem otherwise.
+///
+/// # Safety
+///
+/// `input` must point to `len` readable bytes, unless `len` is 0, and `answer` must be null or
+/// point to writable memory for a pointer.
+#[no_mangle]
+pub unsafe extern "C" fn aoc_solve(
+    day: u8,
+    part: u8,
+    input: *const u8,
+    len: usize,
+    answer: *mut *mut c_char,
+) -> AocStatus {
+    if answer.is_null() || (input.is_null() && len != 0) {
+        return AocStatus::InvalidArgument;
+    }
+
+    let input = match len {
+        0 => &[],
+        _ => slice::from_raw_parts(input, len),
+    };
+
+    let (status, text) = match std::str::from_utf8(input) {
+        Ok(input) => match panic::catch_unwind(|| solve(day, part, input)) {
+            Ok(Ok(result)) => (AocStatus::Ok, result.to_string()),
+            Ok(Err(err @ (Error::UnknownDay { .. } | Error::UnknownPuzzle { .. }))) => {
+                (AocStatus::UnknownPuzzle, err.to_string())
+            }
+            Ok(Err(err)) => (AocStatus::InvalidInput, err.to_string()),
+            Err(payload) => (AocStatus::Panicked, panic_message(payload)),
+        },
+        Err(err) => (
+            AocStatus::InvalidUtf8,
+            format!("The input is not UTF-8: {err}"),
+        ),
+    };
+
+    *answer = c_string(text).into_raw();
+
+    status
+}
+
+/// Releases a string returned by `aoc_solve`. Does nothing when `s` is null.
+///
+/// # Safety
+///
+/// `s` must be null or a string from `aoc_solve` that hasn't been released yet.
+#[no_mangle]
+pub unsafe extern "C" fn aoc_free_string(s: *mut c_char) {
+    if !s.is_null() {
+        drop(CString::from_raw(s));
+    }
+}
+
+fn panic_message(payload: Box<dyn std::any::Any + Send>) -> String {
+    let message = payload
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("unknown cause");
+
+    format!("Panicked: {message}")
+}
+
+/// Converts `text` for C, which can't tell a NUL inside a string from its end.
+fn c_string(text: String) -> CString {
+    CString::new(text).unwrap_or_else(|err| {
+        let mut bytes = err.into_vec();
+        bytes.retain(|&b| b != 0);
+        CString::new(bytes).unwrap()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{ffi::CStr, ptr};
+
+    use super::{aoc_free_string, aoc_solve, AocStatus};
+
+    fn solve(day: u8, part: u8, input: &[u8]) -> (AocStatus, String) {
+        let mut answer = ptr::null_mut();
+        let status = unsafe { aoc_solve(day, part, input.as_ptr(), input.len(), &mut answer) };
+        let text = unsafe { CStr::from_ptr(answer) }
+            .to_string_lossy()
+            .into_owned();
+
+        unsafe { aoc_free_string(answer) };
+
+        (status, text)
+    }
+
+    #[test]
+    fn solve_and_free() {
+        assert_eq!(
+            solve(6, 1, b"bvwbjplbgvbhsrlpgdmjqwftvncz"),
+            (AocStatus::Ok, "5".into())
+        );
+        assert_eq!(solve(25, 2, b"").0, AocStatus::UnknownPuzzle);
+        assert_eq!(solve(2, 1, b"A \xff").0, AocStatus::InvalidUtf8);
+        assert_eq!(
+            solve(2, 1, b"A Y\nB Q"),
+            (
+                AocStatus::InvalidInput,
+                "Malformed input for day 2 at 2:3: expected a response (X, Y or Z), found \"Q\""
+                    .into()
+            )
+        );
+    }
+
+    #[test]
+    fn invalid_arguments() {
+        let status = unsafe { aoc_solve(1, 1, b"1".as_ptr(), 1, ptr::null_mut()) };
+        assert_eq!(status, AocStatus::InvalidArgument);
+
+        let mut answer = ptr::null_mut();
+        let status = unsafe { aoc_solve(1, 1, ptr::null(), 1, &mut answer) };
+        assert_eq!(status, AocStatus::InvalidArgument);
+        assert!(answer.is_null());
+
+        unsafe { aoc_free_string(answer) };
+    }
+}
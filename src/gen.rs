@@ -0,0 +1,927 @@
+//! Random puzzle inputs for stress tests and benchmarks.
+//!
+//! Every generator is deterministic for a given seed, and produces input that [`crate::validate`]
+//! accepts without errors. What `size` controls differs per day and is documented on each
+//! generator; it is clamped where the puzzle only makes sense within some range.
+
+use std::collections::{BTreeSet, HashSet};
+
+use itertools::Itertools;
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+
+use crate::{day25::snafu, Error};
+
+/// Generates an input for `day` from `seed`, scaled by `size`.
+pub fn generate(day: u8, seed: u64, size: usize) -> Result<String, Error> {
+    let rng = &mut StdRng::seed_from_u64(seed);
+
+    Ok(match day {
+        1 => day1(rng, size),
+        2 => day2(rng, size),
+        3 => day3(rng, size),
+        4 => day4(rng, size),
+        5 => day5(rng, size),
+        6 => day6(rng, size),
+        7 => day7(rng, size),
+        8 => day8(rng, size),
+        9 => day9(rng, size),
+        10 => day10(rng, size),
+        11 => day11(rng, size),
+        12 => day12(rng, size),
+        13 => day13(rng, size),
+        14 => day14(rng, size),
+        15 => day15(rng, size),
+        16 => day16(rng, size),
+        17 => day17(rng, size),
+        18 => day18(rng, size),
+        19 => day19(rng, size),
+        20 => day20(rng, size),
+        21 => day21(rng, size),
+        22 => day22(rng, size),
+        23 => day23(rng, size),
+        24 => day24(rng, size),
+        25 => day25(rng, size),
+        _ => return Err(Error::UnknownDay { day }),
+    })
+}
+
+fn letters(rng: &mut StdRng, alphabet: std::ops::RangeInclusive<u8>, len: usize) -> String {
+    (0..len)
+        .map(|_| rng.gen_range(alphabet.clone()) as char)
+        .collect()
+}
+
+/// `size` elves (at least three), carrying one to ten snacks each.
+fn day1(rng: &mut StdRng, size: usize) -> String {
+    (0..size.max(3))
+        .map(|_| {
+            (0..rng.gen_range(1..=10))
+                .map(|_| rng.gen_range(1000..=60000))
+                .join("\n")
+        })
+        .join("\n\n")
+}
+
+/// `size` rounds of rock paper scissors.
+fn day2(rng: &mut StdRng, size: usize) -> String {
+    (0..size.max(1))
+        .map(|_| {
+            let opponent = ["A", "B", "C"][rng.gen_range(0..3)];
+            let response = ["X", "Y", "Z"][rng.gen_range(0..3)];
+
+            format!("{opponent} {response}")
+        })
+        .join("\n")
+}
+
+/// `size` groups of three rucksacks.
+///
+/// Each group gets its own badge and splits the other 51 items between its rucksacks, so that the
+/// badge is the only item all three have in common. Within a rucksack, only one item is put into
+/// both compartments.
+fn day3(rng: &mut StdRng, size: usize) -> String {
+    let mut items = (b'a'..=b'z').chain(b'A'..=b'Z').collect::<Vec<_>>();
+    let mut rucksacks = Vec::new();
+
+    for _ in 0..size.max(1) {
+        items.shuffle(rng);
+
+        let (&badge, rest) = items.split_first().unwrap();
+
+        for own in rest.chunks(17) {
+            let (&shared, own) = own.split_first().unwrap();
+            let (first, second) = own.split_at(own.len() / 2);
+            let len = rng.gen_range(2..=12);
+            let badge_in_first = rng.gen_bool(0.5);
+
+            let mut rucksack = String::new();
+
+            for (pool, has_badge) in [(first, badge_in_first), (second, !badge_in_first)] {
+                let mut compartment = vec![shared];
+
+                if has_badge {
+                    compartment.push(badge);
+                }
+
+                while compartment.len() < len {
+                    compartment.push(*pool.choose(rng).unwrap());
+                }
+
+                compartment.shuffle(rng);
+                rucksack.extend(compartment.into_iter().map(char::from));
+            }
+
+            rucksacks.push(rucksack);
+        }
+    }
+
+    rucksacks.join("\n")
+}
+
+/// `size` pairs of section assignments.
+fn day4(rng: &mut StdRng, size: usize) -> String {
+    let range = |rng: &mut StdRng| {
+        let start = rng.gen_range(1..=99);
+        let end = rng.gen_range(start..=99);
+
+        format!("{start}-{end}")
+    };
+
+    (0..size.max(1))
+        .map(|_| format!("{},{}", range(rng), range(rng)))
+        .join("\n")
+}
+
+/// Two to nine stacks of crates, rearranged by up to `size` moves that never empty a stack.
+fn day5(rng: &mut StdRng, size: usize) -> String {
+    let count = rng.gen_range(2..=9);
+
+    let mut stacks = (0..count)
+        .map(|_| {
+            let height = rng.gen_range(1..=8);
+            letters(rng, b'A'..=b'Z', height)
+                .chars()
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+
+    let height = stacks.iter().map(Vec::len).max().unwrap();
+
+    let mut drawing = (0..height)
+        .rev()
+        .map(|level| {
+            stacks
+                .iter()
+                .map(|stack| match stack.get(level) {
+                    Some(c) => format!("[{c}]"),
+                    None => "   ".to_string(),
+                })
+                .join(" ")
+        })
+        .collect::<Vec<_>>();
+
+    drawing.push((1..=count).map(|n| format!(" {n} ")).join(" "));
+
+    let mut moves = Vec::new();
+
+    for _ in 0..size {
+        let sources = (0..count)
+            .filter(|&idx| stacks[idx].len() > 1)
+            .collect::<Vec<_>>();
+
+        let Some(&from) = sources.choose(rng) else {
+            break;
+        };
+
+        let to = (from + rng.gen_range(1..count)) % count;
+        let n = rng.gen_range(1..stacks[from].len());
+        let rest = stacks[from].len() - n;
+        let crates = stacks[from].split_off(rest);
+
+        stacks[to].extend(crates);
+        moves.push(format!("move {n} from {} to {}", from + 1, to + 1));
+    }
+
+    format!("{}\n\n{}", drawing.join("\n"), moves.join("\n"))
+}
+
+/// A datastream of about `size` characters, with both kinds of marker somewhere in the middle.
+fn day6(rng: &mut StdRng, size: usize) -> String {
+    let mut marker = (b'a'..=b'z').map(char::from).collect::<Vec<_>>();
+
+    marker.shuffle(rng);
+    marker.truncate(14);
+
+    // Four letters are enough for a start-of-packet marker, but never for a start-of-message one.
+    let before = letters(rng, b'a'..=b'd', size / 2);
+    let after = letters(rng, b'a'..=b'z', size / 2);
+
+    format!("{before}{}{after}", marker.iter().join(""))
+}
+
+/// A terminal session exploring `size` directories, whose files leave too little space for the
+/// update on the disk.
+fn day7(rng: &mut StdRng, size: usize) -> String {
+    struct Dir {
+        name: String,
+        dirs: Vec<usize>,
+        files: Vec<(u64, String)>,
+    }
+
+    fn explore(dirs: &[Dir], idx: usize, lines: &mut Vec<String>) {
+        let dir = &dirs[idx];
+
+        lines.push("$ ls".to_string());
+        lines.extend(
+            dir.dirs
+                .iter()
+                .map(|&sub| format!("dir {}", dirs[sub].name)),
+        );
+        lines.extend(
+            dir.files
+                .iter()
+                .map(|(size, name)| format!("{size} {name}")),
+        );
+
+        for &sub in &dir.dirs {
+            lines.push(format!("$ cd {}", dirs[sub].name));
+            explore(dirs, sub, lines);
+            lines.push("$ cd ..".to_string());
+        }
+    }
+
+    let count = size.max(1);
+    let max_file_size = (30_000_000 / (4 * count as u64)).clamp(1, 300_000);
+
+    let mut dirs = Vec::<Dir>::new();
+    let mut total = 0;
+
+    for idx in 0..count {
+        let mut names = HashSet::new();
+
+        let files = (0..rng.gen_range(0..=4))
+            .filter_map(|_| {
+                let len = rng.gen_range(1..=8);
+                let name = letters(rng, b'a'..=b'z', len);
+                let name = match rng.gen_bool(0.5) {
+                    true => format!("{name}.{}", letters(rng, b'a'..=b'z', 3)),
+                    false => name,
+                };
+
+                names.insert(name.clone()).then(|| {
+                    let size = rng.gen_range(1..=max_file_size);
+                    total += size;
+                    (size, name)
+                })
+            })
+            .collect();
+
+        dirs.push(Dir {
+            name: String::new(),
+            dirs: Vec::new(),
+            files,
+        });
+
+        if idx > 0 {
+            let parent = rng.gen_range(0..idx);
+
+            loop {
+                let len = rng.gen_range(1..=8);
+                let name = letters(rng, b'a'..=b'z', len);
+
+                let taken = dirs[parent].files.iter().any(|(_, file)| *file == name)
+                    || dirs[parent].dirs.iter().any(|&sub| dirs[sub].name == name);
+
+                if !taken {
+                    dirs[idx].name = name;
+                    break;
+                }
+            }
+
+            dirs[parent].dirs.push(idx);
+        }
+    }
+
+    // One big file in the root brings the used space to somewhere between 40M and 70M.
+    let used = rng.gen_range(40_000_001..=70_000_000);
+
+    dirs[0].files.push((used - total, "big.dat".to_string()));
+
+    let mut lines = vec!["$ cd /".to_string()];
+
+    explore(&dirs, 0, &mut lines);
+
+    lines.join("\n")
+}
+
+/// A forest of `size` by `size` trees.
+fn day8(rng: &mut StdRng, size: usize) -> String {
+    let size = size.max(1);
+
+    (0..size)
+        .map(|_| letters(rng, b'0'..=b'9', size))
+        .join("\n")
+}
+
+/// `size` moves of the head of the rope.
+fn day9(rng: &mut StdRng, size: usize) -> String {
+    (0..size.max(1))
+        .map(|_| {
+            let direction = ["D", "L", "R", "U"][rng.gen_range(0..4)];
+
+            format!("{direction} {}", rng.gen_range(1..=20))
+        })
+        .join("\n")
+}
+
+/// A program of `size` instructions, or more if that's needed to draw all 240 pixels.
+fn day10(rng: &mut StdRng, size: usize) -> String {
+    let mut lines = Vec::new();
+    let mut cycles = 0;
+    let mut x = 1;
+
+    while cycles < 240 || lines.len() < size {
+        if rng.gen_bool(0.4) {
+            lines.push("noop".to_string());
+            cycles += 1;
+        } else {
+            let mut value = rng.gen_range(-10..=10);
+
+            if !(-5..=45).contains(&(x + value)) {
+                value = -value;
+            }
+
+            x += value;
+            lines.push(format!("addx {value}"));
+            cycles += 2;
+        }
+    }
+
+    lines.join("\n")
+}
+
+/// Between four and nine monkeys, `size` deciding how many.
+///
+/// Monkeys 0 and 1 square and multiply the worry levels, but nobody throws to them, so that the
+/// worry levels in part 1 stay small. The product of the divisors fits in 32 bits, so squaring
+/// worry levels in part 2 can't overflow either.
+fn day11(rng: &mut StdRng, size: usize) -> String {
+    let count = size.clamp(4, 9);
+
+    let mut divisors = [2, 3, 5, 7, 11, 13, 17, 19, 23];
+    divisors.shuffle(rng);
+
+    (0..count)
+        .map(|idx| {
+            let items = (0..rng.gen_range(1..=6))
+                .map(|_| rng.gen_range(50..=99))
+                .join(", ");
+
+            let operation = match idx {
+                0 => "old * old".to_string(),
+                1 => format!("old * {}", rng.gen_range(2..=19)),
+                _ => format!("old + {}", rng.gen_range(1..=8)),
+            };
+
+            let targets = (2..count).filter(|&target| target != idx).collect::<Vec<_>>();
+
+            format!(
+                "Monkey {idx}:\n  Starting items: {items}\n  Operation: new = {operation}\n  Test: \
+                 divisible by {}\n    If true: throw to monkey {}\n    If false: throw to monkey {}",
+                divisors[idx],
+                targets.choose(rng).unwrap(),
+                targets.choose(rng).unwrap(),
+            )
+        })
+        .join("\n\n")
+}
+
+/// A map `size` squares wide, climbing from a to z along one of its rows.
+///
+/// The climb goes up at most one letter per square, so the map is at least 26 squares wide.
+fn day12(rng: &mut StdRng, size: usize) -> String {
+    let width = size.max(26);
+    let height = (size / 4).max(1);
+    let path = rng.gen_range(0..height);
+
+    (0..height)
+        .map(|y| {
+            (0..width)
+                .map(|x| {
+                    let ramp = (x * 25 / (width - 1)) as i32;
+
+                    match (x, y) {
+                        (0, y) if y == path => 'S',
+                        (x, y) if y == path && x == width - 1 => 'E',
+                        (_, y) if y == path => (b'a' + ramp as u8) as char,
+                        _ => (b'a' + (ramp + rng.gen_range(-3..=2)).clamp(0, 25) as u8) as char,
+                    }
+                })
+                .collect::<String>()
+        })
+        .join("\n")
+}
+
+/// `size` pairs of packets.
+fn day13(rng: &mut StdRng, size: usize) -> String {
+    fn packet(rng: &mut StdRng, depth: usize) -> String {
+        let values = (0..rng.gen_range(0..=4))
+            .map(|_| {
+                if depth < 3 && rng.gen_bool(0.3) {
+                    packet(rng, depth + 1)
+                } else {
+                    rng.gen_range(0..=10).to_string()
+                }
+            })
+            .join(",");
+
+        format!("[{values}]")
+    }
+
+    let packet = |rng: &mut StdRng| loop {
+        let packet = packet(rng, 0);
+
+        if packet != "[[2]]" && packet != "[[6]]" {
+            return packet;
+        }
+    };
+
+    (0..size.max(1))
+        .map(|_| format!("{}\n{}", packet(rng), packet(rng)))
+        .join("\n\n")
+}
+
+/// `size` paths of rock below the sand source.
+fn day14(rng: &mut StdRng, size: usize) -> String {
+    let depth = (size as i32 / 2 + 10).min(180);
+
+    (0..size.max(1))
+        .map(|_| {
+            let mut x = rng.gen_range(470..=530);
+            let mut y = rng.gen_range(2..=depth);
+            let mut points = vec![format!("{x},{y}")];
+
+            for idx in 0..rng.gen_range(1..=4) {
+                let delta = rng.gen_range(1..=6) * if rng.gen_bool(0.5) { 1 } else { -1i32 };
+
+                if idx % 2 == 0 {
+                    x += delta;
+                } else {
+                    y = (y + delta).clamp(1, depth);
+                }
+
+                points.push(format!("{x},{y}"));
+            }
+
+            points.join(" -> ")
+        })
+        .join("\n")
+}
+
+/// `size` sensors around a single hidden beacon within the default [`crate::day15::Params`].
+///
+/// Four huge sensors, each covering everything on one side of the hidden beacon when looking along
+/// the diagonals `x + y` and `x - y`, leave only that one position uncovered. The other sensors
+/// stop short of it.
+fn day15(rng: &mut StdRng, size: usize) -> String {
+    // Odd, so that all four sensors end up on whole coordinates.
+    const REACH: i64 = 20_000_001;
+
+    let max_pos = crate::day15::Params::default().max_pos as i64;
+    let hidden = (rng.gen_range(1..max_pos), rng.gen_range(0..=max_pos));
+    let (u, v) = (hidden.0 + hidden.1, hidden.0 - hidden.1);
+
+    let mut sensors = [
+        (u - 1 - REACH, v),
+        (u + 1 + REACH, v),
+        (u, v - 1 - REACH),
+        (u, v + 1 + REACH),
+    ]
+    .into_iter()
+    .map(|(u, v)| {
+        (
+            ((u + v) / 2, (u - v) / 2),
+            ((u + v) / 2 + REACH, (u - v) / 2),
+        )
+    })
+    .collect::<Vec<_>>();
+
+    while sensors.len() < size + 4 {
+        let sensor = (rng.gen_range(0..=max_pos), rng.gen_range(0..=max_pos));
+        let distance = sensor.0.abs_diff(hidden.0) + sensor.1.abs_diff(hidden.1);
+
+        if distance < 2 {
+            continue;
+        }
+
+        let reach = rng.gen_range(1..distance.min(1_000_000)) as i64;
+        let dx = rng.gen_range(0..=reach);
+        let mut sign = || if rng.gen_bool(0.5) { 1 } else { -1 };
+        let beacon = (sensor.0 + dx * sign(), sensor.1 + (reach - dx) * sign());
+
+        sensors.push((sensor, beacon));
+    }
+
+    sensors.shuffle(rng);
+
+    sensors
+        .into_iter()
+        .map(|((sx, sy), (bx, by))| {
+            format!("Sensor at x={sx}, y={sy}: closest beacon is at x={bx}, y={by}")
+        })
+        .join("\n")
+}
+
+/// A cave of `size` valves, up to fifteen of which have a flow rate.
+fn day16(rng: &mut StdRng, size: usize) -> String {
+    let count = size.clamp(2, 26 * 26);
+
+    let mut names = vec!["AA".to_string()];
+
+    while names.len() < count {
+        let name = letters(rng, b'A'..=b'Z', 2);
+
+        if !names.contains(&name) {
+            names.push(name);
+        }
+    }
+
+    let mut tunnels = vec![BTreeSet::new(); count];
+    let mut connect = |a: usize, b: usize| {
+        tunnels[a].insert(b);
+        tunnels[b].insert(a);
+    };
+
+    for idx in 1..count {
+        connect(idx, rng.gen_range(0..idx));
+    }
+
+    for _ in 0..count / 3 {
+        let (a, b) = (rng.gen_range(0..count), rng.gen_range(0..count));
+
+        if a != b {
+            connect(a, b);
+        }
+    }
+
+    let mut rates = (1..=25).collect::<Vec<u8>>();
+    rates.shuffle(rng);
+    rates.truncate((count / 4).clamp(1, 15).min(count - 1));
+
+    let mut flow_rates = vec![0; count];
+
+    for (idx, rate) in rand::seq::index::sample(rng, count - 1, rates.len())
+        .into_iter()
+        .zip(rates)
+    {
+        flow_rates[idx + 1] = rate;
+    }
+
+    (0..count)
+        .map(|idx| {
+            let leads_to = tunnels[idx].iter().map(|&other| &names[other]).join(", ");
+            let tunnels = match tunnels[idx].len() {
+                1 => format!("tunnel leads to valve {leads_to}"),
+                _ => format!("tunnels lead to valves {leads_to}"),
+            };
+
+            format!(
+                "Valve {} has flow rate={}; {tunnels}",
+                names[idx], flow_rates[idx]
+            )
+        })
+        .join("\n")
+}
+
+/// A pattern of `size` jets.
+fn day17(rng: &mut StdRng, size: usize) -> String {
+    (0..size.max(1))
+        .map(|_| if rng.gen_bool(0.5) { '<' } else { '>' })
+        .collect()
+}
+
+/// A droplet of `size` cubes, grown from the middle of a 20×20×20 space so that it may enclose
+/// some air.
+fn day18(rng: &mut StdRng, size: usize) -> String {
+    const SIDE: u8 = 20;
+
+    let count = size.clamp(1, (SIDE as usize).pow(3));
+    let mut cubes = vec![(SIDE / 2, SIDE / 2, SIDE / 2)];
+    let mut seen = cubes.iter().copied().collect::<HashSet<_>>();
+
+    while cubes.len() < count {
+        let (x, y, z) = *cubes.choose(rng).unwrap();
+        let step =
+            |c: u8, rng: &mut StdRng| c.saturating_add_signed(rng.gen_range(-1..=1)).min(SIDE - 1);
+        let cube = (step(x, rng), step(y, rng), step(z, rng));
+
+        if seen.insert(cube) {
+            cubes.push(cube);
+        }
+    }
+
+    cubes.shuffle(rng);
+
+    cubes
+        .into_iter()
+        .map(|(x, y, z)| format!("{x},{y},{z}"))
+        .join("\n")
+}
+
+/// `size` blueprints.
+fn day19(rng: &mut StdRng, size: usize) -> String {
+    (1..=size.max(1))
+        .map(|id| {
+            format!(
+                "Blueprint {id}: Each ore robot costs {} ore. Each clay robot costs {} ore. Each \
+                 obsidian robot costs {} ore and {} clay. Each geode robot costs {} ore and {} \
+                 obsidian.",
+                rng.gen_range(2..=4),
+                rng.gen_range(2..=4),
+                rng.gen_range(2..=4),
+                rng.gen_range(5..=20),
+                rng.gen_range(2..=4),
+                rng.gen_range(5..=20),
+            )
+        })
+        .join("\n")
+}
+
+/// An encrypted file of `size` numbers, exactly one of which is 0.
+fn day20(rng: &mut StdRng, size: usize) -> String {
+    let count = size.clamp(2, u16::MAX as usize + 1);
+
+    let mut numbers = (0..count)
+        .map(|_| {
+            let value = rng.gen_range(1..=10000);
+
+            if rng.gen_bool(0.5) {
+                value
+            } else {
+                -value
+            }
+        })
+        .collect::<Vec<_>>();
+
+    numbers[rng.gen_range(0..count)] = 0;
+    numbers.iter().join("\n")
+}
+
+/// About `size` monkeys, yelling numbers that work out exactly in both parts.
+///
+/// The monkeys are generated from root down, picking each job to give a value that was decided
+/// before. Along the way to humn only jobs are used that keep working with the larger number humn
+/// yells in part 1.
+fn day21(rng: &mut StdRng, size: usize) -> String {
+    /// The smallest factor of `value` up to 10, or 1 if there is none.
+    fn smallest_divisor(value: u64) -> u64 {
+        (2..=10)
+            .find(|&d| value > 0 && value.is_multiple_of(d))
+            .unwrap_or(1)
+    }
+
+    struct Monkeys<'a> {
+        rng: &'a mut StdRng,
+        names: HashSet<String>,
+        lines: Vec<String>,
+    }
+
+    impl Monkeys<'_> {
+        fn name(&mut self) -> String {
+            loop {
+                let name = letters(self.rng, b'a'..=b'z', 4);
+
+                if name != "root" && name != "humn" && self.names.insert(name.clone()) {
+                    return name;
+                }
+            }
+        }
+
+        fn split(&mut self, budget: usize) -> (usize, usize) {
+            let left = self.rng.gen_range(0..budget);
+
+            (left, budget - 1 - left)
+        }
+
+        fn job(&mut self, lhs: String, op: char, rhs: String, swap: bool) -> String {
+            let name = self.name();
+
+            let (lhs, rhs) = if swap { (rhs, lhs) } else { (lhs, rhs) };
+
+            self.lines.push(format!("{name}: {lhs} {op} {rhs}"));
+            name
+        }
+
+        /// A monkey that yells `value`, without humn being involved.
+        fn yelling(&mut self, value: u64, budget: usize) -> String {
+            if budget == 0 {
+                let name = self.name();
+                self.lines.push(format!("{name}: {value}"));
+                return name;
+            }
+
+            let (left, right) = self.split(budget);
+            let divisor = smallest_divisor(value);
+
+            let (lhs, op, rhs) = match self.rng.gen_range(0..4) {
+                0 if value < 1 << 40 => (value * 2, '/', 2),
+                1 => {
+                    let rhs = self.rng.gen_range(0..=100);
+                    (value + rhs, '-', rhs)
+                }
+                2 if divisor > 1 => (divisor, '*', value / divisor),
+                _ => {
+                    let lhs = self.rng.gen_range(0..=value);
+                    (lhs, '+', value - lhs)
+                }
+            };
+
+            let lhs = self.yelling(lhs, left);
+            let rhs = self.yelling(rhs, right);
+
+            self.job(lhs, op, rhs, false)
+        }
+
+        /// A monkey that yells `value` when humn yells the answer to part 2, and returns that
+        /// answer too.
+        fn listening(&mut self, value: u64, budget: usize) -> (String, u64) {
+            if budget == 0 {
+                return ("humn".to_string(), value);
+            }
+
+            let (left, right) = self.split(budget);
+            let divisor = smallest_divisor(value);
+            let swap = self.rng.gen_bool(0.5);
+
+            let (child, op, other, swap) = match self.rng.gen_range(0..4) {
+                0 if value < 1 << 40 => {
+                    let other = self.rng.gen_range(1..=5);
+                    (value * other, '/', other, false)
+                }
+                1 => {
+                    let other = self.rng.gen_range(0..=100);
+                    (value + other, '-', other, false)
+                }
+                2 if divisor > 1 => (value / divisor, '*', divisor, swap),
+                _ => {
+                    let other = self.rng.gen_range(0..=value);
+                    (value - other, '+', other, swap)
+                }
+            };
+
+            let (child, answer) = self.listening(child, left);
+            let other = self.yelling(other, right);
+
+            (self.job(child, op, other, swap), answer)
+        }
+    }
+
+    let mut monkeys = Monkeys {
+        rng,
+        names: HashSet::new(),
+        lines: Vec::new(),
+    };
+
+    let budget = size.max(2) - 1;
+    let left = monkeys.rng.gen_range(1..=budget);
+    let value = monkeys.rng.gen_range(1000..=1_000_000_000);
+
+    let (listening, answer) = monkeys.listening(value, left);
+    let yelling = monkeys.yelling(value, budget - left);
+    let (lhs, rhs) = match monkeys.rng.gen_bool(0.5) {
+        true => (listening, yelling),
+        false => (yelling, listening),
+    };
+
+    let humn = answer + monkeys.rng.gen_range(0..=100);
+
+    monkeys.lines.push(format!("root: {lhs} + {rhs}"));
+    monkeys.lines.push(format!("humn: {humn}"));
+    monkeys.lines.shuffle(monkeys.rng);
+    monkeys.lines.join("\n")
+}
+
+/// A map folding into a cube the way part 2 expects, followed by a path of `size` moves.
+fn day22(rng: &mut StdRng, size: usize) -> String {
+    const FACES: [(usize, usize); 6] = [(1, 0), (2, 0), (1, 1), (0, 2), (1, 2), (0, 3)];
+
+    let map = (0..200)
+        .map(|y| {
+            let width = FACES
+                .iter()
+                .filter(|face| face.1 == y / 50)
+                .map(|face| (face.0 + 1) * 50)
+                .max()
+                .unwrap();
+
+            (0..width)
+                .map(|x| match FACES.contains(&(x / 50, y / 50)) {
+                    false => ' ',
+                    true if (x, y) != (50, 0) && rng.gen_bool(0.08) => '#',
+                    true => '.',
+                })
+                .collect::<String>()
+        })
+        .join("\n");
+
+    let mut path = rng.gen_range(1..=50).to_string();
+
+    for _ in 1..size.max(1) {
+        path.push(if rng.gen_bool(0.5) { 'L' } else { 'R' });
+        path.push_str(&rng.gen_range(1..=50).to_string());
+    }
+
+    format!("{map}\n\n{path}")
+}
+
+/// A grove of `size` by `size` tiles, with elves on about a third of them.
+fn day23(rng: &mut StdRng, size: usize) -> String {
+    let size = size.max(1);
+    let first = rng.gen_range(0..size * size);
+
+    (0..size)
+        .map(|y| {
+            (0..size)
+                .map(|x| match y * size + x == first || rng.gen_bool(0.35) {
+                    true => '#',
+                    false => '.',
+                })
+                .collect::<String>()
+        })
+        .join("\n")
+}
+
+/// A basin about `size` tiles wide that can always be crossed.
+///
+/// Horizontal blizzards only start in odd rows and vertical ones only in odd columns, so tiles in
+/// an even row and column are never hit. All blizzards of a row or column blow the same way and
+/// leave at least one tile free, so crossing one only takes waiting for the gap to come by.
+fn day24(rng: &mut StdRng, size: usize) -> String {
+    let width = size.max(3) | 1;
+    let height = (width / 4).max(3) | 1;
+
+    let columns = (0..width)
+        .map(|_| ['^', 'v'][rng.gen_range(0..2)])
+        .collect_vec();
+
+    let mut lines = vec![format!("#.{}", "#".repeat(width))];
+
+    for y in 0..height {
+        let wind = ['<', '>'][rng.gen_range(0..2)];
+        let row = (0..width)
+            .map(|x| {
+                if y % 2 == 1 && x > 0 && rng.gen_bool(0.3) {
+                    wind
+                } else if x % 2 == 1 && y % 2 == 0 && rng.gen_bool(0.3) {
+                    columns[x]
+                } else {
+                    '.'
+                }
+            })
+            .collect::<String>();
+
+        lines.push(format!("#{row}#"));
+    }
+
+    lines.push(format!("{}.#", "#".repeat(width)));
+    lines.join("\n")
+}
+
+/// `size` fuel requirements in SNAFU.
+fn day25(rng: &mut StdRng, size: usize) -> String {
+    (0..size.max(1))
+        .map(|_| {
+            let max = 10u64.pow(rng.gen_range(1..=12));
+            snafu::stringify(rng.gen_range(1..=max))
+        })
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Severity;
+
+    /// Puzzles that take too long with generated input in a debug build.
+    const SLOW: [(u8, u8); 5] = [(15, 2), (16, 1), (16, 2), (19, 1), (19, 2)];
+
+    #[test]
+    fn deterministic() {
+        for day in 1..=25 {
+            assert_eq!(super::generate(day, 7, 20), super::generate(day, 7, 20));
+        }
+
+        assert_ne!(super::generate(1, 1, 20), super::generate(1, 2, 20));
+        assert!(super::generate(26, 1, 20).is_err());
+    }
+
+    #[test]
+    fn valid() {
+        for day in 1..=25 {
+            for seed in 0..5 {
+                let input = super::generate(day, seed, 20).unwrap();
+
+                let errors = crate::validate(day, &input)
+                    .unwrap()
+                    .into_iter()
+                    .filter(|diagnostic| diagnostic.severity == Severity::Error)
+                    .collect::<Vec<_>>();
+
+                assert_eq!(errors, [], "day {day}, seed {seed}:\n{input}");
+            }
+        }
+    }
+
+    #[test]
+    fn solvable() {
+        for (day, part) in crate::PUZZLES {
+            if SLOW.contains(&(day, part)) {
+                continue;
+            }
+
+            let input = super::generate(day, 3, 20).unwrap();
+            let answer = crate::solve(day, part, &input);
+
+            assert!(answer.is_ok(), "day {day} part {part}: {answer:?}\n{input}");
+        }
+    }
+}
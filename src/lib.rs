@@ -1,12 +1,19 @@
-#![feature(exclusive_range_pattern)]
-#![feature(generators, iter_from_generator)]
-
 extern crate aoc_runner;
 
 #[macro_use]
 extern crate aoc_runner_derive;
 
-mod util;
+mod answer;
+mod budget;
+mod diagnostic;
+mod error;
+pub mod ffi;
+pub mod gen;
+pub mod params;
+mod registry;
+mod stats;
+pub mod util;
+pub mod visualize;
 
 mod day1;
 mod day2;
@@ -36,4 +43,15 @@ mod day23;
 mod day24;
 mod day25;
 
+pub use answer::Answer;
+pub use budget::{Budget, Cancel, Outcome, Progress};
+pub use diagnostic::{Diagnostic, Severity};
+pub use error::{Error, ParseError};
+pub use params::Params;
+pub use registry::{
+    parse, solve, solve_parsed, solve_parsed_within, solve_with, solve_with_stats, solve_within,
+    validate, visualize, Parsed, PUZZLES, VISUALIZED,
+};
+pub use stats::Stats;
+
 aoc_lib! { year = 2022 }
@@ -1,3 +1,1003 @@
-use aoc_runner_derive::aoc_main;
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs,
+    io::{self, Read},
+    ops::RangeInclusive,
+    panic,
+    path::{Path, PathBuf},
+    process::ExitCode,
+    time::{Duration, Instant},
+};
 
-aoc_main! { lib = advent_of_code }
+use advent_of_code::{
+    gen, params, parse, solve_parsed, solve_parsed_within, validate,
+    visualize::{self, Recorder},
+    Budget, Params, Progress, Severity, Stats, PUZZLES, VISUALIZED,
+};
+use clap::{Parser, ValueEnum};
+use serde::{Deserialize, Serialize};
+
+/// Runs the Advent of Code 2022 solutions and reports their answers and timings.
+#[derive(Parser)]
+#[command(name = "advent-of-code")]
+struct Args {
+    /// Days to run, as single days or ranges such as `3-7` [default: all days]
+    #[arg(value_parser = parse_days)]
+    days: Vec<RangeInclusive<u8>>,
+
+    /// Only run this part
+    #[arg(short, long, value_parser = clap::value_parser!(u8).range(1..=2))]
+    part: Option<u8>,
+
+    /// Directory containing `dayN.txt` files, a single input file, or `-` to read from stdin
+    #[arg(short, long, default_value = "input/2022")]
+    input: PathBuf,
+
+    /// Solve random inputs generated from this seed instead of reading them
+    #[arg(long, value_name = "SEED", conflicts_with_all = ["input", "verify"])]
+    generate: Option<u64>,
+
+    /// Rough size of the generated inputs, e.g. the number of lines or the width of a grid
+    #[arg(long, default_value_t = 100, requires = "generate")]
+    size: usize,
+
+    #[arg(short, long, value_enum, default_value_t = Format::Text)]
+    format: Format,
+
+    /// Override a puzzle parameter, e.g. `day15.target_y=10` (see `--list-params`)
+    #[arg(long = "param", value_name = "NAME=VALUE")]
+    params: Vec<String>,
+
+    /// Print the names and default values of all puzzle parameters
+    #[arg(long)]
+    list_params: bool,
+
+    /// Compare every answer against the expected answers
+    #[arg(long)]
+    verify: bool,
+
+    /// File with the expected answers [default: `answers.toml` in the input directory, or next to a
+    /// single input file; required for input from stdin]
+    #[arg(long, requires = "verify")]
+    answers: Option<PathBuf>,
+
+    /// Report how much work the searches of days 12, 16, 19 and 24 did along with their answers
+    #[arg(long, conflicts_with_all = ["bench", "validate", "visualize"])]
+    stats: bool,
+
+    /// Stop the searches of days 16 and 19 after this many seconds, with their best answer so far
+    #[arg(long, value_name = "SECONDS", conflicts_with_all = ["bench", "validate", "visualize"])]
+    timeout: Option<f64>,
+
+    /// Show how far the searches of days 16 and 19 have come, every second
+    #[arg(long, conflicts_with_all = ["bench", "validate", "visualize"])]
+    progress: bool,
+
+    /// Check the inputs for problems the solvers can't handle, without solving anything
+    #[arg(long, conflicts_with_all = ["verify", "bench"])]
+    validate: bool,
+
+    /// Print the parsed input of every day as JSON, as described by `Parsed::to_json`
+    #[arg(long, conflicts_with_all = ["verify", "bench", "validate", "visualize", "stats"])]
+    dump_parsed: bool,
+
+    /// Record the simulations of days 9, 14, 17, 23 and 24 into this directory, step by step
+    #[arg(long, value_name = "DIR", conflicts_with_all = ["verify", "bench", "validate"])]
+    visualize: Option<PathBuf>,
+
+    /// How to write the recorded steps
+    #[arg(long, value_enum, default_value_t = Frames::Ascii, requires = "visualize")]
+    frames: Frames,
+
+    /// Only record every Nth step, along with the last one
+    #[arg(long, default_value_t = 1, requires = "visualize", value_parser = clap::value_parser!(u32).range(1..))]
+    every: u32,
+
+    /// Width and height in pixels of a single tile in images
+    #[arg(long, default_value_t = 4, requires = "visualize", value_parser = clap::value_parser!(u32).range(1..))]
+    scale: u32,
+
+    /// Run every solver repeatedly and report min, median and p95 timings
+    #[arg(long, conflicts_with = "verify")]
+    bench: bool,
+
+    /// Number of timed runs per solver, after one untimed warm-up run
+    #[arg(long, default_value_t = 10, requires = "bench", value_parser = clap::value_parser!(u32).range(1..))]
+    iterations: u32,
+
+    /// Write the benchmark results to this JSON file
+    #[arg(long, requires = "bench")]
+    save_baseline: Option<PathBuf>,
+
+    /// Compare the benchmark results against a file written by `--save-baseline`
+    #[arg(long, requires = "bench")]
+    baseline: Option<PathBuf>,
+
+    /// Percentage by which a median may exceed the baseline before it counts as a slowdown
+    #[arg(long, default_value_t = 10.0, requires = "baseline")]
+    threshold: f64,
+}
+
+impl Args {
+    fn params(&self) -> Result<Params, String> {
+        let mut params = Params::default();
+
+        for param in &self.params {
+            let (name, value) = param
+                .split_once('=')
+                .ok_or_else(|| format!("Expected NAME=VALUE, found {param:?}"))?;
+
+            params.set(name, value).map_err(|err| err.to_string())?;
+        }
+
+        Ok(params)
+    }
+
+    fn selected_days(&self) -> Vec<u8> {
+        let mut days = self.selected().map(|(day, _)| day).collect::<Vec<_>>();
+        days.dedup();
+        days
+    }
+
+    fn selected(&self) -> impl Iterator<Item = (u8, u8)> + '_ {
+        PUZZLES.iter().copied().filter(|&(day, part)| {
+            (self.days.is_empty() || self.days.iter().any(|days| days.contains(&day)))
+                && self.part.is_none_or(|only| only == part)
+        })
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Format {
+    Text,
+    Json,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Frames {
+    /// One text file per step
+    Ascii,
+    /// One colour image per step
+    Ppm,
+    /// One greyscale image per step
+    Pgm,
+}
+
+impl From<Frames> for visualize::Format {
+    fn from(frames: Frames) -> Self {
+        match frames {
+            Frames::Ascii => visualize::Format::Ascii,
+            Frames::Ppm => visualize::Format::Ppm,
+            Frames::Pgm => visualize::Format::Pgm,
+        }
+    }
+}
+
+fn parse_days(s: &str) -> Result<RangeInclusive<u8>, String> {
+    let parse_day = |s: &str| match s.trim().parse::<u8>() {
+        Ok(day @ 1..=25) => Ok(day),
+        _ => Err(format!("{s:?} is not a day between 1 and 25")),
+    };
+
+    match s.split_once('-') {
+        Some((first, last)) => Ok(parse_day(first)?..=parse_day(last)?),
+        None => parse_day(s).map(|day| day..=day),
+    }
+}
+
+enum Input {
+    Directory(PathBuf),
+    /// The same input for every day, read from a file or stdin.
+    Shared(String),
+    Generated {
+        seed: u64,
+        size: usize,
+    },
+}
+
+impl Input {
+    fn open(path: PathBuf) -> io::Result<Self> {
+        if path.as_os_str() == "-" {
+            let mut input = String::new();
+            io::stdin().read_to_string(&mut input)?;
+            Ok(Input::Shared(input))
+        } else if path.is_file() {
+            Ok(Input::Shared(fs::read_to_string(path)?))
+        } else {
+            Ok(Input::Directory(path))
+        }
+    }
+
+    fn read(&self, day: u8) -> io::Result<String> {
+        match self {
+            Input::Directory(path) => fs::read_to_string(path.join(format!("day{day}.txt"))),
+            Input::Shared(input) => Ok(input.clone()),
+            &Input::Generated { seed, size } => {
+                gen::generate(day, seed, size).map_err(io::Error::other)
+            }
+        }
+    }
+}
+
+/// Expected answers keyed by `dayN` and `partN`, e.g. `answers["day1"]["part2"]`.
+type Answers = HashMap<String, HashMap<String, toml::Value>>;
+
+fn read_answers(path: &PathBuf) -> Result<Answers, String> {
+    let answers = fs::read_to_string(path).map_err(|err| err.to_string())?;
+
+    toml::from_str(&answers).map_err(|err| err.to_string())
+}
+
+/// `answers.toml` in the input directory, or next to a single input file. Input from stdin has no
+/// place of its own, so its answers have to be given explicitly.
+fn default_answers(input: &Path) -> Option<PathBuf> {
+    if input.as_os_str() == "-" {
+        None
+    } else if input.is_dir() {
+        Some(input.join("answers.toml"))
+    } else {
+        Some(input.with_file_name("answers.toml"))
+    }
+}
+
+fn expected_answer(answers: &Answers, day: u8, part: u8) -> Option<String> {
+    match answers
+        .get(&format!("day{day}"))?
+        .get(&format!("part{part}"))?
+    {
+        toml::Value::String(answer) => Some(answer.clone()),
+        answer => Some(answer.to_string()),
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum Verdict {
+    Correct,
+    Mismatch,
+    Missing,
+    Panicked,
+    Failed,
+}
+
+#[derive(Serialize)]
+struct Report {
+    day: u8,
+    part: u8,
+    answer: Option<String>,
+    /// Whether the answer is only the best one found before the timeout.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    partial: bool,
+    error: Option<String>,
+    panicked: bool,
+    /// Wall-clock time spent in the solver, excluding reading and parsing the input.
+    #[serde(rename = "time_ns", serialize_with = "as_nanos")]
+    time: Duration,
+    /// Wall-clock time spent parsing the input, on the first report of every day, as both parts
+    /// are solved from the same parsed input.
+    #[serde(
+        rename = "parse_ns",
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "as_optional_nanos"
+    )]
+    parse_time: Option<Duration>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    expected: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    verdict: Option<Verdict>,
+    #[serde(skip_serializing_if = "Option::is_none", serialize_with = "as_record")]
+    stats: Option<Stats>,
+}
+
+impl Report {
+    fn verify(&mut self, answers: &Answers) {
+        self.expected = expected_answer(answers, self.day, self.part);
+
+        self.verdict = Some(match (&self.answer, &self.expected) {
+            _ if self.panicked => Verdict::Panicked,
+            (None, _) => Verdict::Failed,
+            (Some(_), None) => Verdict::Missing,
+            (Some(answer), Some(expected)) if answer == expected => Verdict::Correct,
+            (Some(_), Some(_)) => Verdict::Mismatch,
+        });
+    }
+}
+
+fn as_nanos<S: serde::Serializer>(time: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_u64(time.as_nanos() as u64)
+}
+
+fn as_optional_nanos<S: serde::Serializer>(
+    time: &Option<Duration>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    time.map(|time| time.as_nanos() as u64)
+        .serialize(serializer)
+}
+
+fn as_record<S: serde::Serializer>(
+    stats: &Option<Stats>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    #[derive(Serialize)]
+    struct Record<'a> {
+        expanded: usize,
+        pruned: &'a BTreeMap<&'static str, usize>,
+        peak_frontier: usize,
+        visited: usize,
+    }
+
+    stats
+        .as_ref()
+        .map(|stats| Record {
+            expanded: stats.expanded,
+            pruned: &stats.pruned,
+            peak_frontier: stats.peak_frontier,
+            visited: stats.visited,
+        })
+        .serialize(serializer)
+}
+
+fn run(args: &Args, input: &Input, params: &Params) -> Vec<Report> {
+    let budget = |day, part| {
+        let mut budget = Budget::new();
+
+        if let Some(timeout) = args.timeout {
+            budget = budget.timeout(Duration::from_secs_f64(timeout));
+        }
+
+        if args.progress {
+            budget = budget.on_progress(Duration::from_secs(1), move |progress: &Progress| {
+                eprintln!(
+                    "Day {day:>2} part {part}: {:.1?}, expanded {}, best so far {}",
+                    progress.elapsed, progress.expanded, progress.best
+                )
+            });
+        }
+
+        budget
+    };
+
+    let mut reports = Vec::new();
+
+    for day in args.selected_days() {
+        let mut parse_time = None;
+        let mut parse_panicked = false;
+
+        let parsed = input
+            .read(day)
+            .map_err(|err| format!("Could not read input for day {day}: {err}"))
+            .and_then(|input| {
+                let start = Instant::now();
+                let result = panic::catch_unwind(|| parse(day, &input));
+                parse_time = Some(start.elapsed());
+                result
+                    .map_err(|payload| {
+                        parse_panicked = true;
+                        panic_message(payload)
+                    })?
+                    .map_err(|err| err.to_string())
+            });
+
+        for (_, part) in args.selected().filter(|&(selected, _)| selected == day) {
+            let mut time = Duration::ZERO;
+            let mut panicked = parse_panicked;
+
+            let result = parsed.as_ref().map_err(Clone::clone).and_then(|parsed| {
+                let start = Instant::now();
+                let budget = budget(day, part);
+                // Nothing outlives a panic but the progress callback, which only prints.
+                let result = panic::catch_unwind(panic::AssertUnwindSafe(|| {
+                    solve_parsed_within(parsed, part, params, &budget)
+                }));
+                time = start.elapsed();
+                result
+                    .map_err(|payload| {
+                        panicked = true;
+                        panic_message(payload)
+                    })?
+                    .map_err(|err| err.to_string())
+            });
+
+            let (answer, partial, error, stats) = match result {
+                Ok((outcome, stats)) => (
+                    Some(outcome.answer.to_string()),
+                    !outcome.is_final,
+                    None,
+                    stats.filter(|_| args.stats),
+                ),
+                Err(err) => (None, false, Some(err), None),
+            };
+
+            reports.push(Report {
+                day,
+                part,
+                answer,
+                partial,
+                error,
+                panicked,
+                time,
+                parse_time: parse_time.take(),
+                expected: None,
+                verdict: None,
+                stats,
+            });
+        }
+    }
+
+    reports
+}
+
+fn panic_message(payload: Box<dyn std::any::Any + Send>) -> String {
+    let message = payload
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("unknown cause");
+
+    format!("Panicked: {message}")
+}
+
+fn print_text(reports: &[Report]) {
+    for report in reports {
+        let outcome = match (&report.answer, &report.error) {
+            (Some(answer), _) if report.partial => format!("{answer} (best so far, timed out)"),
+            (Some(answer), _) => answer.clone(),
+            (None, Some(error)) => error.clone(),
+            (None, None) => unreachable!(),
+        };
+
+        let outcome = match (report.verdict, &report.expected) {
+            (None, _) => outcome,
+            (Some(Verdict::Correct), _) => format!("{outcome} [ok]"),
+            (Some(Verdict::Mismatch), Some(expected)) => {
+                format!("{outcome} [MISMATCH, expected {expected}]")
+            }
+            (Some(Verdict::Missing), _) => format!("{outcome} [no expected answer]"),
+            (Some(_), _) => format!("{outcome} [FAILED]"),
+        };
+
+        let time = match report.parse_time {
+            Some(parse_time) => format!("{:.2?}, parsed in {parse_time:.2?}", report.time),
+            None => format!("{:.2?}", report.time),
+        };
+
+        println!(
+            "Day {:>2} part {}: {} ({})",
+            report.day, report.part, outcome, time
+        );
+
+        if let Some(stats) = &report.stats {
+            println!("    {stats}");
+        }
+    }
+
+    let total = reports
+        .iter()
+        .map(|report| report.time + report.parse_time.unwrap_or_default())
+        .sum::<Duration>();
+
+    println!("Total: {total:.2?}");
+
+    if reports.iter().any(|report| report.verdict.is_some()) {
+        let count = |verdict| {
+            reports
+                .iter()
+                .filter(|report| report.verdict == Some(verdict))
+                .count()
+        };
+
+        println!(
+            "Verified: {} correct, {} mismatched, {} missing, {} panicked, {} failed",
+            count(Verdict::Correct),
+            count(Verdict::Mismatch),
+            count(Verdict::Missing),
+            count(Verdict::Panicked),
+            count(Verdict::Failed),
+        );
+    }
+}
+
+#[derive(Serialize)]
+struct Finding {
+    severity: String,
+    line: usize,
+    column: usize,
+    message: String,
+}
+
+#[derive(Serialize)]
+struct ValidationReport {
+    day: u8,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+    findings: Vec<Finding>,
+    /// Whether any finding means the puzzle can't be solved correctly.
+    failed: bool,
+}
+
+fn validate_all(args: &Args, input: &Input) -> Vec<ValidationReport> {
+    args.selected_days()
+        .into_iter()
+        .map(|day| {
+            let diagnostics = input
+                .read(day)
+                .map_err(|err| format!("Could not read input for day {day}: {err}"))
+                .and_then(|input| validate(day, &input).map_err(|err| err.to_string()));
+
+            match diagnostics {
+                Ok(diagnostics) => ValidationReport {
+                    day,
+                    error: None,
+                    failed: diagnostics
+                        .iter()
+                        .any(|diagnostic| diagnostic.severity == Severity::Error),
+                    findings: diagnostics
+                        .into_iter()
+                        .map(|diagnostic| Finding {
+                            severity: diagnostic.severity.to_string(),
+                            line: diagnostic.line,
+                            column: diagnostic.column,
+                            message: diagnostic.message,
+                        })
+                        .collect(),
+                },
+                Err(err) => ValidationReport {
+                    day,
+                    error: Some(err),
+                    findings: Vec::new(),
+                    failed: true,
+                },
+            }
+        })
+        .collect()
+}
+
+fn print_validation_text(reports: &[ValidationReport]) {
+    for report in reports {
+        if let Some(error) = &report.error {
+            println!("Day {:>2}: {error}", report.day);
+        } else if report.findings.is_empty() {
+            println!("Day {:>2}: ok", report.day);
+        }
+
+        for finding in &report.findings {
+            let location = match finding.line {
+                0 => String::new(),
+                line => format!(" at {line}:{}", finding.column),
+            };
+
+            println!(
+                "Day {:>2}: {}{location}: {}",
+                report.day, finding.severity, finding.message
+            );
+        }
+    }
+}
+
+fn dump_parsed(args: &Args, input: &Input) -> Result<Vec<serde_json::Value>, String> {
+    args.selected_days()
+        .into_iter()
+        .map(|day| {
+            let input = input
+                .read(day)
+                .map_err(|err| format!("Could not read input for day {day}: {err}"))?;
+
+            parse(day, &input)
+                .map(|parsed| parsed.to_json())
+                .map_err(|err| err.to_string())
+        })
+        .collect()
+}
+
+#[derive(Serialize)]
+struct VisualizationReport {
+    day: u8,
+    part: u8,
+    directory: PathBuf,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    frames: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+fn visualize_all(
+    args: &Args,
+    input: &Input,
+    params: &Params,
+    dir: &Path,
+) -> Vec<VisualizationReport> {
+    args.selected()
+        .filter(|(day, _)| VISUALIZED.contains(day))
+        .map(|(day, part)| {
+            let directory = dir.join(format!("day{day:02}-part{part}"));
+
+            let frames = input
+                .read(day)
+                .map_err(|err| format!("Could not read input for day {day}: {err}"))
+                .and_then(|input| {
+                    let mut recorder = Recorder::new(&directory, args.frames.into())
+                        .map_err(|err| err.to_string())?
+                        .every(args.every as usize)
+                        .scale(args.scale as usize);
+
+                    advent_of_code::visualize(day, part, &input, params, &mut recorder)
+                        .map_err(|err| err.to_string())?;
+
+                    recorder.finish().map_err(|err| err.to_string())
+                });
+
+            let (frames, error) = match frames {
+                Ok(frames) => (Some(frames), None),
+                Err(err) => (None, Some(err)),
+            };
+
+            VisualizationReport {
+                day,
+                part,
+                directory,
+                frames,
+                error,
+            }
+        })
+        .collect()
+}
+
+fn print_visualization_text(reports: &[VisualizationReport]) {
+    if reports.is_empty() {
+        println!("Only days {VISUALIZED:?} can be visualized");
+    }
+
+    for report in reports {
+        let outcome = match (report.frames, &report.error) {
+            (Some(frames), _) => format!("{frames} frames in {}", report.directory.display()),
+            (None, Some(error)) => error.clone(),
+            (None, None) => unreachable!(),
+        };
+
+        println!("Day {:>2} part {}: {outcome}", report.day, report.part);
+    }
+}
+
+/// Timings of the solver alone, with the input parsed once up front and timed on its own.
+#[derive(Serialize, Deserialize)]
+struct BenchStats {
+    day: u8,
+    part: u8,
+    iterations: usize,
+    /// Time it took to normalize and parse the input, which is not part of the other times.
+    #[serde(default)]
+    parse_ns: u64,
+    min_ns: u64,
+    median_ns: u64,
+    p95_ns: u64,
+}
+
+#[derive(Serialize)]
+struct BenchReport {
+    #[serde(flatten)]
+    stats: BenchStats,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    baseline_median_ns: Option<u64>,
+    /// Relative change of the median compared to the baseline, in percent.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    change: Option<f64>,
+    slower: bool,
+}
+
+/// A day and part that was benchmarked, or the reason it couldn't be.
+#[derive(Serialize)]
+#[serde(untagged)]
+enum BenchEntry {
+    Measured(BenchReport),
+    Failed {
+        day: u8,
+        part: u8,
+        error: String,
+        panicked: bool,
+    },
+}
+
+fn bench_one(
+    day: u8,
+    part: u8,
+    input: &str,
+    params: &Params,
+    iterations: u32,
+) -> Result<BenchStats, String> {
+    let start = Instant::now();
+    let parsed = parse(day, input).map_err(|err| err.to_string())?;
+    let parse_ns = start.elapsed().as_nanos() as u64;
+
+    solve_parsed(&parsed, part, params).map_err(|err| err.to_string())?;
+
+    // A solver that fails in any of the timed runs is reported as failing, not with its times.
+    let mut times = (0..iterations)
+        .map(|_| {
+            let start = Instant::now();
+            let answer =
+                std::hint::black_box(solve_parsed(std::hint::black_box(&parsed), part, params));
+            let elapsed = start.elapsed().as_nanos() as u64;
+
+            answer.map(|_| elapsed).map_err(|err| err.to_string())
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    times.sort_unstable();
+
+    let percentile = |p: usize| times[(times.len() * p).div_ceil(100).max(1) - 1];
+
+    Ok(BenchStats {
+        day,
+        part,
+        iterations: times.len(),
+        parse_ns,
+        min_ns: times[0],
+        median_ns: times[times.len() / 2],
+        p95_ns: percentile(95),
+    })
+}
+
+fn bench(args: &Args, input: &Input, params: &Params) -> Result<Vec<BenchEntry>, String> {
+    let baseline = match &args.baseline {
+        Some(path) => {
+            let baseline = fs::read_to_string(path)
+                .map_err(|err| format!("Could not read {}: {err}", path.display()))?;
+
+            serde_json::from_str::<Vec<BenchStats>>(&baseline)
+                .map_err(|err| format!("Could not parse {}: {err}", path.display()))?
+        }
+        None => Vec::new(),
+    };
+
+    let mut reports = Vec::new();
+
+    for (day, part) in args.selected() {
+        let mut panicked = false;
+
+        let stats = input
+            .read(day)
+            .map_err(|err| format!("Could not read input for day {day}: {err}"))
+            .and_then(|input| {
+                panic::catch_unwind(|| bench_one(day, part, &input, params, args.iterations))
+                    .map_err(|payload| {
+                        panicked = true;
+                        panic_message(payload)
+                    })?
+            });
+
+        let stats = match stats {
+            Ok(stats) => stats,
+            Err(error) => {
+                reports.push(BenchEntry::Failed {
+                    day,
+                    part,
+                    error,
+                    panicked,
+                });
+                continue;
+            }
+        };
+
+        let baseline_median_ns = baseline
+            .iter()
+            .find(|base| (base.day, base.part) == (day, part))
+            .map(|base| base.median_ns);
+
+        let change = baseline_median_ns
+            .map(|base| (stats.median_ns as f64 / base.max(1) as f64 - 1.0) * 100.0);
+
+        reports.push(BenchEntry::Measured(BenchReport {
+            stats,
+            baseline_median_ns,
+            change,
+            slower: change.is_some_and(|change| change > args.threshold),
+        }));
+    }
+
+    if let Some(path) = &args.save_baseline {
+        let stats = reports
+            .iter()
+            .filter_map(|entry| match entry {
+                BenchEntry::Measured(report) => Some(&report.stats),
+                BenchEntry::Failed { .. } => None,
+            })
+            .collect::<Vec<_>>();
+
+        fs::write(path, serde_json::to_string_pretty(&stats).unwrap())
+            .map_err(|err| format!("Could not write {}: {err}", path.display()))?;
+    }
+
+    Ok(reports)
+}
+
+fn print_bench_text(reports: &[BenchEntry]) {
+    for entry in reports {
+        let report = match entry {
+            BenchEntry::Measured(report) => report,
+            BenchEntry::Failed {
+                day, part, error, ..
+            } => {
+                println!("Day {day:>2} part {part}: {error}");
+                continue;
+            }
+        };
+
+        let stats = &report.stats;
+        let time = |ns: u64| Duration::from_nanos(ns);
+
+        let comparison = match report.change {
+            Some(change) if report.slower => format!(", {change:+.1}% vs baseline [SLOWER]"),
+            Some(change) => format!(", {change:+.1}% vs baseline"),
+            None => String::new(),
+        };
+
+        println!(
+            "Day {:>2} part {}: min {:.2?}, median {:.2?}, p95 {:.2?} over {} runs, parsed in {:.2?}{}",
+            stats.day,
+            stats.part,
+            time(stats.min_ns),
+            time(stats.median_ns),
+            time(stats.p95_ns),
+            stats.iterations,
+            time(stats.parse_ns),
+            comparison,
+        );
+    }
+}
+
+fn print_params() {
+    let defaults = Params::default();
+
+    for name in params::NAMES {
+        println!("{name} = {}", defaults.get(name).unwrap());
+    }
+}
+
+fn main() -> ExitCode {
+    let args = Args::parse();
+
+    if args.list_params {
+        print_params();
+        return ExitCode::SUCCESS;
+    }
+
+    let params = match args.params() {
+        Ok(params) => params,
+        Err(err) => {
+            eprintln!("{err}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let input = match args.generate {
+        Some(seed) => Ok(Input::Generated {
+            seed,
+            size: args.size,
+        }),
+        None => Input::open(args.input.clone()),
+    };
+
+    let input = match input {
+        Ok(input) => input,
+        Err(err) => {
+            eprintln!("Could not read {}: {err}", args.input.display());
+            return ExitCode::FAILURE;
+        }
+    };
+
+    if args.validate {
+        let reports = validate_all(&args, &input);
+
+        match args.format {
+            Format::Text => print_validation_text(&reports),
+            Format::Json => println!("{}", serde_json::to_string_pretty(&reports).unwrap()),
+        }
+
+        return if reports.iter().any(|report| report.failed) {
+            ExitCode::FAILURE
+        } else {
+            ExitCode::SUCCESS
+        };
+    }
+
+    if args.dump_parsed {
+        return match dump_parsed(&args, &input) {
+            Ok(models) => {
+                println!("{}", serde_json::to_string_pretty(&models).unwrap());
+                ExitCode::SUCCESS
+            }
+            Err(err) => {
+                eprintln!("{err}");
+                ExitCode::FAILURE
+            }
+        };
+    }
+
+    if let Some(dir) = &args.visualize {
+        let reports = visualize_all(&args, &input, &params, dir);
+
+        match args.format {
+            Format::Text => print_visualization_text(&reports),
+            Format::Json => println!("{}", serde_json::to_string_pretty(&reports).unwrap()),
+        }
+
+        return if reports.is_empty() || reports.iter().any(|report| report.error.is_some()) {
+            ExitCode::FAILURE
+        } else {
+            ExitCode::SUCCESS
+        };
+    }
+
+    if args.bench {
+        let reports = match bench(&args, &input, &params) {
+            Ok(reports) => reports,
+            Err(err) => {
+                eprintln!("{err}");
+                return ExitCode::FAILURE;
+            }
+        };
+
+        match args.format {
+            Format::Text => print_bench_text(&reports),
+            Format::Json => println!("{}", serde_json::to_string_pretty(&reports).unwrap()),
+        }
+
+        let failed = |entry: &BenchEntry| match entry {
+            BenchEntry::Measured(report) => report.slower,
+            BenchEntry::Failed { .. } => true,
+        };
+
+        return if reports.iter().any(failed) {
+            ExitCode::FAILURE
+        } else {
+            ExitCode::SUCCESS
+        };
+    }
+
+    let answers = if args.verify {
+        let Some(path) = args
+            .answers
+            .clone()
+            .or_else(|| default_answers(&args.input))
+        else {
+            eprintln!("Verifying input from stdin needs the expected answers given with --answers");
+            return ExitCode::FAILURE;
+        };
+
+        match read_answers(&path) {
+            Ok(answers) => Some(answers),
+            Err(err) => {
+                eprintln!(
+                    "Could not read expected answers from {}: {err}",
+                    path.display()
+                );
+                return ExitCode::FAILURE;
+            }
+        }
+    } else {
+        None
+    };
+
+    let mut reports = run(&args, &input, &params);
+
+    if let Some(answers) = &answers {
+        for report in reports.iter_mut() {
+            report.verify(answers);
+        }
+    }
+
+    match args.format {
+        Format::Text => print_text(&reports),
+        Format::Json => println!("{}", serde_json::to_string_pretty(&reports).unwrap()),
+    }
+
+    let failed =
+        |report: &Report| report.error.is_some() || report.verdict == Some(Verdict::Mismatch);
+
+    if reports.iter().any(failed) {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    }
+}
@@ -0,0 +1,154 @@
+//! Tunables that the puzzles hard-code, such as round counts and time limits.
+//!
+//! Every day with tunables has its own `Params` struct whose [`Default`] matches the real puzzle.
+//! Example inputs and variants of a puzzle can be solved by changing them and passing them to
+//! [`solve_with`](crate::solve_with).
+
+use std::{ops::RangeBounds, str::FromStr};
+
+use crate::Error;
+
+pub use crate::{
+    day11::Params as Day11, day15::Params as Day15, day16::Params as Day16, day17::Params as Day17,
+    day19::Params as Day19, day20::Params as Day20, day7::Params as Day7,
+};
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Params {
+    pub day7: Day7,
+    pub day11: Day11,
+    pub day15: Day15,
+    pub day16: Day16,
+    pub day17: Day17,
+    pub day19: Day19,
+    pub day20: Day20,
+}
+
+/// Parses `value`, which has to lie within the values the puzzle can handle.
+fn parse<T: FromStr + PartialOrd>(
+    name: &str,
+    value: &str,
+    range: impl RangeBounds<T>,
+) -> Result<T, Error> {
+    value
+        .parse()
+        .ok()
+        .filter(|value| range.contains(value))
+        .ok_or_else(|| Error::InvalidParam {
+            name: name.to_string(),
+            value: value.to_string(),
+        })
+}
+
+/// The values a parameter may take, all of them unless a range is given.
+macro_rules! range {
+    () => {
+        ..
+    };
+    ($range:expr) => {
+        $range
+    };
+}
+
+macro_rules! params {
+    ($($day:ident . $field:ident $(in $range:expr)?),* $(,)?) => {
+        /// Every name accepted by [`Params::set`] and [`Params::get`].
+        pub const NAMES: &[&str] = &[$(concat!(stringify!($day), ".", stringify!($field))),*];
+
+        impl Params {
+            /// Changes the parameter called `name`, e.g. `"day15.target_y"`, to the parsed `value`.
+            pub fn set(&mut self, name: &str, value: &str) -> Result<(), Error> {
+                match name {
+                    $(
+                        _ if name == concat!(stringify!($day), ".", stringify!($field)) => {
+                            self.$day.$field = parse(name, value, range!($($range)?))?;
+                        }
+                    )*
+                    _ => {
+                        return Err(Error::UnknownParam {
+                            name: name.to_string(),
+                        })
+                    }
+                }
+
+                Ok(())
+            }
+
+            /// The current value of the parameter called `name`.
+            pub fn get(&self, name: &str) -> Option<String> {
+                match name {
+                    $(
+                        _ if name == concat!(stringify!($day), ".", stringify!($field)) => {
+                            Some(self.$day.$field.to_string())
+                        }
+                    )*
+                    _ => None,
+                }
+            }
+        }
+    };
+}
+
+params! {
+    day7.disk_size,
+    day7.required_space,
+    // Keeps the inspections that are extrapolated over the skipped rounds well within 64 bits.
+    day11.part1_rounds in ..=u32::MAX as usize,
+    day11.part2_rounds in ..=u32::MAX as usize,
+    day15.target_y,
+    day15.max_pos in 0..,
+    day16.part1_minutes,
+    day16.part2_minutes,
+    // Every rock adds at most four rows to the tower, whose height has to fit.
+    day17.part1_shapes in ..=usize::MAX / 4,
+    day17.part2_shapes in ..=usize::MAX / 4,
+    day19.part1_minutes,
+    day19.part2_minutes,
+    day19.part2_blueprints,
+    // Applied to numbers of up to 15 bits, three of which are added up.
+    day20.decryption_key in ..=i64::MAX as usize / (3 << 15),
+    day20.rounds,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Params, NAMES};
+    use crate::Error;
+
+    #[test]
+    fn set() {
+        let mut params = Params::default();
+
+        params.set("day15.target_y", "10").unwrap();
+
+        assert_eq!(params.day15.target_y, 10);
+        assert_eq!(params.get("day15.max_pos").as_deref(), Some("4000000"));
+        assert!(matches!(
+            params.set("day15.target_y", "ten"),
+            Err(Error::InvalidParam { .. })
+        ));
+        assert!(matches!(
+            params.set("day15.max_pos", "-1"),
+            Err(Error::InvalidParam { .. })
+        ));
+        assert!(matches!(
+            params.set("day11.part2_rounds", "5000000000"),
+            Err(Error::InvalidParam { .. })
+        ));
+        assert!(matches!(
+            params.set("day15.nope", "1"),
+            Err(Error::UnknownParam { .. })
+        ));
+    }
+
+    #[test]
+    fn names() {
+        for name in NAMES {
+            let mut params = Params::default();
+
+            params.set(name, "1").unwrap();
+
+            assert_eq!(params.get(name).as_deref(), Some("1"));
+        }
+    }
+}
@@ -0,0 +1,663 @@
+use std::{any::Any, fmt::Debug};
+
+use serde::Serialize;
+use serde_json::{json, Value};
+
+use crate::{
+    util::input::normalize, visualize::Recorder, Answer, Budget, Diagnostic, Error, Outcome,
+    Params, Stats,
+};
+
+use crate::{
+    day1, day10, day11, day12, day13, day14, day15, day16, day17, day18, day19, day2, day20, day21,
+    day22, day23, day24, day25, day3, day4, day5, day6, day7, day8, day9,
+};
+
+/// Every `(day, part)` combination that [`solve`] knows about, in order.
+pub const PUZZLES: [(u8, u8); 49] = [
+    (1, 1),
+    (1, 2),
+    (2, 1),
+    (2, 2),
+    (3, 1),
+    (3, 2),
+    (4, 1),
+    (4, 2),
+    (5, 1),
+    (5, 2),
+    (6, 1),
+    (6, 2),
+    (7, 1),
+    (7, 2),
+    (8, 1),
+    (8, 2),
+    (9, 1),
+    (9, 2),
+    (10, 1),
+    (10, 2),
+    (11, 1),
+    (11, 2),
+    (12, 1),
+    (12, 2),
+    (13, 1),
+    (13, 2),
+    (14, 1),
+    (14, 2),
+    (15, 1),
+    (15, 2),
+    (16, 1),
+    (16, 2),
+    (17, 1),
+    (17, 2),
+    (18, 1),
+    (18, 2),
+    (19, 1),
+    (19, 2),
+    (20, 1),
+    (20, 2),
+    (21, 1),
+    (21, 2),
+    (22, 1),
+    (22, 2),
+    (23, 1),
+    (23, 2),
+    (24, 1),
+    (24, 2),
+    (25, 1),
+];
+
+pub fn solve(day: u8, part: u8, input: &str) -> Result<Answer, Error> {
+    solve_with(day, part, input, &Params::default())
+}
+
+/// Like [`solve`], but with the puzzle's tunables taken from `params`.
+pub fn solve_with(day: u8, part: u8, input: &str, params: &Params) -> Result<Answer, Error> {
+    solve_with_stats(day, part, input, params).map(|(answer, _)| answer)
+}
+
+/// Like [`solve_with`], along with the work the search did for the puzzles that are solved by one.
+/// The stats are `None` for every other puzzle.
+pub fn solve_with_stats(
+    day: u8,
+    part: u8,
+    input: &str,
+    params: &Params,
+) -> Result<(Answer, Option<Stats>), Error> {
+    solve_within(day, part, input, params, &Budget::new())
+        .map(|(outcome, stats)| (outcome.answer, stats))
+}
+
+/// Like [`solve_with_stats`], but the searches of days 16 and 19 give up with their best answer so
+/// far once `budget` runs out. Every other puzzle ignores the budget and always gives a final
+/// answer.
+pub fn solve_within(
+    day: u8,
+    part: u8,
+    input: &str,
+    params: &Params,
+    budget: &Budget,
+) -> Result<(Outcome<Answer>, Option<Stats>), Error> {
+    if !PUZZLES.contains(&(day, part)) {
+        return Err(Error::UnknownPuzzle { day, part });
+    }
+
+    solve_parsed_within(&parse(day, input)?, part, params, budget)
+}
+
+/// The input of a day, parsed into the model that both of its parts are solved from.
+pub struct Parsed {
+    day: u8,
+    model: Box<dyn Any + Send + Sync>,
+    to_json: fn(&(dyn Any + Send + Sync)) -> Value,
+}
+
+impl Parsed {
+    pub fn day(&self) -> u8 {
+        self.day
+    }
+
+    /// The model of the day, whose type is picked by the solver it is handed to.
+    fn model<T: Any>(&self) -> &T {
+        self.model
+            .downcast_ref()
+            .expect("Every day is solved from the model of its own generator")
+    }
+
+    /// The model as `{"day": N, "model": ...}`, with a schema per day that stays the same across
+    /// versions. Positions are `[x, y]` with `(0, 0)` in the top left corner, and sets are sorted.
+    ///
+    /// | Day | Model |
+    /// |-----|-------|
+    /// | 1   | Total calories of every elf: `[6000, 4000]` |
+    /// | 2   | Opponent's shape and response: `[["rock", "Y"]]`, shapes `rock`, `paper` or `scissors` |
+    /// | 3   | Distinct items of both compartments, sorted: `[["JWgprtvw", "FMcfhps"]]` |
+    /// | 4   | Both ranges of every pair: `[[{"start": 2, "end": 4}, {"start": 6, "end": 8}]]` |
+    /// | 5   | Crates bottom to top and 1-based moves: `{"stacks": [["Z", "N"]], "moves": [{"count": 1, "from": 2, "to": 1}]}` |
+    /// | 6   | The datastream: `"mjqjpqmgbljsphdztnvjfqwrcgsmlb"` |
+    /// | 7   | The root directory: `{"name": "/", "children": [...], "files": [{"name": "b.txt", "size": 14848514}], "files_size": 48381165}`, `files_size` including subdirectories |
+    /// | 8   | Tree heights row by row: `[[3, 0, 3, 7, 3]]` |
+    /// | 9   | Motions: `[{"right": 4}]`, directions `up`, `down`, `left` or `right` |
+    /// | 10  | Instructions: `[{"addx": 15}, "noop"]` |
+    /// | 11  | Monkeys: `[{"items": [79], "operation": {"multiply": 19}, "test": {"divisible_by": 23}, "if_true": 2, "if_false": 3}]`, operations `{"add": n}`, `{"multiply": n}` or `"square"` |
+    /// | 12  | Heights as rows of `a`-`z`: `{"heights": ["aabqponm"], "start": [0, 0], "end": [5, 2]}` |
+    /// | 13  | Packet pairs: `[{"left": [[1], [2, 3]], "right": [[1], 4]}]` |
+    /// | 14  | Rock row by row: `{"rock": [[498, 4], [502, 4]]}` |
+    /// | 15  | Sensors: `[{"position": [2, 18], "closest_beacon": [-2, 15]}]` |
+    /// | 16  | Valves by name: `[{"name": "AA", "flow_rate": 0, "tunnels": ["DD", "II"]}]` |
+    /// | 17  | The jets: `">>><<><>"` |
+    /// | 18  | Cubes ordered by `z`, `y` then `x`: `[[2, 2, 1], [2, 1, 2]]` |
+    /// | 19  | Blueprints: `[{"id": 1, "ore_robot_cost": {"ore": 4, "clay": 0, "obsidian": 0}, ...}]`, with `clay_robot_cost`, `obsidian_robot_cost` and `geode_robot_cost` alike |
+    /// | 20  | The numbers of the file: `[1, 2, -3]` |
+    /// | 21  | Monkeys: `[{"name": "root", "job": {"add": ["pppw", "sjmn"]}}]`, jobs `{"number": n}` or `add`, `sub`, `mul`, `div` |
+    /// | 22  | Map rows padded with spaces and the path: `{"map": ["  .#", "#..."], "path": [10, "R", 5, "L"]}` |
+    /// | 23  | Elves row by row: `[[4, 0], [2, 1]]` |
+    /// | 24  | Basin inside the walls: `{"width": 6, "height": 4, "blizzards": [{"position": [0, 0], "direction": "east"}]}`, directions `north`, `south`, `west` or `east` |
+    /// | 25  | Fuel requirements in decimal: `[1747, 906]` |
+    ///
+    /// Object keys are written in alphabetical order.
+    pub fn to_json(&self) -> Value {
+        json!({ "day": self.day, "model": (self.to_json)(self.model.as_ref()) })
+    }
+}
+
+impl Debug for Parsed {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Parsed")
+            .field("day", &self.day)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Parses `input` for `day` once, to solve any number of its parts with [`solve_parsed`].
+///
+/// The input is [normalized](normalize) first, so inputs saved on Windows or by other editors are
+/// read the same as clean ones.
+pub fn parse(day: u8, input: &str) -> Result<Parsed, Error> {
+    let input = normalize(input);
+    let input = input.as_ref();
+
+    type Model = (
+        Box<dyn Any + Send + Sync>,
+        fn(&(dyn Any + Send + Sync)) -> Value,
+    );
+
+    fn model<T: Any + Send + Sync + Serialize>(model: Result<T, Error>) -> Result<Model, Error> {
+        fn to_json<T: Any + Serialize>(model: &(dyn Any + Send + Sync)) -> Value {
+            serde_json::to_value(model.downcast_ref::<T>().expect("Parsed by the same day"))
+                .expect("Every model serializes to JSON")
+        }
+
+        model.map(|model| (Box::new(model) as _, to_json::<T> as _))
+    }
+
+    let model = match day {
+        1 => model(day1::parse(input)),
+        2 => model(day2::parse(input)),
+        3 => model(day3::parse(input)),
+        4 => model(day4::parse(input)),
+        5 => model(day5::parse(input)),
+        6 => model(Ok(day6::parse(input))),
+        7 => model(day7::parse(input)),
+        8 => model(day8::parse(input)),
+        9 => model(day9::parse(input)),
+        10 => model(day10::parse(input)),
+        11 => model(day11::parse(input)),
+        12 => model(day12::parse(input)),
+        13 => model(day13::parse(input)),
+        14 => model(day14::parse(input)),
+        15 => model(day15::parse(input)),
+        16 => model(day16::parse(input)),
+        17 => model(day17::parse(input)),
+        18 => model(day18::parse(input)),
+        19 => model(day19::parse(input)),
+        20 => model(day20::parse(input)),
+        21 => model(day21::parse(input)),
+        22 => model(day22::parse(input)),
+        23 => model(day23::parse(input)),
+        24 => model(day24::parse(input)),
+        25 => model(day25::parse(input)),
+        _ => return Err(Error::UnknownDay { day }),
+    };
+
+    model
+        .map(|(model, to_json)| Parsed {
+            day,
+            model,
+            to_json,
+        })
+        .map_err(|err| err.locate(day, input))
+}
+
+/// Like [`solve_with`], for an input that was already parsed.
+pub fn solve_parsed(parsed: &Parsed, part: u8, params: &Params) -> Result<Answer, Error> {
+    solve_parsed_within(parsed, part, params, &Budget::new()).map(|(outcome, _)| outcome.answer)
+}
+
+/// Like [`solve_within`], for an input that was already parsed.
+pub fn solve_parsed_within(
+    parsed: &Parsed,
+    part: u8,
+    params: &Params,
+    budget: &Budget,
+) -> Result<(Outcome<Answer>, Option<Stats>), Error> {
+    fn final_answer<T: Into<Answer>>(answer: T) -> (Outcome<Answer>, Option<Stats>) {
+        (
+            Outcome {
+                answer: answer.into(),
+                is_final: true,
+            },
+            None,
+        )
+    }
+
+    fn with_stats<T: Into<Answer>>(
+        (outcome, stats): (Outcome<T>, Stats),
+    ) -> (Outcome<Answer>, Option<Stats>) {
+        (outcome.map(Into::into), Some(stats))
+    }
+
+    fn final_with_stats<T: Into<Answer>>(
+        (answer, stats): (T, Stats),
+    ) -> (Outcome<Answer>, Option<Stats>) {
+        (final_answer(answer).0, Some(stats))
+    }
+
+    Ok(match (parsed.day, part) {
+        (1, 1) => final_answer(day1::part1(parsed.model())?),
+        (1, 2) => final_answer(day1::part2(parsed.model())?),
+        (2, 1) => final_answer(day2::part1(parsed.model())),
+        (2, 2) => final_answer(day2::part2(parsed.model())),
+        (3, 1) => final_answer(day3::part1(parsed.model())?),
+        (3, 2) => final_answer(day3::part2(parsed.model())?),
+        (4, 1) => final_answer(day4::part1(parsed.model())),
+        (4, 2) => final_answer(day4::part2(parsed.model())),
+        (5, 1) => final_answer(day5::part1(parsed.model())),
+        (5, 2) => final_answer(day5::part2(parsed.model())),
+        (6, 1) => final_answer(day6::part1(parsed.model())?),
+        (6, 2) => final_answer(day6::part2(parsed.model())?),
+        (7, 1) => final_answer(day7::part1(parsed.model())),
+        (7, 2) => final_answer(day7::part2_with(parsed.model(), &params.day7)?),
+        (8, 1) => final_answer(day8::part1(parsed.model())),
+        (8, 2) => final_answer(day8::part2(parsed.model())),
+        (9, 1) => final_answer(day9::part1(parsed.model())),
+        (9, 2) => final_answer(day9::part2(parsed.model())),
+        (10, 1) => final_answer(day10::part1(parsed.model())),
+        (10, 2) => final_answer(day10::part2(parsed.model())),
+        (11, 1) => final_answer(day11::part1_with(parsed.model(), &params.day11)?),
+        (11, 2) => final_answer(day11::part2_with(parsed.model(), &params.day11)?),
+        (12, 1) => final_with_stats(day12::part1_with_stats(parsed.model())?),
+        (12, 2) => final_with_stats(day12::part2_with_stats(parsed.model())?),
+        (13, 1) => final_answer(day13::part1(parsed.model())),
+        (13, 2) => final_answer(day13::part2(parsed.model())),
+        (14, 1) => final_answer(day14::part1(parsed.model())),
+        (14, 2) => final_answer(day14::part2(parsed.model())),
+        (15, 1) => final_answer(day15::part1_with(parsed.model(), &params.day15)),
+        (15, 2) => final_answer(day15::part2_with(parsed.model(), &params.day15)?),
+        (16, 1) => with_stats(day16::part1_within(parsed.model(), &params.day16, budget)),
+        (16, 2) => with_stats(day16::part2_within(parsed.model(), &params.day16, budget)),
+        (17, 1) => final_answer(day17::part1_with(parsed.model(), &params.day17)),
+        (17, 2) => final_answer(day17::part2_with(parsed.model(), &params.day17)),
+        (18, 1) => final_answer(day18::part1(parsed.model())),
+        (18, 2) => final_answer(day18::part2(parsed.model())),
+        (19, 1) => with_stats(day19::part1_within(parsed.model(), &params.day19, budget)),
+        (19, 2) => with_stats(day19::part2_within(parsed.model(), &params.day19, budget)),
+        (20, 1) => final_answer(day20::part1(parsed.model())?),
+        (20, 2) => final_answer(day20::part2_with(parsed.model(), &params.day20)?),
+        (21, 1) => final_answer(day21::part1(parsed.model())?),
+        (21, 2) => final_answer(day21::part2(parsed.model())?),
+        (22, 1) => final_answer(day22::part1(parsed.model())),
+        (22, 2) => final_answer(day22::part2(parsed.model())?),
+        (23, 1) => final_answer(day23::part1(parsed.model())),
+        (23, 2) => final_answer(day23::part2(parsed.model())?),
+        (24, 1) => final_with_stats(day24::part1_with_stats(parsed.model())?),
+        (24, 2) => final_with_stats(day24::part2_with_stats(parsed.model())?),
+        (25, 1) => final_answer(day25::part1(parsed.model())),
+        (day, part) => return Err(Error::UnknownPuzzle { day, part }),
+    })
+}
+
+/// Checks `input` for the assumptions the solvers of `day` make about it, without solving anything.
+///
+/// An input without any [`Severity::Error`](crate::Severity::Error) diagnostics can be solved, as
+/// far as these checks can tell.
+pub fn validate(day: u8, input: &str) -> Result<Vec<Diagnostic>, Error> {
+    let input = normalize(input);
+    let input = input.as_ref();
+
+    Ok(match day {
+        1 => day1::validate(input),
+        2 => day2::validate(input),
+        3 => day3::validate(input),
+        4 => day4::validate(input),
+        5 => day5::validate(input),
+        6 => day6::validate(input),
+        7 => day7::validate(input),
+        8 => day8::validate(input),
+        9 => day9::validate(input),
+        10 => day10::validate(input),
+        11 => day11::validate(input),
+        12 => day12::validate(input),
+        13 => day13::validate(input),
+        14 => day14::validate(input),
+        15 => day15::validate(input),
+        16 => day16::validate(input),
+        17 => day17::validate(input),
+        18 => day18::validate(input),
+        19 => day19::validate(input),
+        20 => day20::validate(input),
+        21 => day21::validate(input),
+        22 => day22::validate(input),
+        23 => day23::validate(input),
+        24 => day24::validate(input),
+        25 => day25::validate(input),
+        _ => return Err(Error::UnknownDay { day }),
+    })
+}
+
+/// Days that [`visualize`] can record.
+pub const VISUALIZED: [u8; 5] = [9, 14, 17, 23, 24];
+
+/// Records the simulation of a puzzle step by step, see [`crate::visualize`].
+pub fn visualize(
+    day: u8,
+    part: u8,
+    input: &str,
+    params: &Params,
+    recorder: &mut Recorder,
+) -> Result<(), Error> {
+    if !PUZZLES.contains(&(day, part)) {
+        return Err(Error::UnknownPuzzle { day, part });
+    }
+
+    if !VISUALIZED.contains(&day) {
+        return Err(Error::UnknownPuzzle { day, part });
+    }
+
+    let parsed = parse(day, input)?;
+    match day {
+        9 => day9::visualize(parsed.model(), part, recorder),
+        14 => day14::visualize(parsed.model(), part, recorder),
+        17 => day17::visualize(parsed.model(), part, &params.day17, recorder),
+        23 => day23::visualize(parsed.model(), part, recorder),
+        24 => day24::visualize(parsed.model(), part, recorder)?,
+        _ => unreachable!("Every visualized day has a simulation to record"),
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use crate::{
+        visualize::{Format, Recorder},
+        Answer, Budget, Cancel, Error, Params,
+    };
+
+    #[test]
+    fn test_case_1() {
+        let result = super::solve(
+            1,
+            1,
+            "1000\n2000\n3000\n\n4000\n\n5000\n6000\n\n7000\n8000\n9000\n\n10000",
+        );
+        assert_eq!(result, Ok(Answer::U64(24000)));
+    }
+
+    #[test]
+    fn test_case_2() {
+        let result = super::solve(25, 2, "");
+        assert_eq!(result, Err(Error::UnknownPuzzle { day: 25, part: 2 }));
+    }
+
+    #[test]
+    fn test_case_3() {
+        let Err(Error::Parse(err)) = super::solve(9, 1, "R 4\nU x\n") else {
+            panic!("Expected a parse error");
+        };
+
+        assert_eq!((err.day, err.line, err.column), (9, 2, 3));
+        assert_eq!(err.found, "x");
+    }
+
+    #[test]
+    fn test_case_4() {
+        let Err(Error::Parse(err)) =
+            super::solve(21, 1, "root: pppw + sjmn\ndbpl: 5\ncczh: sllz % lgvd")
+        else {
+            panic!("Expected a parse error");
+        };
+
+        assert_eq!((err.day, err.line, err.column), (21, 3, 12));
+        assert_eq!(err.found, "%");
+        assert_eq!(err.expected, "an operator (+, -, * or /)");
+    }
+
+    #[test]
+    fn test_case_5() {
+        let mut params = Params::default();
+        params.set("day11.part2_rounds", "20").unwrap();
+
+        let input = "Monkey 0:\n  Starting items: 79, 98\n  Operation: new = old * 19\n  Test: divisible by 23\n    If true: throw to monkey 2\n    If false: throw to monkey 3\n\nMonkey 1:\n  Starting items: 54, 65, 75, 74\n  Operation: new = old + 6\n  Test: divisible by 19\n    If true: throw to monkey 2\n    If false: throw to monkey 0\n\nMonkey 2:\n  Starting items: 79, 60, 97\n  Operation: new = old * old\n  Test: divisible by 13\n    If true: throw to monkey 1\n    If false: throw to monkey 3\n\nMonkey 3:\n  Starting items: 74\n  Operation: new = old + 3\n  Test: divisible by 17\n    If true: throw to monkey 0\n    If false: throw to monkey 1";
+
+        assert_eq!(
+            super::solve_with(11, 2, input, &params),
+            Ok(Answer::U64(103 * 99))
+        );
+    }
+
+    #[test]
+    fn test_case_6() {
+        assert_eq!(super::validate(26, ""), Err(Error::UnknownDay { day: 26 }));
+
+        let diagnostics = super::validate(25, "1=\n2=-01\n13").unwrap();
+
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(
+            diagnostics[0].to_string(),
+            "error at 3:2: Expected a SNAFU digit (=, -, 0, 1 or 2), found \"3\""
+        );
+    }
+
+    #[test]
+    fn test_case_7() {
+        let dir = std::env::temp_dir().join(format!("advent-of-code-day24-{}", std::process::id()));
+        let input = "#.######\n#>>.<^<#\n#.<..<<#\n#>v.><>#\n#<^v^^>#\n######.#";
+        let params = Params::default();
+
+        let mut recorder = Recorder::new(&dir, Format::Ascii).unwrap();
+        super::visualize(24, 1, input, &params, &mut recorder).unwrap();
+
+        assert_eq!(recorder.finish().unwrap(), 19);
+        assert_eq!(
+            fs::read_to_string(dir.join("00000.txt")).unwrap(),
+            "#E######\n#>>.<^<#\n#.<..<<#\n#>v.><>#\n#<^v^^>#\n######.#\n"
+        );
+        assert_eq!(
+            fs::read_to_string(dir.join("00018.txt")).unwrap(),
+            "#.######\n#>2.<.<#\n#.2v^2<#\n#>..>2>#\n#<....>#\n######E#\n"
+        );
+
+        let mut recorder = Recorder::new(&dir, Format::Ascii).unwrap();
+
+        assert_eq!(
+            super::visualize(1, 1, "1", &params, &mut recorder),
+            Err(Error::UnknownPuzzle { day: 1, part: 1 })
+        );
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_case_8() {
+        let input = "#.######\n#>>.<^<#\n#.<..<<#\n#>v.><>#\n#<^v^^>#\n######.#";
+        let params = Params::default();
+
+        let (answer, one_trip) = super::solve_with_stats(24, 1, input, &params).unwrap();
+        let one_trip = one_trip.unwrap();
+
+        assert_eq!(answer, Answer::Usize(18));
+        assert!(one_trip.expanded > 18);
+        assert!(one_trip.visited > one_trip.expanded);
+
+        let (answer, three_trips) = super::solve_with_stats(24, 2, input, &params).unwrap();
+
+        assert_eq!(answer, Answer::Usize(54));
+        assert!(three_trips.unwrap().expanded > one_trip.expanded);
+
+        assert_eq!(
+            super::solve_with_stats(1, 1, "1", &params),
+            Ok((Answer::U64(1), None))
+        );
+    }
+
+    #[test]
+    fn test_case_9() {
+        let input = "Blueprint 1: Each ore robot costs 4 ore. Each clay robot costs 2 ore. Each obsidian robot costs 3 ore and 14 clay. Each geode robot costs 2 ore and 7 obsidian.\nBlueprint 2: Each ore robot costs 2 ore. Each clay robot costs 3 ore. Each obsidian robot costs 3 ore and 8 clay. Each geode robot costs 3 ore and 12 obsidian.";
+        let params = Params::default();
+        let cancel = Cancel::new();
+
+        cancel.cancel();
+
+        let budget = Budget::new().cancel_on(&cancel);
+        let (outcome, stats) = super::solve_within(19, 1, input, &params, &budget).unwrap();
+
+        assert!(!outcome.is_final);
+        assert!(matches!(outcome.answer, Answer::Usize(quality) if quality <= 33));
+        assert_eq!(stats.unwrap().expanded, 2 * 1023);
+
+        let (outcome, _) = super::solve_within(1, 1, "1", &params, &budget).unwrap();
+
+        assert!(outcome.is_final);
+    }
+
+    #[test]
+    fn test_case_10() {
+        let parsed = super::parse(18, "1,1,1\n2,1,1").unwrap();
+        let params = Params::default();
+
+        assert_eq!(parsed.day(), 18);
+        assert_eq!(
+            super::solve_parsed(&parsed, 1, &params),
+            Ok(Answer::Usize(10))
+        );
+        assert_eq!(
+            super::solve_parsed(&parsed, 2, &params),
+            Ok(Answer::Usize(10))
+        );
+        assert_eq!(
+            super::solve_parsed(&parsed, 3, &params),
+            Err(Error::UnknownPuzzle { day: 18, part: 3 })
+        );
+
+        let Err(Error::Parse(err)) = super::parse(18, "1,1,1\n2,x,1") else {
+            panic!("Expected a parse error");
+        };
+
+        assert_eq!((err.day, err.line, err.column), (18, 2, 3));
+        assert_eq!(
+            super::parse(26, "").unwrap_err(),
+            Error::UnknownDay { day: 26 }
+        );
+    }
+
+    #[test]
+    fn test_case_11() {
+        let parsed = super::parse(21, "root: dbpl + humn\ndbpl: 5\nhumn: 3").unwrap();
+
+        assert_eq!(
+            parsed.to_json(),
+            serde_json::json!({
+                "day": 21,
+                "model": [
+                    { "name": "root", "job": { "add": ["dbpl", "humn"] } },
+                    { "name": "dbpl", "job": { "number": 5 } },
+                    { "name": "humn", "job": { "number": 3 } },
+                ],
+            })
+        );
+
+        let parsed = super::parse(5, "    [D]\n[N] [C]\n 1   2 \n\nmove 1 from 2 to 1").unwrap();
+
+        assert_eq!(
+            parsed.to_json()["model"],
+            serde_json::json!({
+                "stacks": [["N"], ["C", "D"]],
+                "moves": [{ "count": 1, "from": 2, "to": 1 }],
+            })
+        );
+    }
+
+    #[test]
+    fn test_case_12() {
+        let params = Params::default();
+
+        // Part 2 of day 22 only knows how the real input folds into a cube
+        for (day, parts, clean) in [
+            (
+                5,
+                2,
+                include_str!("../input/2022/examples/day5-example.txt"),
+            ),
+            (
+                11,
+                2,
+                include_str!("../input/2022/examples/day11-example.txt"),
+            ),
+            (
+                12,
+                2,
+                include_str!("../input/2022/examples/day12-example.txt"),
+            ),
+            (
+                17,
+                2,
+                include_str!("../input/2022/examples/day17-example.txt"),
+            ),
+            (
+                22,
+                1,
+                include_str!("../input/2022/examples/day22-example.txt"),
+            ),
+        ] {
+            let edited = format!(
+                "\u{feff}{}\r\n\r\n",
+                clean
+                    .lines()
+                    .map(str::trim_end)
+                    .collect::<Vec<_>>()
+                    .join("\r\n")
+            );
+
+            for part in 1..=parts {
+                assert_eq!(
+                    super::solve_with(day, part, &edited, &params),
+                    super::solve_with(day, part, clean, &params),
+                    "day {day} part {part}"
+                );
+            }
+
+            assert_eq!(super::validate(day, &edited), super::validate(day, clean));
+        }
+    }
+
+    #[test]
+    fn test_case_13() {
+        // Each part needs less of the input than the other one
+        assert_eq!(super::solve(1, 1, "100\n\n200"), Ok(Answer::U64(200)));
+        assert!(matches!(
+            super::solve(1, 2, "100\n\n200"),
+            Err(Error::NoSolution { .. })
+        ));
+        assert_eq!(super::solve(6, 1, "aaabcdaaa"), Ok(Answer::Usize(6)));
+        assert!(matches!(
+            super::solve(6, 2, "aaabcdaaa"),
+            Err(Error::NoSolution { .. })
+        ));
+        assert!(matches!(
+            super::solve(20, 1, "1\n2"),
+            Err(Error::NoSolution { .. })
+        ));
+    }
+}
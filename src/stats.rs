@@ -0,0 +1,91 @@
+use std::{collections::BTreeMap, fmt::Display};
+
+/// How much work a search did to find an answer, see [`crate::solve_with_stats`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Stats {
+    /// States whose successors were generated.
+    pub expanded: usize,
+    /// States that were dropped before being expanded, by the name of the bound that ruled them
+    /// out.
+    pub pruned: BTreeMap<&'static str, usize>,
+    /// Most states that were waiting to be expanded at the same time.
+    pub peak_frontier: usize,
+    /// Distinct states that were remembered as seen, or 0 for searches that don't remember them.
+    pub visited: usize,
+}
+
+impl Stats {
+    /// Adds `count` states that were pruned by `bound`, leaving out bounds that pruned nothing.
+    ///
+    /// Searches count in plain variables while running and fold the counts in once at the end, as
+    /// going through the map for every state slows them down considerably.
+    pub(crate) fn prune(&mut self, bound: &'static str, count: usize) {
+        if count > 0 {
+            *self.pruned.entry(bound).or_default() += count;
+        }
+    }
+
+    pub(crate) fn frontier(&mut self, len: usize) {
+        self.peak_frontier = self.peak_frontier.max(len);
+    }
+
+    /// Adds up the work of two separate searches, keeping the larger of their peak frontiers.
+    pub(crate) fn merge(mut self, other: Stats) -> Stats {
+        self.expanded += other.expanded;
+        self.peak_frontier = self.peak_frontier.max(other.peak_frontier);
+        self.visited += other.visited;
+
+        for (bound, count) in other.pruned {
+            *self.pruned.entry(bound).or_default() += count;
+        }
+
+        self
+    }
+}
+
+impl Display for Stats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "expanded {}", self.expanded)?;
+
+        for (bound, count) in &self.pruned {
+            write!(f, ", pruned {count} by {bound}")?;
+        }
+
+        write!(
+            f,
+            ", peak frontier {}, visited {}",
+            self.peak_frontier, self.visited
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Stats;
+
+    #[test]
+    fn merge() {
+        let mut a = Stats {
+            expanded: 10,
+            peak_frontier: 4,
+            visited: 7,
+            ..Stats::default()
+        };
+        let mut b = Stats {
+            expanded: 5,
+            peak_frontier: 6,
+            ..Stats::default()
+        };
+
+        a.prune("bound", 1);
+        b.prune("bound", 1);
+        b.prune("seen", 1);
+        b.prune("unused", 0);
+        b.frontier(3);
+
+        assert_eq!(
+            a.merge(b).to_string(),
+            "expanded 15, pruned 2 by bound, pruned 1 by seen, peak frontier 6, visited 7"
+        );
+    }
+}
@@ -0,0 +1,768 @@
+use std::{
+    hash::{Hash, Hasher},
+    ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign, BitXor, BitXorAssign, Not},
+};
+
+use super::{
+    geom::{Direction, Point3},
+    grid::{Grid, Pos, DELTAS_4},
+};
+
+const WORD: usize = u64::BITS as usize;
+
+/// The word that holds `bit`, and the mask for it within that word.
+fn locate(bit: usize) -> (usize, u64) {
+    (bit / WORD, 1 << (bit % WORD))
+}
+
+/// The positions of all set bits, in order.
+fn ones(words: &[u64]) -> impl Iterator<Item = usize> + '_ {
+    words.iter().enumerate().flat_map(|(idx, &word)| {
+        let mut word = word;
+
+        std::iter::from_fn(move || {
+            (word != 0).then(|| {
+                let bit = word.trailing_zeros() as usize;
+                word &= word - 1;
+                idx * WORD + bit
+            })
+        })
+    })
+}
+
+fn count_ones(words: &[u64]) -> usize {
+    words.iter().map(|word| word.count_ones() as usize).sum()
+}
+
+/// A set of small numbers with one bit for each, which grows to fit the largest one.
+#[derive(Debug, Clone, Default)]
+pub struct BitSet {
+    words: Vec<u64>,
+}
+
+impl BitSet {
+    pub fn new() -> Self {
+        BitSet::default()
+    }
+
+    /// A set that holds numbers below `bits` without growing.
+    pub fn with_capacity(bits: usize) -> Self {
+        BitSet {
+            words: vec![0; bits.div_ceil(WORD)],
+        }
+    }
+
+    /// Adds `value`, returning whether it was new.
+    pub fn insert(&mut self, value: usize) -> bool {
+        let (idx, mask) = locate(value);
+
+        if idx >= self.words.len() {
+            self.words.resize(idx + 1, 0);
+        }
+
+        let new = self.words[idx] & mask == 0;
+        self.words[idx] |= mask;
+        new
+    }
+
+    /// Takes `value` out, returning whether it was there.
+    pub fn remove(&mut self, value: usize) -> bool {
+        let (idx, mask) = locate(value);
+        let Some(word) = self.words.get_mut(idx) else {
+            return false;
+        };
+
+        let present = *word & mask != 0;
+        *word &= !mask;
+        present
+    }
+
+    pub fn contains(&self, value: usize) -> bool {
+        let (idx, mask) = locate(value);
+
+        self.words.get(idx).is_some_and(|word| word & mask != 0)
+    }
+
+    pub fn len(&self) -> usize {
+        count_ones(&self.words)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.words.iter().all(|&word| word == 0)
+    }
+
+    pub fn clear(&mut self) {
+        self.words.fill(0);
+    }
+
+    /// The numbers in the set, in ascending order.
+    pub fn iter(&self) -> impl Iterator<Item = usize> + '_ {
+        ones(&self.words)
+    }
+
+    /// The words without the empty ones at the end, which sets that only differ in capacity share.
+    fn trimmed(&self) -> &[u64] {
+        let len = self
+            .words
+            .iter()
+            .rposition(|&word| word != 0)
+            .map_or(0, |idx| idx + 1);
+
+        &self.words[..len]
+    }
+}
+
+/// Sets with the same numbers are equal, no matter how far they grew.
+impl PartialEq for BitSet {
+    fn eq(&self, other: &Self) -> bool {
+        self.trimmed() == other.trimmed()
+    }
+}
+
+impl Eq for BitSet {}
+
+impl Hash for BitSet {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.trimmed().hash(state);
+    }
+}
+
+impl BitOrAssign<&BitSet> for BitSet {
+    fn bitor_assign(&mut self, rhs: &BitSet) {
+        if self.words.len() < rhs.words.len() {
+            self.words.resize(rhs.words.len(), 0);
+        }
+
+        self.words
+            .iter_mut()
+            .zip(&rhs.words)
+            .for_each(|(l, r)| *l |= r);
+    }
+}
+
+impl BitAndAssign<&BitSet> for BitSet {
+    fn bitand_assign(&mut self, rhs: &BitSet) {
+        self.words.truncate(rhs.words.len());
+        self.words
+            .iter_mut()
+            .zip(&rhs.words)
+            .for_each(|(l, r)| *l &= r);
+    }
+}
+
+impl BitXorAssign<&BitSet> for BitSet {
+    fn bitxor_assign(&mut self, rhs: &BitSet) {
+        if self.words.len() < rhs.words.len() {
+            self.words.resize(rhs.words.len(), 0);
+        }
+
+        self.words
+            .iter_mut()
+            .zip(&rhs.words)
+            .for_each(|(l, r)| *l ^= r);
+    }
+}
+
+impl FromIterator<usize> for BitSet {
+    fn from_iter<I: IntoIterator<Item = usize>>(iter: I) -> Self {
+        let mut set = BitSet::new();
+        set.extend(iter);
+        set
+    }
+}
+
+impl Extend<usize> for BitSet {
+    fn extend<I: IntoIterator<Item = usize>>(&mut self, iter: I) {
+        for value in iter {
+            self.insert(value);
+        }
+    }
+}
+
+/// A set of numbers below `64 * WORDS`, which lives on the stack and is cheap to copy and hash.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct FixedBitSet<const WORDS: usize> {
+    words: [u64; WORDS],
+}
+
+impl<const WORDS: usize> Default for FixedBitSet<WORDS> {
+    fn default() -> Self {
+        FixedBitSet::new()
+    }
+}
+
+impl<const WORDS: usize> FixedBitSet<WORDS> {
+    /// How many numbers the set can hold, all of those below this one.
+    pub const CAPACITY: usize = WORDS * WORD;
+
+    pub const fn new() -> Self {
+        FixedBitSet { words: [0; WORDS] }
+    }
+
+    /// Adds `value`, returning whether it was new. Panics when it is beyond the capacity.
+    pub fn insert(&mut self, value: usize) -> bool {
+        let (idx, mask) = locate(value);
+
+        let new = self.words[idx] & mask == 0;
+        self.words[idx] |= mask;
+        new
+    }
+
+    /// Takes `value` out, returning whether it was there.
+    pub fn remove(&mut self, value: usize) -> bool {
+        let present = self.contains(value);
+
+        if present {
+            let (idx, mask) = locate(value);
+            self.words[idx] &= !mask;
+        }
+
+        present
+    }
+
+    pub fn contains(&self, value: usize) -> bool {
+        let (idx, mask) = locate(value);
+
+        self.words.get(idx).is_some_and(|word| word & mask != 0)
+    }
+
+    pub fn len(&self) -> usize {
+        count_ones(&self.words)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.words.iter().all(|&word| word == 0)
+    }
+
+    /// The numbers in the set, in ascending order.
+    pub fn iter(&self) -> impl Iterator<Item = usize> + '_ {
+        ones(&self.words)
+    }
+}
+
+impl<const WORDS: usize> FromIterator<usize> for FixedBitSet<WORDS> {
+    fn from_iter<I: IntoIterator<Item = usize>>(iter: I) -> Self {
+        let mut set = FixedBitSet::new();
+
+        for value in iter {
+            set.insert(value);
+        }
+
+        set
+    }
+}
+
+/// Implements a bitwise operator and its assigning form word by word.
+macro_rules! impl_fixed_op {
+    ($op:ident, $method:ident, $assign_op:ident, $assign_method:ident) => {
+        impl<const WORDS: usize> $assign_op for FixedBitSet<WORDS> {
+            fn $assign_method(&mut self, rhs: Self) {
+                self.words
+                    .iter_mut()
+                    .zip(rhs.words)
+                    .for_each(|(l, r)| l.$assign_method(r));
+            }
+        }
+
+        impl<const WORDS: usize> $op for FixedBitSet<WORDS> {
+            type Output = Self;
+
+            fn $method(mut self, rhs: Self) -> Self {
+                self.$assign_method(rhs);
+                self
+            }
+        }
+    };
+}
+
+impl_fixed_op!(BitOr, bitor, BitOrAssign, bitor_assign);
+impl_fixed_op!(BitAnd, bitand, BitAndAssign, bitand_assign);
+impl_fixed_op!(BitXor, bitxor, BitXorAssign, bitxor_assign);
+
+impl<const WORDS: usize> Not for FixedBitSet<WORDS> {
+    type Output = Self;
+
+    fn not(self) -> Self {
+        FixedBitSet {
+            words: self.words.map(|word| !word),
+        }
+    }
+}
+
+/// A rectangle of bits stored row by row, with `(0, 0)` in the top left corner like a [`Grid`].
+///
+/// Every row starts a new word, so that whole rows can be read and combined at once.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct BitBoard {
+    words: Vec<u64>,
+    width: usize,
+    height: usize,
+    /// How many words every row takes.
+    stride: usize,
+}
+
+impl BitBoard {
+    /// A board without any bits set.
+    pub fn new(width: usize, height: usize) -> Self {
+        let stride = width.div_ceil(WORD);
+
+        BitBoard {
+            words: vec![0; stride * height],
+            width,
+            height,
+            stride,
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn in_bounds(&self, (x, y): Pos) -> bool {
+        x < self.width && y < self.height
+    }
+
+    fn locate(&self, (x, y): Pos) -> (usize, u64) {
+        let (idx, mask) = locate(x);
+
+        (y * self.stride + idx, mask)
+    }
+
+    /// Whether the bit at `pos` is set, which is never the case outside of the board.
+    pub fn get(&self, pos: Pos) -> bool {
+        let (idx, mask) = self.locate(pos);
+
+        self.in_bounds(pos) && self.words[idx] & mask != 0
+    }
+
+    /// Sets or clears the bit at `pos`, which must be on the board.
+    pub fn set(&mut self, pos: Pos, value: bool) {
+        assert!(self.in_bounds(pos), "{pos:?} is outside of the board");
+
+        let (idx, mask) = self.locate(pos);
+
+        if value {
+            self.words[idx] |= mask;
+        } else {
+            self.words[idx] &= !mask;
+        }
+    }
+
+    pub fn count_ones(&self) -> usize {
+        count_ones(&self.words)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.words.iter().all(|&word| word == 0)
+    }
+
+    /// The positions of the set bits, row by row from the top.
+    pub fn iter(&self) -> impl Iterator<Item = Pos> + '_ {
+        self.words
+            .chunks(self.stride.max(1))
+            .enumerate()
+            .flat_map(|(y, row)| ones(row).map(move |x| (x, y)))
+    }
+
+    /// The words of row `y`, with column `x` in bit `x % 64` of word `x / 64`.
+    pub fn row(&self, y: usize) -> &[u64] {
+        &self.words[y * self.stride..(y + 1) * self.stride]
+    }
+
+    /// Like [`BitBoard::row`], where the bits beyond the width have to stay clear.
+    pub fn row_mut(&mut self, y: usize) -> &mut [u64] {
+        &mut self.words[y * self.stride..(y + 1) * self.stride]
+    }
+
+    /// Adds an empty row below the others.
+    pub fn push_row(&mut self) {
+        self.words.resize(self.words.len() + self.stride, 0);
+        self.height += 1;
+    }
+
+    /// Whether any bit is set in the outermost rows or columns, which an empty board has none of.
+    pub fn touches_edge(&self) -> bool {
+        let (first, last) = (locate(0), locate(self.width.saturating_sub(1)));
+
+        self.width > 0
+            && self.height > 0
+            && (self.row(0).iter().any(|&word| word != 0)
+                || self.row(self.height - 1).iter().any(|&word| word != 0)
+                || (0..self.height).any(|y| {
+                    let row = self.row(y);
+                    row[first.0] & first.1 != 0 || row[last.0] & last.1 != 0
+                }))
+    }
+
+    /// A board that is larger by `margin` on every side, with the bits moved along.
+    pub fn padded(&self, margin: usize) -> BitBoard {
+        let mut padded = BitBoard::new(self.width + 2 * margin, self.height + 2 * margin);
+
+        for (x, y) in self.iter() {
+            padded.set((x + margin, y + margin), true);
+        }
+
+        padded
+    }
+
+    /// Moves every bit one step in `dir`, dropping the ones that leave the board.
+    pub fn shifted(&self, dir: Direction) -> BitBoard {
+        // A board without rows or columns has no words to move, and no stride to move them by.
+        if self.words.is_empty() {
+            return self.clone();
+        }
+
+        let mut shifted = BitBoard::new(self.width, self.height);
+        let (len, stride) = (self.words.len(), self.stride);
+
+        match dir {
+            Direction::North => {
+                shifted.words[..len - stride].copy_from_slice(&self.words[stride..])
+            }
+            Direction::South => {
+                shifted.words[stride..].copy_from_slice(&self.words[..len - stride])
+            }
+            Direction::East => {
+                for (to, from) in shifted
+                    .words
+                    .chunks_mut(stride)
+                    .zip(self.words.chunks(stride))
+                {
+                    for idx in 0..stride {
+                        let carry = if idx > 0 {
+                            from[idx - 1] >> (WORD - 1)
+                        } else {
+                            0
+                        };
+                        to[idx] = from[idx] << 1 | carry;
+                    }
+                }
+
+                shifted.clear_beyond_width();
+            }
+            Direction::West => {
+                for (to, from) in shifted
+                    .words
+                    .chunks_mut(stride)
+                    .zip(self.words.chunks(stride))
+                {
+                    for idx in 0..stride {
+                        let carry = from.get(idx + 1).map_or(0, |next| next << (WORD - 1));
+                        to[idx] = from[idx] >> 1 | carry;
+                    }
+                }
+            }
+        }
+
+        shifted
+    }
+
+    /// Moves `pos` by `delta`, or returns `None` when that leaves the board.
+    pub fn checked_add(&self, pos: Pos, delta: (isize, isize)) -> Option<Pos> {
+        let next = (
+            pos.0.checked_add_signed(delta.0)?,
+            pos.1.checked_add_signed(delta.1)?,
+        );
+
+        self.in_bounds(next).then_some(next)
+    }
+
+    /// The up to four orthogonally adjacent positions that are on the board, in the same order as
+    /// [`Grid::neighbours4`].
+    pub fn neighbours4(&self, pos: Pos) -> impl Iterator<Item = Pos> + '_ {
+        DELTAS_4
+            .iter()
+            .filter_map(move |&delta| self.checked_add(pos, delta))
+    }
+
+    fn clear_beyond_width(&mut self) {
+        if self.width.is_multiple_of(WORD) {
+            return;
+        }
+
+        let mask = (1 << (self.width % WORD)) - 1;
+
+        for row in self.words.chunks_mut(self.stride) {
+            row[self.stride - 1] &= mask;
+        }
+    }
+}
+
+/// Sets the bits of the cells that are `true`.
+impl From<&Grid<bool>> for BitBoard {
+    fn from(grid: &Grid<bool>) -> Self {
+        let mut board = BitBoard::new(grid.width(), grid.height());
+
+        for pos in grid.positions().filter(|&pos| grid[pos]) {
+            board.set(pos, true);
+        }
+
+        board
+    }
+}
+
+/// Implements a bitwise operator and its assigning form word by word, for boards of equal size.
+macro_rules! impl_board_op {
+    ($op:ident, $method:ident, $assign_op:ident, $assign_method:ident) => {
+        impl $assign_op<&BitBoard> for BitBoard {
+            fn $assign_method(&mut self, rhs: &BitBoard) {
+                assert_eq!(
+                    (self.width, self.height),
+                    (rhs.width, rhs.height),
+                    "The boards differ in size"
+                );
+
+                self.words
+                    .iter_mut()
+                    .zip(&rhs.words)
+                    .for_each(|(l, r)| l.$assign_method(r));
+            }
+        }
+
+        impl $op for &BitBoard {
+            type Output = BitBoard;
+
+            fn $method(self, rhs: &BitBoard) -> BitBoard {
+                let mut result = self.clone();
+                result.$assign_method(rhs);
+                result
+            }
+        }
+    };
+}
+
+impl_board_op!(BitOr, bitor, BitOrAssign, bitor_assign);
+impl_board_op!(BitAnd, bitand, BitAndAssign, bitand_assign);
+impl_board_op!(BitXor, bitxor, BitXorAssign, bitxor_assign);
+
+impl Not for &BitBoard {
+    type Output = BitBoard;
+
+    fn not(self) -> BitBoard {
+        let mut result = BitBoard {
+            words: self.words.iter().map(|word| !word).collect(),
+            ..*self
+        };
+
+        result.clear_beyond_width();
+        result
+    }
+}
+
+/// A box of bits, indexed by points with every coordinate from zero up to the size of the box.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BitVolume {
+    bits: BitSet,
+    width: usize,
+    height: usize,
+    depth: usize,
+}
+
+impl BitVolume {
+    /// A volume without any bits set.
+    pub fn new(width: usize, height: usize, depth: usize) -> Self {
+        BitVolume {
+            bits: BitSet::with_capacity(width * height * depth),
+            width,
+            height,
+            depth,
+        }
+    }
+
+    pub fn in_bounds(&self, point: Point3) -> bool {
+        (0..self.width as i32).contains(&point.x)
+            && (0..self.height as i32).contains(&point.y)
+            && (0..self.depth as i32).contains(&point.z)
+    }
+
+    fn index(&self, point: Point3) -> usize {
+        point.x as usize + self.width * (point.y as usize + self.height * point.z as usize)
+    }
+
+    /// Whether the bit at `point` is set, which is never the case outside of the volume.
+    pub fn get(&self, point: Point3) -> bool {
+        self.in_bounds(point) && self.bits.contains(self.index(point))
+    }
+
+    /// Sets or clears the bit at `point`, which must be in the volume.
+    pub fn set(&mut self, point: Point3, value: bool) {
+        assert!(self.in_bounds(point), "{point:?} is outside of the volume");
+
+        let idx = self.index(point);
+
+        if value {
+            self.bits.insert(idx);
+        } else {
+            self.bits.remove(idx);
+        }
+    }
+
+    pub fn count_ones(&self) -> usize {
+        self.bits.len()
+    }
+
+    /// The points of the set bits, ordered by `z`, then `y` and then `x`.
+    pub fn iter(&self) -> impl Iterator<Item = Point3> + '_ {
+        self.bits.iter().map(|idx| {
+            let (x, rest) = (idx % self.width, idx / self.width);
+
+            Point3::new(
+                x as i32,
+                (rest % self.height) as i32,
+                (rest / self.height) as i32,
+            )
+        })
+    }
+
+    /// How many of the six points sharing a face with `point`, which must be in the volume, have
+    /// their bit set.
+    pub fn count_neighbours(&self, point: Point3) -> usize {
+        assert!(self.in_bounds(point), "{point:?} is outside of the volume");
+
+        let idx = self.index(point);
+        let axes = [
+            (point.x as usize, self.width, 1),
+            (point.y as usize, self.height, self.width),
+            (point.z as usize, self.depth, self.width * self.height),
+        ];
+
+        axes.into_iter()
+            .map(|(coordinate, size, step)| {
+                let below = coordinate > 0 && self.bits.contains(idx - step);
+                let above = coordinate + 1 < size && self.bits.contains(idx + step);
+
+                below as usize + above as usize
+            })
+            .sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use proptest::prelude::*;
+
+    use super::{BitBoard, BitSet, BitVolume, FixedBitSet};
+    use crate::util::geom::{Direction, Point3};
+
+    #[test]
+    fn bit_set() {
+        let mut set = BitSet::new();
+
+        assert!(set.insert(3) && set.insert(130) && !set.insert(3));
+        assert!(set.contains(130) && !set.contains(64) && !set.contains(1000));
+        assert_eq!(set.iter().collect::<Vec<_>>(), [3, 130]);
+
+        set.remove(130);
+
+        assert_eq!(set, [3].into_iter().collect());
+        assert_eq!(set.len(), 1);
+
+        set |= &[64, 200].into_iter().collect();
+        set &= &[3, 200].into_iter().collect();
+
+        assert_eq!(set.iter().collect::<Vec<_>>(), [3, 200]);
+    }
+
+    #[test]
+    fn fixed_bit_set() {
+        let set = [0, 63, 64, 127].into_iter().collect::<FixedBitSet<2>>();
+
+        assert_eq!(FixedBitSet::<2>::CAPACITY, 128);
+        assert_eq!(set.len(), 4);
+        assert_eq!(
+            !set & [1, 64].into_iter().collect(),
+            [1].into_iter().collect()
+        );
+        assert_eq!((!set).len(), 124);
+        assert!(!set.contains(128));
+    }
+
+    #[test]
+    fn bit_board() {
+        let mut board = BitBoard::new(3, 2);
+
+        board.set((0, 0), true);
+        board.set((2, 1), true);
+
+        assert_eq!(board.iter().collect::<Vec<_>>(), [(0, 0), (2, 1)]);
+        assert_eq!((!&board).count_ones(), 4);
+        assert!(board.touches_edge());
+
+        let padded = board.padded(1);
+
+        assert_eq!((padded.width(), padded.height()), (5, 4));
+        assert_eq!(padded.iter().collect::<Vec<_>>(), [(1, 1), (3, 2)]);
+        assert!(!padded.touches_edge());
+        assert_eq!(
+            padded.neighbours4((0, 0)).collect::<Vec<_>>(),
+            [(0, 1), (1, 0)]
+        );
+    }
+
+    fn points() -> impl Strategy<Value = (usize, usize, Vec<(usize, usize)>)> {
+        (1..150usize, 1..5usize).prop_flat_map(|(width, height)| {
+            let points = prop::collection::vec((0..width, 0..height), 0..20);
+
+            (Just(width), Just(height), points)
+        })
+    }
+
+    proptest! {
+        /// Shifting a board moves the same bits as moving every point on its own.
+        #[test]
+        fn shifted((width, height, points) in points()) {
+            let mut board = BitBoard::new(width, height);
+
+            for &pos in &points {
+                board.set(pos, true);
+            }
+
+            for dir in Direction::ALL {
+                let expected = points
+                    .iter()
+                    .filter_map(|&pos| board.checked_add(pos, dir.delta()))
+                    .collect::<HashSet<_>>();
+
+                prop_assert_eq!(board.shifted(dir).iter().collect::<HashSet<_>>(), expected);
+            }
+        }
+    }
+
+    #[test]
+    fn shifted_empty() {
+        for (width, height) in [(0, 0), (5, 0), (0, 3)] {
+            let board = BitBoard::new(width, height);
+
+            for dir in Direction::ALL {
+                let shifted = board.shifted(dir);
+
+                assert_eq!((shifted.width(), shifted.height()), (width, height));
+                assert!(shifted.is_empty());
+            }
+
+            assert!(!board.touches_edge());
+        }
+    }
+
+    #[test]
+    fn bit_volume() {
+        let mut volume = BitVolume::new(3, 3, 3);
+
+        for point in Point3::new(1, 1, 1).neighbours6() {
+            volume.set(point, true);
+        }
+
+        assert_eq!(volume.count_neighbours(Point3::new(1, 1, 1)), 6);
+        assert_eq!(volume.count_neighbours(Point3::new(0, 0, 0)), 0);
+        assert_eq!(volume.count_neighbours(Point3::new(0, 1, 0)), 2);
+        assert_eq!(volume.iter().next(), Some(Point3::new(1, 1, 0)));
+        assert_eq!(volume.count_ones(), 6);
+        assert!(!volume.get(Point3::new(-1, 1, 1)));
+    }
+}
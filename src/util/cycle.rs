@@ -0,0 +1,228 @@
+use std::{
+    collections::{hash_map::Entry, HashMap},
+    hash::Hash,
+};
+
+/// Something measured after every step of a simulation, which changes by the same amount over
+/// every cycle once the simulation repeats itself.
+pub trait Metric: Clone {
+    /// How much the metric changes from `self` to `later`, which for unsigned metrics must not be
+    /// smaller.
+    fn delta(&self, later: &Self) -> Self;
+
+    /// The metric after it changed by `delta` `times` times.
+    fn advance(&self, delta: &Self, times: u64) -> Self;
+}
+
+macro_rules! impl_metric {
+    ($($t:ty),*) => {
+        $(
+            impl Metric for $t {
+                fn delta(&self, later: &Self) -> Self {
+                    later - self
+                }
+
+                fn advance(&self, delta: &Self, times: u64) -> Self {
+                    self + delta * times as $t
+                }
+            }
+        )*
+    };
+}
+
+impl_metric!(u32, u64, usize, i32, i64);
+
+/// For simulations that only need to know whether and when they repeat.
+impl Metric for () {
+    fn delta(&self, _: &Self) -> Self {}
+
+    fn advance(&self, _: &Self, _: u64) -> Self {}
+}
+
+/// Every value changes on its own.
+impl<T: Metric> Metric for Vec<T> {
+    fn delta(&self, later: &Self) -> Self {
+        self.iter().zip(later).map(|(m, l)| m.delta(l)).collect()
+    }
+
+    fn advance(&self, delta: &Self, times: u64) -> Self {
+        self.iter()
+            .zip(delta)
+            .map(|(m, d)| m.advance(d, times))
+            .collect()
+    }
+}
+
+/// Where a simulation starts repeating itself.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cycle {
+    /// How many steps it takes to reach the first state that comes up again.
+    pub prefix: usize,
+    /// How many steps it takes to get back to a state.
+    pub period: usize,
+}
+
+/// The metrics of a simulation after every step, up to the end of its first cycle, see [`detect`].
+#[derive(Debug, Clone)]
+pub struct History<M> {
+    metrics: Vec<M>,
+    cycle: Option<Cycle>,
+}
+
+impl<M: Metric> History<M> {
+    /// The cycle, or `None` when the simulation reached its limit before it repeated.
+    pub fn cycle(&self) -> Option<Cycle> {
+        self.cycle
+    }
+
+    /// How many steps were simulated.
+    pub fn steps(&self) -> usize {
+        self.metrics.len() - 1
+    }
+
+    /// How much the metrics change over one cycle.
+    pub fn delta(&self) -> Option<M> {
+        self.cycle.map(|cycle| {
+            self.metrics[cycle.prefix].delta(&self.metrics[cycle.prefix + cycle.period])
+        })
+    }
+
+    /// The smallest number of steps that leads to the same state as `steps` do, or `None` when
+    /// `steps` goes past the limit of a simulation that didn't repeat.
+    pub fn equivalent(&self, steps: usize) -> Option<usize> {
+        match self.cycle {
+            Some(Cycle { prefix, period }) if steps > prefix => {
+                Some(prefix + (steps - prefix) % period)
+            }
+            _ => (steps <= self.steps()).then_some(steps),
+        }
+    }
+
+    /// The metrics after `steps` steps, skipping over as many cycles as it takes.
+    pub fn after(&self, steps: usize) -> Option<M> {
+        let equivalent = self.equivalent(steps)?;
+        let metrics = &self.metrics[equivalent];
+
+        match (self.cycle, self.delta()) {
+            (Some(cycle), Some(delta)) if steps > cycle.prefix => {
+                Some(metrics.advance(&delta, ((steps - cycle.prefix) / cycle.period) as u64))
+            }
+            _ => Some(metrics.clone()),
+        }
+    }
+}
+
+/// Applies `step` to `state` until it reaches a state with the same `key` as an earlier one, or
+/// until it took `limit` steps. The key has to capture everything that the following steps depend
+/// on, and `metric` is measured for the initial state and after every step.
+pub fn detect<S, K, M>(
+    mut state: S,
+    limit: usize,
+    mut step: impl FnMut(&mut S),
+    mut key: impl FnMut(&S) -> K,
+    mut metric: impl FnMut(&S) -> M,
+) -> History<M>
+where
+    K: Eq + Hash,
+    M: Metric,
+{
+    let mut seen = HashMap::new();
+    let mut metrics = Vec::new();
+
+    for steps in 0.. {
+        metrics.push(metric(&state));
+
+        match seen.entry(key(&state)) {
+            Entry::Occupied(entry) => {
+                let prefix = *entry.get();
+
+                return History {
+                    metrics,
+                    cycle: Some(Cycle {
+                        prefix,
+                        period: steps - prefix,
+                    }),
+                };
+            }
+            Entry::Vacant(entry) => {
+                entry.insert(steps);
+            }
+        }
+
+        if steps == limit {
+            break;
+        }
+
+        step(&mut state);
+    }
+
+    History {
+        metrics,
+        cycle: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+
+    use super::{detect, Cycle};
+
+    #[test]
+    fn cycle() {
+        // 0 -> 1 -> 2 -> 3 -> 4 -> 2 -> ...
+        let next = [1, 2, 3, 4, 2];
+        let history = detect(0, usize::MAX, |x| *x = next[*x], |&x| x, |&x| x as u64 + 10);
+
+        assert_eq!(
+            history.cycle(),
+            Some(Cycle {
+                prefix: 2,
+                period: 3
+            })
+        );
+        assert_eq!(history.steps(), 5);
+        assert_eq!(history.delta(), Some(0));
+        assert_eq!(history.equivalent(1), Some(1));
+        assert_eq!(history.equivalent(9), Some(3));
+        assert_eq!(history.after(10), Some(14));
+    }
+
+    #[test]
+    fn limit() {
+        let history = detect(0u64, 10, |x| *x += 1, |&x| x, |&x| x);
+
+        assert_eq!(history.cycle(), None);
+        assert_eq!(history.after(10), Some(10));
+        assert_eq!(history.after(11), None);
+    }
+
+    proptest! {
+        /// Skipping cycles gives the same metrics as running every step.
+        #[test]
+        fn matches_simulation(start in 0..100u32, modulus in 1..100u32, steps in 0..1000usize) {
+            // Counts steps and sums up the values, which both grow by the same amount every cycle.
+            let step = |(x, count, sum): &mut (u32, u64, u64)| {
+                *x = (*x * *x + 1) % modulus;
+                *count += 1;
+                *sum += *x as u64;
+            };
+
+            let history = detect(
+                (start % modulus, 0, 0),
+                usize::MAX,
+                step,
+                |&(x, _, _)| x,
+                |&(_, count, sum)| vec![count, sum],
+            );
+
+            let mut state = (start % modulus, 0, 0);
+
+            for _ in 0..steps {
+                step(&mut state);
+            }
+
+            prop_assert_eq!(history.after(steps), Some(vec![state.1, state.2]));
+        }
+    }
+}
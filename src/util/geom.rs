@@ -0,0 +1,345 @@
+use std::ops::{Add, AddAssign, Mul, Neg, Sub, SubAssign};
+
+use serde::{Serialize, Serializer};
+
+use crate::util::grid;
+
+/// A point in the plane, with `y` growing downwards like the rows of a [`Grid`](grid::Grid).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Point2 {
+    pub x: i32,
+    pub y: i32,
+}
+
+impl Point2 {
+    pub const ORIGIN: Point2 = Point2::new(0, 0);
+
+    pub const fn new(x: i32, y: i32) -> Self {
+        Point2 { x, y }
+    }
+
+    /// The neighbouring point in `dir`.
+    pub fn step(self, dir: Direction) -> Self {
+        self + dir.offset()
+    }
+
+    pub fn manhattan(self, other: Point2) -> u32 {
+        self.x.abs_diff(other.x) + self.y.abs_diff(other.y)
+    }
+
+    pub fn chebyshev(self, other: Point2) -> u32 {
+        self.x.abs_diff(other.x).max(self.y.abs_diff(other.y))
+    }
+
+    /// The sign of both coordinates, which is one step towards the origin and beyond.
+    pub fn signum(self) -> Self {
+        Point2::new(self.x.signum(), self.y.signum())
+    }
+
+    /// The four orthogonally adjacent points, clockwise from the north.
+    pub fn neighbours4(self) -> impl Iterator<Item = Point2> {
+        Direction::ALL.into_iter().map(move |dir| self.step(dir))
+    }
+
+    /// The eight orthogonally and diagonally adjacent points, row by row.
+    pub fn neighbours8(self) -> impl Iterator<Item = Point2> {
+        (-1..=1)
+            .flat_map(|dy| (-1..=1).map(move |dx| Point2::new(dx, dy)))
+            .filter(|&offset| offset != Point2::ORIGIN)
+            .map(move |offset| self + offset)
+    }
+}
+
+impl Add for Point2 {
+    type Output = Point2;
+
+    fn add(self, rhs: Point2) -> Self::Output {
+        Point2::new(self.x + rhs.x, self.y + rhs.y)
+    }
+}
+
+impl AddAssign for Point2 {
+    fn add_assign(&mut self, rhs: Point2) {
+        *self = *self + rhs;
+    }
+}
+
+impl Sub for Point2 {
+    type Output = Point2;
+
+    fn sub(self, rhs: Point2) -> Self::Output {
+        Point2::new(self.x - rhs.x, self.y - rhs.y)
+    }
+}
+
+impl SubAssign for Point2 {
+    fn sub_assign(&mut self, rhs: Point2) {
+        *self = *self - rhs;
+    }
+}
+
+impl Mul<i32> for Point2 {
+    type Output = Point2;
+
+    fn mul(self, rhs: i32) -> Self::Output {
+        Point2::new(self.x * rhs, self.y * rhs)
+    }
+}
+
+impl Neg for Point2 {
+    type Output = Point2;
+
+    fn neg(self) -> Self::Output {
+        Point2::new(-self.x, -self.y)
+    }
+}
+
+impl From<(i32, i32)> for Point2 {
+    fn from((x, y): (i32, i32)) -> Self {
+        Point2::new(x, y)
+    }
+}
+
+impl From<grid::Pos> for Point2 {
+    fn from((x, y): grid::Pos) -> Self {
+        Point2::new(x as i32, y as i32)
+    }
+}
+
+/// Serializes as `[x, y]`.
+impl Serialize for Point2 {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        (self.x, self.y).serialize(serializer)
+    }
+}
+
+/// A point in space.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Point3 {
+    pub x: i32,
+    pub y: i32,
+    pub z: i32,
+}
+
+impl Point3 {
+    pub const ORIGIN: Point3 = Point3::new(0, 0, 0);
+
+    pub const fn new(x: i32, y: i32, z: i32) -> Self {
+        Point3 { x, y, z }
+    }
+
+    pub fn manhattan(self, other: Point3) -> u32 {
+        self.x.abs_diff(other.x) + self.y.abs_diff(other.y) + self.z.abs_diff(other.z)
+    }
+
+    pub fn chebyshev(self, other: Point3) -> u32 {
+        self.x
+            .abs_diff(other.x)
+            .max(self.y.abs_diff(other.y))
+            .max(self.z.abs_diff(other.z))
+    }
+
+    /// The six points sharing a face with this one, along `x`, `y` and then `z`.
+    pub fn neighbours6(self) -> impl Iterator<Item = Point3> {
+        [
+            Point3::new(-1, 0, 0),
+            Point3::new(1, 0, 0),
+            Point3::new(0, -1, 0),
+            Point3::new(0, 1, 0),
+            Point3::new(0, 0, -1),
+            Point3::new(0, 0, 1),
+        ]
+        .into_iter()
+        .map(move |offset| self + offset)
+    }
+}
+
+impl Add for Point3 {
+    type Output = Point3;
+
+    fn add(self, rhs: Point3) -> Self::Output {
+        Point3::new(self.x + rhs.x, self.y + rhs.y, self.z + rhs.z)
+    }
+}
+
+impl Sub for Point3 {
+    type Output = Point3;
+
+    fn sub(self, rhs: Point3) -> Self::Output {
+        Point3::new(self.x - rhs.x, self.y - rhs.y, self.z - rhs.z)
+    }
+}
+
+/// Serializes as `[x, y, z]`.
+impl Serialize for Point3 {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        (self.x, self.y, self.z).serialize(serializer)
+    }
+}
+
+/// One of the four directions in the plane, in clockwise order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Direction {
+    North,
+    East,
+    South,
+    West,
+}
+
+impl Direction {
+    pub const ALL: [Direction; 4] = [
+        Direction::North,
+        Direction::East,
+        Direction::South,
+        Direction::West,
+    ];
+
+    /// Turns clockwise by `quarter_turns`, or counter-clockwise when it is negative.
+    pub fn rotate(self, quarter_turns: i32) -> Self {
+        Direction::ALL[(self as i32 + quarter_turns).rem_euclid(4) as usize]
+    }
+
+    pub fn turn_left(self) -> Self {
+        self.rotate(-1)
+    }
+
+    pub fn turn_right(self) -> Self {
+        self.rotate(1)
+    }
+
+    pub fn reverse(self) -> Self {
+        self.rotate(2)
+    }
+
+    /// One step in this direction.
+    pub fn offset(self) -> Point2 {
+        match self {
+            Direction::North => Point2::new(0, -1),
+            Direction::East => Point2::new(1, 0),
+            Direction::South => Point2::new(0, 1),
+            Direction::West => Point2::new(-1, 0),
+        }
+    }
+
+    /// One step in this direction, to move around a [`Grid`](grid::Grid) with.
+    pub fn delta(self) -> (isize, isize) {
+        let offset = self.offset();
+
+        (offset.x as isize, offset.y as isize)
+    }
+}
+
+/// The smallest rectangle that contains some points, including its edges.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BoundingBox {
+    pub min: Point2,
+    pub max: Point2,
+}
+
+impl BoundingBox {
+    /// The box around `points`, or `None` when there are none.
+    pub fn of(points: impl IntoIterator<Item = Point2>) -> Option<Self> {
+        let mut points = points.into_iter();
+        let first = points.next()?;
+
+        Some(points.fold(
+            BoundingBox {
+                min: first,
+                max: first,
+            },
+            |mut bounds, point| {
+                bounds.include(point);
+                bounds
+            },
+        ))
+    }
+
+    /// Grows the box just enough to contain `point`.
+    pub fn include(&mut self, point: Point2) {
+        self.min = Point2::new(self.min.x.min(point.x), self.min.y.min(point.y));
+        self.max = Point2::new(self.max.x.max(point.x), self.max.y.max(point.y));
+    }
+
+    pub fn contains(&self, point: Point2) -> bool {
+        (self.min.x..=self.max.x).contains(&point.x) && (self.min.y..=self.max.y).contains(&point.y)
+    }
+
+    pub fn width(&self) -> usize {
+        (self.max.x - self.min.x) as usize + 1
+    }
+
+    pub fn height(&self) -> usize {
+        (self.max.y - self.min.y) as usize + 1
+    }
+
+    pub fn area(&self) -> usize {
+        self.width() * self.height()
+    }
+
+    /// Where `point` is in a [`Grid`](grid::Grid) of the size of the box, which must contain it.
+    pub fn grid_pos(&self, point: Point2) -> grid::Pos {
+        assert!(self.contains(point), "{point:?} is outside of {self:?}");
+
+        (
+            (point.x - self.min.x) as usize,
+            (point.y - self.min.y) as usize,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BoundingBox, Direction, Point2, Point3};
+
+    #[test]
+    fn distances() {
+        let (a, b) = (Point2::new(1, -2), Point2::new(-3, 5));
+
+        assert_eq!(a.manhattan(b), 11);
+        assert_eq!(a.chebyshev(b), 7);
+        assert_eq!((b - a).signum(), Point2::new(-1, 1));
+        assert_eq!(Point3::new(1, 2, 3).manhattan(Point3::ORIGIN), 6);
+        assert_eq!(Point3::new(1, -5, 3).chebyshev(Point3::ORIGIN), 5);
+    }
+
+    #[test]
+    fn neighbours() {
+        let point = Point2::new(2, 3);
+
+        assert_eq!(
+            point.neighbours4().collect::<Vec<_>>(),
+            [(2, 2), (3, 3), (2, 4), (1, 3)].map(Point2::from)
+        );
+        assert_eq!(point.neighbours8().count(), 8);
+        assert!(point.neighbours8().all(|other| other.chebyshev(point) == 1));
+        assert!(Point3::ORIGIN
+            .neighbours6()
+            .all(|other| other.manhattan(Point3::ORIGIN) == 1));
+    }
+
+    #[test]
+    fn directions() {
+        assert_eq!(Direction::North.turn_left(), Direction::West);
+        assert_eq!(Direction::West.turn_right(), Direction::North);
+        assert_eq!(Direction::East.reverse(), Direction::West);
+        assert_eq!(Direction::South.rotate(-7), Direction::West);
+
+        for dir in Direction::ALL {
+            assert_eq!(dir.reverse().offset(), -dir.offset());
+            assert_eq!(Point2::ORIGIN.step(dir).step(dir.reverse()), Point2::ORIGIN);
+        }
+    }
+
+    #[test]
+    fn bounding_box() {
+        let bounds = BoundingBox::of([(2, 1), (-1, 4), (0, 0)].map(Point2::from)).unwrap();
+
+        assert_eq!(bounds.min, Point2::new(-1, 0));
+        assert_eq!(bounds.max, Point2::new(2, 4));
+        assert_eq!((bounds.width(), bounds.height(), bounds.area()), (4, 5, 20));
+        assert_eq!(bounds.grid_pos(Point2::new(0, 1)), (1, 1));
+        assert!(!bounds.contains(Point2::new(3, 0)));
+        assert_eq!(BoundingBox::of([]), None);
+    }
+}
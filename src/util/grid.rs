@@ -0,0 +1,358 @@
+use std::{
+    fmt::Display,
+    ops::{Index, IndexMut},
+};
+
+use serde::{Serialize, Serializer};
+
+use crate::Error;
+
+/// Column and row of a cell, with `(0, 0)` in the top left corner.
+pub type Pos = (usize, usize);
+
+pub(super) const DELTAS_4: [(isize, isize); 4] = [(0, -1), (-1, 0), (0, 1), (1, 0)];
+
+const DELTAS_8: [(isize, isize); 8] = [
+    (-1, -1),
+    (0, -1),
+    (1, -1),
+    (-1, 0),
+    (1, 0),
+    (-1, 1),
+    (0, 1),
+    (1, 1),
+];
+
+/// A rectangular map stored row by row.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Grid<T> {
+    data: Vec<T>,
+    width: usize,
+}
+
+impl<T> Grid<T> {
+    pub fn new(width: usize, height: usize, fill: T) -> Self
+    where
+        T: Clone,
+    {
+        Grid {
+            data: vec![fill; width * height],
+            width,
+        }
+    }
+
+    /// Parses a character map where every line must be equally long.
+    pub fn parse(
+        s: &str,
+        expected: &str,
+        mut cell: impl FnMut(char) -> Option<T>,
+    ) -> Result<Self, Error> {
+        let width = s.lines().next().map_or(0, |line| line.chars().count());
+        let mut data = Vec::with_capacity(s.len());
+
+        for line in s.lines() {
+            let mut len = 0;
+
+            for (idx, c) in line.char_indices() {
+                data.push(cell(c).ok_or_else(|| Error::parse(&line[idx..], expected))?);
+                len += 1;
+            }
+
+            if len != width {
+                return Err(Error::parse(line, format!("a row of {width} cells")));
+            }
+        }
+
+        Ok(Grid { data, width })
+    }
+
+    /// Parses a character map where short lines are padded with `fill` to the longest line.
+    pub fn parse_padded(
+        s: &str,
+        expected: &str,
+        fill: T,
+        mut cell: impl FnMut(char) -> Option<T>,
+    ) -> Result<Self, Error>
+    where
+        T: Clone,
+    {
+        let width = s
+            .lines()
+            .map(|line| line.chars().count())
+            .max()
+            .unwrap_or(0);
+        let mut data = Vec::with_capacity(s.len());
+
+        for line in s.lines() {
+            let mut len = 0;
+
+            for (idx, c) in line.char_indices() {
+                data.push(cell(c).ok_or_else(|| Error::parse(&line[idx..], expected))?);
+                len += 1;
+            }
+
+            data.extend(std::iter::repeat_n(fill.clone(), width - len));
+        }
+
+        Ok(Grid { data, width })
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.data.len().checked_div(self.width).unwrap_or(0)
+    }
+
+    pub fn contains(&self, (x, y): Pos) -> bool {
+        x < self.width() && y < self.height()
+    }
+
+    pub fn get(&self, pos: Pos) -> Option<&T> {
+        self.contains(pos)
+            .then(|| &self.data[pos.1 * self.width + pos.0])
+    }
+
+    pub fn get_mut(&mut self, pos: Pos) -> Option<&mut T> {
+        if self.contains(pos) {
+            Some(&mut self.data[pos.1 * self.width + pos.0])
+        } else {
+            None
+        }
+    }
+
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = &T> {
+        self.data.iter()
+    }
+
+    pub fn iter_mut(&mut self) -> impl DoubleEndedIterator<Item = &mut T> {
+        self.data.iter_mut()
+    }
+
+    /// All positions in the grid, row by row.
+    pub fn positions(&self) -> impl DoubleEndedIterator<Item = Pos> {
+        let width = self.width;
+
+        (0..self.height()).flat_map(move |y| (0..width).map(move |x| (x, y)))
+    }
+
+    /// The position of the first cell, row by row, that matches `predicate`.
+    pub fn position(&self, predicate: impl FnMut(&T) -> bool) -> Option<Pos> {
+        self.data
+            .iter()
+            .position(predicate)
+            .map(|idx| (idx % self.width, idx / self.width))
+    }
+
+    pub fn row(&self, y: usize) -> impl DoubleEndedIterator<Item = &T> {
+        assert!(y < self.height());
+
+        self.data[y * self.width..(y + 1) * self.width].iter()
+    }
+
+    pub fn row_mut(&mut self, y: usize) -> impl DoubleEndedIterator<Item = &mut T> {
+        assert!(y < self.height());
+
+        self.data[y * self.width..(y + 1) * self.width].iter_mut()
+    }
+
+    pub fn column(&self, x: usize) -> impl DoubleEndedIterator<Item = &T> {
+        assert!(x < self.width());
+
+        self.data.iter().skip(x).step_by(self.width)
+    }
+
+    pub fn column_mut(&mut self, x: usize) -> impl DoubleEndedIterator<Item = &mut T> {
+        assert!(x < self.width());
+
+        self.data.iter_mut().skip(x).step_by(self.width)
+    }
+
+    pub fn rows(&self) -> impl DoubleEndedIterator<Item = &[T]> {
+        self.data.chunks(self.width.max(1))
+    }
+
+    /// Appends a row at the bottom, which must be exactly as wide as the grid.
+    pub fn push_row(&mut self, row: impl IntoIterator<Item = T>) {
+        let len = self.data.len();
+
+        self.data.extend(row);
+
+        assert_eq!(self.data.len() - len, self.width);
+    }
+
+    /// Moves `pos` by `delta`, or returns `None` when that leaves the grid.
+    pub fn checked_add(&self, pos: Pos, delta: (isize, isize)) -> Option<Pos> {
+        let next = (
+            pos.0.checked_add_signed(delta.0)?,
+            pos.1.checked_add_signed(delta.1)?,
+        );
+
+        self.contains(next).then_some(next)
+    }
+
+    /// Moves `pos` by `delta`, coming back in on the opposite side when leaving the grid. An empty
+    /// grid has no side to come back in on, so `pos` stays where it is.
+    pub fn wrapping_add(&self, pos: Pos, delta: (isize, isize)) -> Pos {
+        let width = self.width() as isize;
+        let height = self.height() as isize;
+
+        if width == 0 || height == 0 {
+            return pos;
+        }
+
+        (
+            (pos.0 as isize + delta.0).rem_euclid(width) as usize,
+            (pos.1 as isize + delta.1).rem_euclid(height) as usize,
+        )
+    }
+
+    /// The up to four orthogonally adjacent positions that are inside the grid.
+    pub fn neighbours4(&self, pos: Pos) -> impl Iterator<Item = Pos> + '_ {
+        DELTAS_4
+            .iter()
+            .filter_map(move |&delta| self.checked_add(pos, delta))
+    }
+
+    /// The up to eight orthogonally and diagonally adjacent positions that are inside the grid.
+    pub fn neighbours8(&self, pos: Pos) -> impl Iterator<Item = Pos> + '_ {
+        DELTAS_8
+            .iter()
+            .filter_map(move |&delta| self.checked_add(pos, delta))
+    }
+
+    pub fn map<U>(&self, f: impl FnMut(&T) -> U) -> Grid<U> {
+        Grid {
+            data: self.data.iter().map(f).collect(),
+            width: self.width,
+        }
+    }
+
+    /// Renders the grid one character per cell, e.g. `format!("{}", grid.render(|c| ...))`.
+    pub fn render<F: Fn(&T) -> char>(&self, cell: F) -> Render<'_, T, F> {
+        Render { grid: self, cell }
+    }
+}
+
+impl<T> Index<Pos> for Grid<T> {
+    type Output = T;
+
+    fn index(&self, pos: Pos) -> &Self::Output {
+        assert!(self.contains(pos), "{pos:?} is outside of the grid");
+
+        &self.data[pos.1 * self.width + pos.0]
+    }
+}
+
+impl<T> IndexMut<Pos> for Grid<T> {
+    fn index_mut(&mut self, pos: Pos) -> &mut Self::Output {
+        assert!(self.contains(pos), "{pos:?} is outside of the grid");
+
+        &mut self.data[pos.1 * self.width + pos.0]
+    }
+}
+
+/// Serializes as an array of rows, top to bottom.
+impl<T: Serialize> Serialize for Grid<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.rows())
+    }
+}
+
+impl<T: Display> Display for Grid<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for row in self.rows() {
+            for cell in row {
+                write!(f, "{cell}")?;
+            }
+
+            writeln!(f)?;
+        }
+
+        Ok(())
+    }
+}
+
+pub struct Render<'a, T, F> {
+    grid: &'a Grid<T>,
+    cell: F,
+}
+
+impl<T, F: Fn(&T) -> char> Display for Render<'_, T, F> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for row in self.grid.rows() {
+            for cell in row {
+                write!(f, "{}", (self.cell)(cell))?;
+            }
+
+            writeln!(f)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Grid;
+
+    fn grid() -> Grid<u8> {
+        Grid::parse("123\n456", "a digit", |c| c.to_digit(10).map(|d| d as u8)).unwrap()
+    }
+
+    #[test]
+    fn parse() {
+        let grid = grid();
+
+        assert_eq!((grid.width(), grid.height()), (3, 2));
+        assert_eq!(grid[(2, 1)], 6);
+        assert!(Grid::parse("12\n456", "a digit", |c| c.to_digit(10)).is_err());
+        assert!(Grid::parse("12\n4x", "a digit", |c| c.to_digit(10)).is_err());
+    }
+
+    #[test]
+    fn parse_padded() {
+        let grid = Grid::parse_padded(" #\n###\n", "a tile", '?', Some).unwrap();
+
+        assert_eq!(grid.render(|&c| c).to_string(), " #?\n###\n");
+    }
+
+    #[test]
+    fn neighbours() {
+        let grid = grid();
+
+        assert_eq!(
+            grid.neighbours4((0, 0)).collect::<Vec<_>>(),
+            [(0, 1), (1, 0)]
+        );
+        assert_eq!(grid.neighbours8((1, 0)).count(), 5);
+        assert_eq!(grid.neighbours8((1, 1)).count(), 5);
+    }
+
+    #[test]
+    fn coordinate_arithmetic() {
+        let grid = grid();
+
+        assert_eq!(grid.checked_add((0, 0), (-1, 0)), None);
+        assert_eq!(grid.checked_add((0, 0), (2, 1)), Some((2, 1)));
+        assert_eq!(grid.checked_add((2, 1), (1, 0)), None);
+        assert_eq!(grid.wrapping_add((0, 0), (-1, 0)), (2, 0));
+        assert_eq!(grid.wrapping_add((2, 1), (1, 1)), (0, 0));
+
+        for (width, height) in [(0, 0), (3, 0), (0, 2)] {
+            let empty = Grid::new(width, height, 0);
+
+            assert_eq!(empty.wrapping_add((1, 1), (-1, 2)), (1, 1));
+        }
+    }
+
+    #[test]
+    fn rows_and_columns() {
+        let grid = grid();
+
+        assert_eq!(grid.row(1).copied().collect::<Vec<_>>(), [4, 5, 6]);
+        assert_eq!(grid.column(2).rev().copied().collect::<Vec<_>>(), [6, 3]);
+        assert_eq!(grid.to_string(), "123\n456\n");
+    }
+}
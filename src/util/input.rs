@@ -0,0 +1,81 @@
+use std::borrow::Cow;
+
+const BOM: char = '\u{feff}';
+
+/// Brings an input into the shape every parser expects: without a byte order mark, with `\n` line
+/// endings, without spaces or tabs at the end of a line or blank lines at the end, and ending in a
+/// single newline unless it is empty. Inputs that are already clean are borrowed as they are.
+///
+/// Only trailing whitespace is removed. Spaces and tabs inside a line are kept as they are, as the
+/// leading spaces of the day 22 map are part of it, and a tab is left for the parser to report.
+pub fn normalize(input: &str) -> Cow<'_, str> {
+    let input = input.strip_prefix(BOM).unwrap_or(input);
+
+    if is_normalized(input) {
+        return Cow::Borrowed(input);
+    }
+
+    let mut normalized = String::with_capacity(input.len() + 1);
+
+    for line in input.lines() {
+        normalized.push_str(line.trim_end());
+        normalized.push('\n');
+    }
+
+    normalized.truncate(normalized.trim_end().len());
+
+    if !normalized.is_empty() {
+        normalized.push('\n');
+    }
+
+    Cow::Owned(normalized)
+}
+
+fn is_normalized(input: &str) -> bool {
+    let mut lines = input.split_terminator('\n');
+
+    input.is_empty()
+        || (input.ends_with('\n')
+            && lines
+                .clone()
+                .all(|line| !line.ends_with(char::is_whitespace))
+            && lines.next_back().is_some_and(|line| !line.is_empty()))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+
+    use super::normalize;
+
+    #[test]
+    fn clean() {
+        assert!(matches!(normalize("a\n\nb\n"), Cow::Borrowed("a\n\nb\n")));
+        assert!(matches!(normalize(""), Cow::Borrowed("")));
+    }
+
+    #[test]
+    fn line_endings() {
+        assert_eq!(normalize("a\r\nb\r\n"), "a\nb\n");
+        assert_eq!(normalize("a\r\n\r\nb"), "a\n\nb\n");
+    }
+
+    #[test]
+    fn byte_order_mark() {
+        assert!(matches!(normalize("\u{feff}a\n"), Cow::Borrowed("a\n")));
+        assert_eq!(normalize("\u{feff}a\r\n"), "a\n");
+    }
+
+    #[test]
+    fn trailing_whitespace() {
+        assert_eq!(normalize("  a \t\n b\t\n"), "  a\n b\n");
+        assert_eq!(normalize("a\n\n \n\t\n"), "a\n");
+        assert_eq!(normalize(" \r\n\n"), "");
+    }
+
+    #[test]
+    fn inner_whitespace() {
+        assert!(matches!(normalize("\ta\tb\n"), Cow::Borrowed("\ta\tb\n")));
+        assert_eq!(normalize("  a\t b\t\r\n"), "  a\t b\n");
+    }
+}
@@ -0,0 +1,333 @@
+use std::{fmt::Debug, ops::RangeInclusive};
+
+/// Integers that an [`IntervalSet`] can hold.
+pub trait Integer: Copy + Ord + Debug {
+    /// The next larger value, or `None` at the largest one.
+    fn succ(self) -> Option<Self>;
+
+    /// The next smaller value, or `None` at the smallest one.
+    fn pred(self) -> Option<Self>;
+
+    /// How many values `start..=end` holds, which must not be empty.
+    fn count(start: Self, end: Self) -> u64;
+}
+
+macro_rules! impl_integer {
+    ($($t:ty),*) => {
+        $(
+            impl Integer for $t {
+                fn succ(self) -> Option<Self> {
+                    self.checked_add(1)
+                }
+
+                fn pred(self) -> Option<Self> {
+                    self.checked_sub(1)
+                }
+
+                fn count(start: Self, end: Self) -> u64 {
+                    (end as i128 - start as i128) as u64 + 1
+                }
+            }
+        )*
+    };
+}
+
+impl_integer!(i16, i32, i64, u16, u32, u64, usize);
+
+/// A set of integers stored as the ranges they make up, which is cheap for long runs of values.
+///
+/// The ranges are kept sorted and merged, so that no two of them overlap or touch.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IntervalSet<T> {
+    ranges: Vec<(T, T)>,
+}
+
+impl<T> Default for IntervalSet<T> {
+    fn default() -> Self {
+        IntervalSet { ranges: Vec::new() }
+    }
+}
+
+/// Whether all of `..=end` comes before `start` with at least one value in between.
+fn apart<T: Integer>(end: T, start: T) -> bool {
+    end.succ().is_some_and(|next| next < start)
+}
+
+impl<T: Integer> IntervalSet<T> {
+    pub fn new() -> Self {
+        IntervalSet::default()
+    }
+
+    /// Adds every value in `range`, merging it with the ranges it overlaps or touches.
+    pub fn insert(&mut self, range: RangeInclusive<T>) {
+        let (start, end) = range.into_inner();
+
+        if start > end {
+            return;
+        }
+
+        let first = self.ranges.partition_point(|&(_, e)| apart(e, start));
+        let last = self.ranges.partition_point(|&(s, _)| !apart(end, s));
+
+        let merged = if first < last {
+            (
+                self.ranges[first].0.min(start),
+                self.ranges[last - 1].1.max(end),
+            )
+        } else {
+            (start, end)
+        };
+
+        self.ranges.splice(first..last, [merged]);
+    }
+
+    /// Takes every value in `range` out, splitting the ranges it falls into.
+    pub fn remove(&mut self, range: RangeInclusive<T>) {
+        let (start, end) = range.into_inner();
+
+        if start > end {
+            return;
+        }
+
+        let first = self.ranges.partition_point(|&(_, e)| e < start);
+        let last = self.ranges.partition_point(|&(s, _)| s <= end);
+
+        if first == last {
+            return;
+        }
+
+        let (s, _) = self.ranges[first];
+        let (_, e) = self.ranges[last - 1];
+
+        // Both ends are within the set, so there are values before and after them.
+        let left = (s < start).then(|| (s, start.pred().unwrap()));
+        let right = (e > end).then(|| (end.succ().unwrap(), e));
+
+        self.ranges
+            .splice(first..last, left.into_iter().chain(right));
+    }
+
+    pub fn union(&self, other: &IntervalSet<T>) -> IntervalSet<T> {
+        let mut union = self.clone();
+
+        for range in other.iter() {
+            union.insert(range);
+        }
+
+        union
+    }
+
+    pub fn intersection(&self, other: &IntervalSet<T>) -> IntervalSet<T> {
+        let mut ranges = Vec::new();
+        let (mut lhs, mut rhs) = (
+            self.ranges.iter().peekable(),
+            other.ranges.iter().peekable(),
+        );
+
+        while let (Some(&&(s1, e1)), Some(&&(s2, e2))) = (lhs.peek(), rhs.peek()) {
+            let (start, end) = (s1.max(s2), e1.min(e2));
+
+            if start <= end {
+                ranges.push((start, end));
+            }
+
+            if e1 < e2 {
+                lhs.next();
+            } else {
+                rhs.next();
+            }
+        }
+
+        IntervalSet { ranges }
+    }
+
+    /// The values of this set that are not in `other`.
+    pub fn difference(&self, other: &IntervalSet<T>) -> IntervalSet<T> {
+        let mut difference = self.clone();
+
+        for range in other.iter() {
+            difference.remove(range);
+        }
+
+        difference
+    }
+
+    pub fn contains(&self, value: T) -> bool {
+        let idx = self.ranges.partition_point(|&(_, e)| e < value);
+
+        self.ranges.get(idx).is_some_and(|&(s, _)| s <= value)
+    }
+
+    /// Whether every value of `range` is in the set, which is true for an empty range.
+    pub fn contains_range(&self, range: RangeInclusive<T>) -> bool {
+        let (start, end) = range.into_inner();
+        let idx = self.ranges.partition_point(|&(_, e)| e < start);
+
+        start > end
+            || self
+                .ranges
+                .get(idx)
+                .is_some_and(|&(s, e)| s <= start && end <= e)
+    }
+
+    /// Whether every value of this set is in `other` as well.
+    pub fn is_subset(&self, other: &IntervalSet<T>) -> bool {
+        self.iter().all(|range| other.contains_range(range))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ranges.is_empty()
+    }
+
+    /// How many values the set holds.
+    pub fn len(&self) -> u64 {
+        self.ranges.iter().map(|&(s, e)| T::count(s, e)).sum()
+    }
+
+    /// The ranges of the set, in order.
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = RangeInclusive<T>> + '_ {
+        self.ranges.iter().map(|&(s, e)| s..=e)
+    }
+
+    /// The ranges of values within `bounds` that are not in the set, in order.
+    pub fn gaps(&self, bounds: RangeInclusive<T>) -> impl Iterator<Item = RangeInclusive<T>> + '_ {
+        let (start, end) = bounds.into_inner();
+        let first = self.ranges.partition_point(|&(_, e)| e < start);
+        let mut ranges = self.ranges[first..].iter();
+        // The first value that is neither covered nor part of a gap that was already returned.
+        let mut next = (start <= end).then_some(start);
+
+        std::iter::from_fn(move || loop {
+            let from = next?;
+
+            match ranges.next() {
+                Some(&(s, e)) if s <= end => {
+                    next = e.succ().filter(|_| e < end);
+
+                    if s > from {
+                        // `s` is larger than another value, so it has a predecessor.
+                        return Some(from..=s.pred().unwrap());
+                    }
+                }
+                _ => {
+                    next = None;
+
+                    return Some(from..=end);
+                }
+            }
+        })
+    }
+}
+
+impl<T: Integer> From<RangeInclusive<T>> for IntervalSet<T> {
+    fn from(range: RangeInclusive<T>) -> Self {
+        let mut set = IntervalSet::new();
+        set.insert(range);
+        set
+    }
+}
+
+impl<T: Integer> FromIterator<RangeInclusive<T>> for IntervalSet<T> {
+    fn from_iter<I: IntoIterator<Item = RangeInclusive<T>>>(iter: I) -> Self {
+        let mut set = IntervalSet::new();
+        set.extend(iter);
+        set
+    }
+}
+
+impl<T: Integer> Extend<RangeInclusive<T>> for IntervalSet<T> {
+    fn extend<I: IntoIterator<Item = RangeInclusive<T>>>(&mut self, iter: I) {
+        for range in iter {
+            self.insert(range);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use proptest::prelude::*;
+
+    use super::IntervalSet;
+
+    fn set(ranges: &[(i32, i32)]) -> IntervalSet<i32> {
+        ranges.iter().map(|&(s, e)| s..=e).collect()
+    }
+
+    fn values(set: &IntervalSet<i32>) -> BTreeSet<i32> {
+        set.iter().flatten().collect()
+    }
+
+    #[test]
+    fn insert() {
+        let set = set(&[(5, 7), (1, 2), (3, 4), (10, 12), (11, 15), (20, 19)]);
+
+        assert_eq!(set.iter().collect::<Vec<_>>(), [1..=7, 10..=15]);
+        assert_eq!(set.len(), 13);
+        assert!(set.contains_range(4..=6) && !set.contains_range(6..=10));
+        assert!(set.contains(3) && set.contains(15) && !set.contains(8));
+    }
+
+    #[test]
+    fn remove() {
+        let mut set = set(&[(0, 10), (20, 30)]);
+
+        set.remove(5..=22);
+        set.remove(30..=40);
+
+        assert_eq!(set.iter().collect::<Vec<_>>(), [0..=4, 23..=29]);
+    }
+
+    #[test]
+    fn gaps() {
+        let set = set(&[(2, 3), (6, 8)]);
+
+        assert_eq!(set.gaps(0..=10).collect::<Vec<_>>(), [0..=1, 4..=5, 9..=10]);
+        assert_eq!(set.gaps(3..=7).collect::<Vec<_>>(), [4..=5]);
+        assert_eq!(set.gaps(6..=8).count(), 0);
+        assert_eq!(IntervalSet::new().gaps(1..=2).collect::<Vec<_>>(), [1..=2]);
+    }
+
+    #[test]
+    fn extremes() {
+        let mut set = IntervalSet::from(i32::MIN..=i32::MAX);
+
+        assert_eq!(set.len(), 1 << 32);
+
+        set.remove(i32::MIN..=0);
+
+        assert_eq!(set.iter().collect::<Vec<_>>(), [1..=i32::MAX]);
+        assert_eq!(
+            set.gaps(i32::MIN..=i32::MAX).collect::<Vec<_>>(),
+            [i32::MIN..=0]
+        );
+    }
+
+    fn ranges() -> impl Strategy<Value = Vec<(i32, i32)>> {
+        prop::collection::vec((-20..20, 0..6).prop_map(|(s, len)| (s, s + len)), 0..6)
+    }
+
+    proptest! {
+        /// Every operation agrees with the same operation on sets of single values.
+        #[test]
+        fn matches_btree_set(lhs in ranges(), rhs in ranges()) {
+            let (lhs, rhs) = (set(&lhs), set(&rhs));
+            let (a, b) = (values(&lhs), values(&rhs));
+
+            prop_assert_eq!(values(&lhs.union(&rhs)), &a | &b);
+            prop_assert_eq!(values(&lhs.intersection(&rhs)), &a & &b);
+            prop_assert_eq!(values(&lhs.difference(&rhs)), &a - &b);
+            prop_assert_eq!(lhs.len(), a.len() as u64);
+            prop_assert_eq!(lhs.is_subset(&rhs), a.is_subset(&b));
+            prop_assert_eq!(
+                lhs.gaps(-25..=25).flatten().collect::<BTreeSet<_>>(),
+                (-25..=25).filter(|value| !a.contains(value)).collect()
+            );
+
+            for window in lhs.iter().collect::<Vec<_>>().windows(2) {
+                prop_assert!(window[0].end() + 1 < *window[1].start());
+            }
+        }
+    }
+}
@@ -1 +1,9 @@
+pub mod bits;
+pub mod cycle;
+pub mod geom;
+pub mod grid;
+pub mod input;
+pub mod interval_set;
+pub mod parse;
 pub mod priority_queue;
+pub mod search;
@@ -0,0 +1,333 @@
+use std::str::FromStr;
+
+use crate::{
+    util::{geom::Point2, grid::Grid},
+    Error,
+};
+
+/// What the parsers return. Failures are [`Error`]s pointing into the input, so that they can be
+/// located once they make it back to the whole puzzle input.
+pub type IResult<'a, T> = nom::IResult<&'a str, T, Error>;
+
+/// Fails at `found`, which must be a slice of the input.
+pub fn fail<T>(found: &str, expected: impl Into<String>) -> IResult<'_, T> {
+    Err(nom::Err::Error(Error::parse(found, expected)))
+}
+
+/// Runs `parser` on the whole of `input`, of which only line endings may be left over.
+pub fn all<'a, T>(
+    input: &'a str,
+    mut parser: impl FnMut(&'a str) -> IResult<'a, T>,
+) -> Result<T, Error> {
+    let (rest, value) = parser(input)?;
+
+    if rest.trim_start_matches('\n').is_empty() {
+        Ok(value)
+    } else {
+        Err(Error::parse(
+            rest.trim_start_matches('\n'),
+            "the end of the input",
+        ))
+    }
+}
+
+/// Replaces the error of `parser` with one for `expected`, for alternatives that are better
+/// described together than by the last one that was tried.
+pub fn expect<'a, T>(
+    expected: &'static str,
+    mut parser: impl FnMut(&'a str) -> IResult<'a, T>,
+) -> impl FnMut(&'a str) -> IResult<'a, T> {
+    move |input| parser(input).map_err(|err| err.map(|_| Error::parse(input, expected)))
+}
+
+/// Exactly `text`.
+pub fn tag<'a>(text: &'static str) -> impl FnMut(&'a str) -> IResult<'a, &'a str> {
+    move |input: &'a str| match input.strip_prefix(text) {
+        Some(rest) => Ok((rest, &input[..text.len()])),
+        None => fail(input, format!("{text:?}")),
+    }
+}
+
+/// A single character that `cell` turns into a value, described by `expected` otherwise.
+pub fn char_of<'a, T>(
+    expected: &'static str,
+    mut cell: impl FnMut(char) -> Option<T>,
+) -> impl FnMut(&'a str) -> IResult<'a, T> {
+    move |input: &'a str| {
+        let mut chars = input.chars();
+
+        match chars.next().and_then(&mut cell) {
+            Some(value) => Ok((chars.as_str(), value)),
+            None => fail(
+                &input[..input.chars().next().map_or(0, char::len_utf8)],
+                expected,
+            ),
+        }
+    }
+}
+
+/// Everything up to the next space or line ending, which must not be empty.
+pub fn word(input: &str) -> IResult<'_, &str> {
+    let len = input.find(char::is_whitespace).unwrap_or(input.len());
+
+    if len == 0 {
+        return fail(input, "a word");
+    }
+
+    Ok((&input[len..], &input[..len]))
+}
+
+/// Everything up to the line ending, which may be nothing.
+pub fn line(input: &str) -> IResult<'_, &str> {
+    let len = input.find('\n').unwrap_or(input.len());
+
+    Ok((&input[len..], &input[..len]))
+}
+
+/// A decimal number with an optional sign, which has to fit into `T`.
+pub fn number<T: FromStr>(input: &str) -> IResult<'_, T> {
+    let sign = input.starts_with(['-', '+']) as usize;
+    let digits = input[sign..]
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(input.len() - sign);
+
+    if digits == 0 {
+        // Whatever stands in place of the number, or the next character if that is punctuation.
+        let len = input[sign..]
+            .find(|c: char| !c.is_alphanumeric())
+            .map_or(input.len(), |len| sign + len)
+            .max(input.chars().next().map_or(0, char::len_utf8));
+
+        return fail(&input[..len], "a number");
+    }
+
+    let (number, rest) = input.split_at(sign + digits);
+
+    match number.parse() {
+        Ok(value) => Ok((rest, value)),
+        Err(_) => fail(number, "a number"),
+    }
+}
+
+/// Coordinates written as `x=1, y=-2`.
+pub fn coordinates(input: &str) -> IResult<'_, Point2> {
+    let (input, _) = tag("x=")(input)?;
+    let (input, x) = number(input)?;
+    let (input, _) = tag(", y=")(input)?;
+    let (input, y) = number(input)?;
+
+    Ok((input, Point2::new(x, y)))
+}
+
+/// One or more items separated by commas, each of which may be followed by spaces.
+pub fn list<'a, T>(
+    mut item: impl FnMut(&'a str) -> IResult<'a, T>,
+) -> impl FnMut(&'a str) -> IResult<'a, Vec<T>> {
+    move |mut input| {
+        let mut items = Vec::new();
+
+        loop {
+            let (rest, value) = item(input)?;
+            items.push(value);
+
+            match rest.strip_prefix(',') {
+                Some(next) => input = next.trim_start_matches(' '),
+                None => return Ok((rest, items)),
+            }
+        }
+    }
+}
+
+/// Runs `item` over and over up to the end of the line, for lines made up of items without
+/// separators. `item` has to consume something every time it succeeds.
+pub fn line_of<'a, T>(
+    mut item: impl FnMut(&'a str) -> IResult<'a, T>,
+) -> impl FnMut(&'a str) -> IResult<'a, Vec<T>> {
+    move |mut input| {
+        let mut items = Vec::new();
+
+        while !input.is_empty() && !input.starts_with('\n') {
+            let (rest, value) = item(input)?;
+            items.push(value);
+            input = rest;
+        }
+
+        Ok((input, items))
+    }
+}
+
+/// Runs `item` on one line after the other, each of which it has to consume up to the line ending.
+/// Stops at the end of the input or at a blank line, which is left to the caller. Empty input has
+/// no lines at all, so that the days can tell what is missing themselves.
+pub fn lines<'a, T>(
+    mut item: impl FnMut(&'a str) -> IResult<'a, T>,
+) -> impl FnMut(&'a str) -> IResult<'a, Vec<T>> {
+    move |mut input| {
+        let mut items = Vec::new();
+
+        if input.is_empty() {
+            return Ok((input, items));
+        }
+
+        loop {
+            let (rest, value) = item(input)?;
+            items.push(value);
+
+            if !rest.is_empty() && !rest.starts_with('\n') {
+                return fail(rest, "the end of the line");
+            }
+
+            match rest.strip_prefix('\n') {
+                Some(next) if !next.is_empty() && !next.starts_with('\n') => input = next,
+                _ => return Ok((rest, items)),
+            }
+        }
+    }
+}
+
+/// Runs `block` on every group of lines, where the groups are separated by blank lines.
+pub fn blocks<'a, T>(
+    mut block: impl FnMut(&'a str) -> IResult<'a, T>,
+) -> impl FnMut(&'a str) -> IResult<'a, Vec<T>> {
+    move |mut input| {
+        let mut blocks = Vec::new();
+
+        loop {
+            let (rest, value) = block(input)?;
+            blocks.push(value);
+
+            match rest.strip_prefix("\n\n") {
+                Some(next) => input = next,
+                None if rest.trim_start_matches('\n').is_empty() => return Ok((rest, blocks)),
+                None => return fail(rest, "a blank line"),
+            }
+        }
+    }
+}
+
+/// The lines up to the next blank line, without the final line ending.
+pub fn paragraph(input: &str) -> IResult<'_, &str> {
+    let len = input
+        .find("\n\n")
+        .unwrap_or(input.trim_end_matches('\n').len());
+
+    Ok((&input[len..], &input[..len]))
+}
+
+/// A character map up to the next blank line, where every line must be equally long, see
+/// [`Grid::parse`].
+pub fn grid<'a, T>(
+    expected: &'static str,
+    mut cell: impl FnMut(char) -> Option<T>,
+) -> impl FnMut(&'a str) -> IResult<'a, Grid<T>> {
+    move |input| {
+        let (rest, map) = paragraph(input)?;
+
+        Grid::parse(map, expected, &mut cell)
+            .map(|grid| (rest, grid))
+            .map_err(nom::Err::Error)
+    }
+}
+
+/// A character map up to the next blank line, where short lines are padded with `fill`, see
+/// [`Grid::parse_padded`].
+pub fn grid_padded<'a, T: Clone>(
+    expected: &'static str,
+    fill: T,
+    mut cell: impl FnMut(char) -> Option<T>,
+) -> impl FnMut(&'a str) -> IResult<'a, Grid<T>> {
+    move |input| {
+        let (rest, map) = paragraph(input)?;
+
+        Grid::parse_padded(map, expected, fill.clone(), &mut cell)
+            .map(|grid| (rest, grid))
+            .map_err(nom::Err::Error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{all, blocks, coordinates, grid, lines, list, number, tag};
+    use crate::{error::ParseError, util::geom::Point2, Error};
+
+    /// Where the error is, and what was expected there.
+    fn error<T: std::fmt::Debug>(input: &str, result: Result<T, Error>) -> (usize, usize, String) {
+        let Error::Parse(ParseError {
+            line,
+            column,
+            expected,
+            ..
+        }) = result.unwrap_err().locate(0, input)
+        else {
+            panic!("Expected a parse error");
+        };
+
+        (line, column, expected)
+    }
+
+    #[test]
+    fn numbers() {
+        assert_eq!(all("-12", number::<i32>), Ok(-12));
+        assert_eq!(all("+7\n", number::<u8>), Ok(7));
+        assert_eq!(error("300", all("300", number::<u8>)).2, "a number");
+        assert_eq!(error("-1", all("-1", number::<u8>)).2, "a number");
+        assert_eq!(error("x", all("x", number::<u8>)).2, "a number");
+        assert_eq!(all("", lines(number::<u8>)), Ok(vec![]));
+
+        let Err(Error::Parse(err)) = all("-x,1", number::<i8>) else {
+            panic!("Expected a parse error");
+        };
+
+        assert_eq!(err.found, "-x");
+    }
+
+    #[test]
+    fn coordinates_and_lists() {
+        assert_eq!(all("x=1, y=-2", coordinates), Ok(Point2::new(1, -2)));
+        assert_eq!(all("1, 2,3", list(number::<u8>)), Ok(vec![1, 2, 3]));
+
+        let input = "x=1, y=-2 z";
+
+        assert_eq!(
+            error(input, all(input, coordinates)),
+            (1, 10, "the end of the input".to_string())
+        );
+    }
+
+    #[test]
+    fn line_numbers() {
+        let input = "1\n2\n\n3\nx\n";
+        let result = all(input, blocks(lines(number::<u8>)));
+
+        assert_eq!(error(input, result), (5, 1, "a number".to_string()));
+
+        let input = "1 -> 2\n3 => 4";
+        let mut pair = |input| {
+            let (input, a) = number::<u8>(input)?;
+            let (input, _) = tag(" -> ")(input)?;
+            let (input, b) = number::<u8>(input)?;
+
+            Ok((input, (a, b)))
+        };
+
+        assert_eq!(
+            error(input, all(input, lines(&mut pair))),
+            (2, 2, "\" -> \"".to_string())
+        );
+    }
+
+    #[test]
+    fn grids() {
+        let input = "12\n34\n\n56\n7x\n";
+        let digit = |c: char| c.to_digit(10);
+
+        let grids = all("12\n34\n\n56\n78\n", blocks(grid("a digit", digit))).unwrap();
+
+        assert_eq!(grids.len(), 2);
+        assert_eq!(grids[1][(1, 1)], 8);
+        assert_eq!(
+            error(input, all(input, blocks(grid("a digit", digit)))),
+            (5, 2, "a digit".to_string())
+        );
+    }
+}
@@ -1,59 +1,234 @@
-use std::{collections::BinaryHeap, marker::PhantomData};
+use std::{collections::HashMap, hash::Hash, marker::PhantomData};
 
-pub trait PriorityQueueItem<C> {
-    fn cost(&self, context: &C) -> usize;
+/// Decides which end of a [`PriorityQueue`] comes out first.
+pub trait Order {
+    fn precedes<C: Ord>(a: &C, b: &C) -> bool;
 }
 
-struct PriorityQueueStorage<C, T: PriorityQueueItem<C>> {
-    cost: usize,
-    data: T,
-    marker: PhantomData<C>,
+/// Lowest cost first.
+pub struct Min;
+
+/// Highest cost first.
+pub struct Max;
+
+impl Order for Min {
+    fn precedes<C: Ord>(a: &C, b: &C) -> bool {
+        a < b
+    }
 }
 
-impl<C, T: PriorityQueueItem<C>> Ord for PriorityQueueStorage<C, T> {
-    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-        other.cost.cmp(&self.cost)
+impl Order for Max {
+    fn precedes<C: Ord>(a: &C, b: &C) -> bool {
+        a > b
     }
 }
 
-impl<C, T: PriorityQueueItem<C>> PartialOrd for PriorityQueueStorage<C, T> {
-    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        other.cost.partial_cmp(&self.cost)
+/// A binary heap where every item is addressed by a key, so that its cost can be changed while it
+/// is queued. Pushing a key that is already queued replaces its cost instead of adding it twice.
+pub struct PriorityQueue<K, C, O = Min> {
+    heap: Vec<(K, C)>,
+    positions: HashMap<K, usize>,
+    order: PhantomData<O>,
+}
+
+impl<K: Clone + Eq + Hash, C: Ord, O: Order> PriorityQueue<K, C, O> {
+    pub fn new() -> Self {
+        PriorityQueue {
+            heap: Vec::new(),
+            positions: HashMap::new(),
+            order: PhantomData,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.heap.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.heap.is_empty()
+    }
+
+    pub fn contains(&self, key: &K) -> bool {
+        self.positions.contains_key(key)
+    }
+
+    /// The cost `key` is queued with.
+    pub fn get(&self, key: &K) -> Option<&C> {
+        self.positions.get(key).map(|&idx| &self.heap[idx].1)
+    }
+
+    /// The item that [`pop`](Self::pop) would return next.
+    pub fn peek(&self) -> Option<(&K, &C)> {
+        self.heap.first().map(|(key, cost)| (key, cost))
+    }
+
+    /// Queues `key`, or changes its cost if it is already queued and returns the previous cost.
+    pub fn push(&mut self, key: K, cost: C) -> Option<C> {
+        if self.contains(&key) {
+            return self.update(&key, cost);
+        }
+
+        self.positions.insert(key.clone(), self.heap.len());
+        self.heap.push((key, cost));
+        self.sift_up(self.heap.len() - 1);
+
+        None
+    }
+
+    /// Moves a queued `key` closer to the front if `cost` comes before its current cost.
+    ///
+    /// Returns whether the cost was changed.
+    pub fn decrease_key(&mut self, key: &K, cost: C) -> bool {
+        let Some(&idx) = self.positions.get(key) else {
+            return false;
+        };
+
+        if !O::precedes(&cost, &self.heap[idx].1) {
+            return false;
+        }
+
+        self.heap[idx].1 = cost;
+        self.sift_up(idx);
+
+        true
+    }
+
+    /// Changes the cost of a queued `key` in either direction and returns the previous cost.
+    pub fn update(&mut self, key: &K, cost: C) -> Option<C> {
+        let &idx = self.positions.get(key)?;
+        let previous = std::mem::replace(&mut self.heap[idx].1, cost);
+
+        if O::precedes(&self.heap[idx].1, &previous) {
+            self.sift_up(idx);
+        } else {
+            self.sift_down(idx);
+        }
+
+        Some(previous)
+    }
+
+    pub fn pop(&mut self) -> Option<(K, C)> {
+        if self.heap.is_empty() {
+            return None;
+        }
+
+        let last = self.heap.len() - 1;
+
+        self.swap(0, last);
+
+        let (key, cost) = self.heap.pop().unwrap();
+
+        self.positions.remove(&key);
+        self.sift_down(0);
+
+        Some((key, cost))
+    }
+
+    fn swap(&mut self, a: usize, b: usize) {
+        self.heap.swap(a, b);
+        *self.positions.get_mut(&self.heap[a].0).unwrap() = a;
+        *self.positions.get_mut(&self.heap[b].0).unwrap() = b;
+    }
+
+    fn sift_up(&mut self, mut idx: usize) {
+        while idx > 0 {
+            let parent = (idx - 1) / 2;
+
+            if !O::precedes(&self.heap[idx].1, &self.heap[parent].1) {
+                break;
+            }
+
+            self.swap(idx, parent);
+            idx = parent;
+        }
+    }
+
+    fn sift_down(&mut self, mut idx: usize) {
+        loop {
+            let mut first = idx;
+
+            for child in [2 * idx + 1, 2 * idx + 2] {
+                if child < self.heap.len() && O::precedes(&self.heap[child].1, &self.heap[first].1)
+                {
+                    first = child;
+                }
+            }
+
+            if first == idx {
+                break;
+            }
+
+            self.swap(idx, first);
+            idx = first;
+        }
     }
 }
 
-impl<C, T: PriorityQueueItem<C>> PartialEq for PriorityQueueStorage<C, T> {
-    fn eq(&self, other: &Self) -> bool {
-        self.cost.eq(&other.cost)
+impl<K: Clone + Eq + Hash, C: Ord, O: Order> Default for PriorityQueue<K, C, O> {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
-impl<C, T: PriorityQueueItem<C>> Eq for PriorityQueueStorage<C, T> {}
+impl<K: Clone + Eq + Hash, C: Ord, O: Order> Extend<(K, C)> for PriorityQueue<K, C, O> {
+    fn extend<I: IntoIterator<Item = (K, C)>>(&mut self, iter: I) {
+        for (key, cost) in iter {
+            self.push(key, cost);
+        }
+    }
+}
 
-pub struct PriorityQueue<C, T: PriorityQueueItem<C>> {
-    context: C,
-    heap: BinaryHeap<PriorityQueueStorage<C, T>>,
+impl<K: Clone + Eq + Hash, C: Ord, O: Order> FromIterator<(K, C)> for PriorityQueue<K, C, O> {
+    fn from_iter<I: IntoIterator<Item = (K, C)>>(iter: I) -> Self {
+        let mut queue = Self::new();
+        queue.extend(iter);
+        queue
+    }
 }
 
-impl<C, T: PriorityQueueItem<C>> PriorityQueue<C, T> {
-    pub fn new(context: C) -> Self {
-        PriorityQueue {
-            context,
-            heap: BinaryHeap::new(),
-        }
+#[cfg(test)]
+mod tests {
+    use super::{Max, PriorityQueue};
+
+    fn drain<O: super::Order>(mut queue: PriorityQueue<char, i32, O>) -> Vec<(char, i32)> {
+        std::iter::from_fn(|| queue.pop()).collect()
+    }
+
+    #[test]
+    fn min_and_max() {
+        let items = [('a', 3), ('b', -1), ('c', 7), ('d', 0)];
+
+        let min: PriorityQueue<_, _> = items.into_iter().collect();
+        let max: PriorityQueue<_, _, Max> = items.into_iter().collect();
+
+        assert_eq!(min.peek(), Some((&'b', &-1)));
+        assert_eq!(drain(min), [('b', -1), ('d', 0), ('a', 3), ('c', 7)]);
+        assert_eq!(drain(max), [('c', 7), ('a', 3), ('d', 0), ('b', -1)]);
     }
 
-    pub fn push(&mut self, data: T) {
-        let cost = data.cost(&self.context);
+    #[test]
+    fn change_cost() {
+        let mut queue: PriorityQueue<_, _> = [('a', 3), ('b', 5), ('c', 7)].into_iter().collect();
 
-        self.heap.push(PriorityQueueStorage {
-            cost,
-            data,
-            marker: PhantomData,
-        })
+        assert!(queue.decrease_key(&'c', 1));
+        assert!(!queue.decrease_key(&'a', 4));
+        assert!(!queue.decrease_key(&'x', 0));
+        assert_eq!(queue.update(&'c', 9), Some(1));
+        assert_eq!(queue.push('b', 2), Some(5));
+        assert_eq!(queue.get(&'b'), Some(&2));
+        assert_eq!(queue.len(), 3);
+        assert_eq!(drain(queue), [('b', 2), ('a', 3), ('c', 9)]);
     }
 
-    pub fn pop(&mut self) -> Option<(usize, T)> {
-        self.heap.pop().map(|item| (item.cost, item.data))
+    #[test]
+    fn extend() {
+        let mut queue = PriorityQueue::<_, _>::new();
+
+        assert!(queue.is_empty());
+
+        queue.extend((0..100).map(|n| (n % 10, n)));
+
+        assert_eq!(queue.len(), 10);
+        assert_eq!(queue.pop(), Some((0, 90)));
     }
 }
@@ -0,0 +1,232 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    hash::Hash,
+};
+
+use super::priority_queue::PriorityQueue;
+use crate::stats::Stats;
+
+/// The outcome of a successful search.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Path<S> {
+    /// Total cost of all steps from the start to the goal.
+    pub cost: usize,
+    /// Every state along the way, starting with the start and ending with the goal.
+    pub states: Vec<S>,
+    /// How much work the search did, where states that were reached again without a cheaper cost
+    /// count as pruned by `"seen"`.
+    pub stats: Stats,
+}
+
+fn reconstruct<S>(goal: S, parent: impl Fn(&S) -> Option<S>) -> Vec<S> {
+    let mut states = vec![goal];
+
+    while let Some(previous) = parent(states.last().unwrap()) {
+        states.push(previous);
+    }
+
+    states.reverse();
+    states
+}
+
+/// Breadth first search, where every step costs one.
+pub fn bfs<S, I>(
+    start: S,
+    mut successors: impl FnMut(&S) -> I,
+    mut is_goal: impl FnMut(&S) -> bool,
+) -> Option<Path<S>>
+where
+    S: Clone + Eq + Hash,
+    I: IntoIterator<Item = S>,
+{
+    let mut parents = HashMap::<S, Option<S>>::new();
+    let mut queue = VecDeque::new();
+    let mut stats = Stats::default();
+    let mut seen = 0;
+
+    parents.insert(start.clone(), None);
+    queue.push_back((start, 0));
+
+    while let Some((state, cost)) = queue.pop_front() {
+        if is_goal(&state) {
+            stats.visited = parents.len();
+            stats.prune("seen", seen);
+
+            return Some(Path {
+                cost,
+                stats,
+                states: reconstruct(state, |state| parents[state].clone()),
+            });
+        }
+
+        stats.expanded += 1;
+
+        for next in successors(&state) {
+            if parents.contains_key(&next) {
+                seen += 1;
+            } else {
+                parents.insert(next.clone(), Some(state.clone()));
+                queue.push_back((next, cost + 1));
+            }
+        }
+
+        stats.frontier(queue.len());
+    }
+
+    None
+}
+
+/// Lowest cost search, where `successors` yields every next state along with the cost of getting there.
+pub fn dijkstra<S, I>(
+    start: S,
+    successors: impl FnMut(&S) -> I,
+    is_goal: impl FnMut(&S) -> bool,
+) -> Option<Path<S>>
+where
+    S: Clone + Eq + Hash,
+    I: IntoIterator<Item = (S, usize)>,
+{
+    astar(start, successors, is_goal, |_| 0)
+}
+
+/// Lowest cost search guided by `heuristic`, which must never overestimate the remaining cost.
+///
+/// States are reopened when a cheaper way to them turns up, so the heuristic doesn't have to be
+/// consistent for the result to be optimal.
+pub fn astar<S, I>(
+    start: S,
+    successors: impl FnMut(&S) -> I,
+    is_goal: impl FnMut(&S) -> bool,
+    heuristic: impl FnMut(&S) -> usize,
+) -> Option<Path<S>>
+where
+    S: Clone + Eq + Hash,
+    I: IntoIterator<Item = (S, usize)>,
+{
+    astar_until(start, successors, is_goal, heuristic, |_, _| false).unwrap_or(None)
+}
+
+/// Like [`astar`], but `stop` is asked before every state is expanded, along with the cost of
+/// getting to it, whether to give up. Giving up returns the work done so far as an error.
+pub fn astar_until<S, I>(
+    start: S,
+    mut successors: impl FnMut(&S) -> I,
+    mut is_goal: impl FnMut(&S) -> bool,
+    mut heuristic: impl FnMut(&S) -> usize,
+    mut stop: impl FnMut(&S, usize) -> bool,
+) -> Result<Option<Path<S>>, Stats>
+where
+    S: Clone + Eq + Hash,
+    I: IntoIterator<Item = (S, usize)>,
+{
+    let mut best = HashMap::<S, (usize, Option<S>)>::new();
+    let mut queue = PriorityQueue::<S, usize>::new();
+    let mut stats = Stats::default();
+    let mut seen = 0;
+
+    best.insert(start.clone(), (0, None));
+    queue.push(start.clone(), heuristic(&start));
+
+    while let Some((state, _)) = queue.pop() {
+        let cost = best[&state].0;
+
+        if is_goal(&state) {
+            stats.visited = best.len();
+            stats.prune("seen", seen);
+
+            return Ok(Some(Path {
+                cost,
+                stats,
+                states: reconstruct(state, |state| best[state].1.clone()),
+            }));
+        }
+
+        if stop(&state, cost) {
+            stats.visited = best.len();
+            stats.prune("seen", seen);
+            return Err(stats);
+        }
+
+        stats.expanded += 1;
+
+        for (next, step_cost) in successors(&state) {
+            let next_cost = cost + step_cost;
+
+            if let Some(&(known_cost, _)) = best.get(&next) {
+                if known_cost <= next_cost {
+                    seen += 1;
+                    continue;
+                }
+            }
+
+            let estimate = next_cost + heuristic(&next);
+
+            // Either lowers the estimate of a state that is still queued, or reopens one.
+            best.insert(next.clone(), (next_cost, Some(state.clone())));
+            queue.push(next, estimate);
+        }
+
+        stats.frontier(queue.len());
+    }
+
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    /// Steps along a number line, where going down is twice as expensive as going up.
+    fn line(n: &i32) -> [(i32, usize); 2] {
+        [(n + 1, 1), (n - 1, 2)]
+    }
+
+    #[test]
+    fn bfs() {
+        let path = super::bfs(0, |&n| [n * 2, n + 1], |&n| n == 10).unwrap();
+
+        assert_eq!(path.cost, 5);
+        assert_eq!(path.states, [0, 1, 2, 4, 5, 10]);
+    }
+
+    #[test]
+    fn dijkstra() {
+        let path = super::dijkstra(0, line, |&n| n == -2).unwrap();
+
+        assert_eq!(path.cost, 4);
+        assert_eq!(path.states, [0, -1, -2]);
+    }
+
+    #[test]
+    fn astar() {
+        let path = super::astar(0, line, |&n| n == 5, |&n| 5i32.abs_diff(n) as usize).unwrap();
+
+        assert_eq!(path.cost, 5);
+        assert_eq!(path.states, [0, 1, 2, 3, 4, 5]);
+        assert!(path.stats.visited < 10);
+        assert_eq!(path.stats.expanded, 5);
+    }
+
+    #[test]
+    fn astar_until() {
+        let mut costs = Vec::new();
+
+        let stats = super::astar_until(
+            0,
+            line,
+            |&n| n == 5,
+            |&n| 5i32.abs_diff(n) as usize,
+            |_, cost| {
+                costs.push(cost);
+                cost == 3
+            },
+        )
+        .unwrap_err();
+
+        assert_eq!(costs, [0, 1, 2, 3]);
+        assert_eq!(stats.expanded, 3);
+    }
+
+    #[test]
+    fn unreachable() {
+        assert_eq!(super::bfs(0u8, |&n| n.checked_add(1), |_| false), None);
+    }
+}
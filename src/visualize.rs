@@ -0,0 +1,313 @@
+//! Frames of the step by step simulations, written to a directory as text or images.
+//!
+//! A simulation hands every step to a [`Recorder`], which decides by the step number alone whether
+//! it becomes a frame. A run therefore yields the same frames however fast it goes, and the frames
+//! can be turned into an animation at any frame rate afterwards.
+
+use std::{
+    fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+use crate::util::grid::Grid;
+
+/// How a single tile of a frame looks as text and in an image.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Tile {
+    pub symbol: char,
+    pub colour: [u8; 3],
+}
+
+impl Tile {
+    pub const fn new(symbol: char, colour: [u8; 3]) -> Self {
+        Tile { symbol, colour }
+    }
+
+    /// Brightness of the colour, for greyscale images.
+    fn grey(&self) -> u8 {
+        let [r, g, b] = self.colour.map(u32::from);
+
+        ((299 * r + 587 * g + 114 * b) / 1000) as u8
+    }
+}
+
+/// The state of a simulation in between two steps.
+pub trait Draw {
+    fn draw(&self) -> Grid<Tile>;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// One character per tile, in `.txt` files.
+    Ascii,
+    /// Colour images in binary `.ppm` files.
+    Ppm,
+    /// Greyscale images in binary `.pgm` files.
+    Pgm,
+}
+
+impl Format {
+    fn extension(self) -> &'static str {
+        match self {
+            Format::Ascii => "txt",
+            Format::Ppm => "ppm",
+            Format::Pgm => "pgm",
+        }
+    }
+}
+
+/// Writes the sampled steps of a simulation as numbered frames, `00000.txt`, `00001.txt` and so on.
+///
+/// Writing stops at the first error, which is reported by [`finish`](Self::finish) so that the
+/// simulations don't have to deal with it on every step.
+pub struct Recorder {
+    dir: PathBuf,
+    format: Format,
+    every: usize,
+    scale: usize,
+    frames: usize,
+    last_step: Option<usize>,
+    error: Option<io::Error>,
+}
+
+impl Recorder {
+    /// Records every step into `dir`, which is created if it doesn't exist. The frames of an
+    /// earlier recording in the same `format` are removed first, so none of them end up in this
+    /// one. Any other files are left alone.
+    pub fn new(dir: impl Into<PathBuf>, format: Format) -> io::Result<Self> {
+        let dir = dir.into();
+
+        fs::create_dir_all(&dir)?;
+
+        for entry in fs::read_dir(&dir)? {
+            let path = entry?.path();
+
+            if is_frame(&path, format) {
+                fs::remove_file(path)?;
+            }
+        }
+
+        Ok(Recorder {
+            dir,
+            format,
+            every: 1,
+            scale: 1,
+            frames: 0,
+            last_step: None,
+            error: None,
+        })
+    }
+
+    /// Only records steps that are a multiple of `steps`, along with the last one.
+    pub fn every(mut self, steps: usize) -> Self {
+        self.every = steps.max(1);
+        self
+    }
+
+    /// Draws every tile of an image as a square of `pixels` × `pixels`.
+    pub fn scale(mut self, pixels: usize) -> Self {
+        self.scale = pixels.max(1);
+        self
+    }
+
+    /// Records `state` if `step` is sampled.
+    pub fn record(&mut self, step: usize, state: &dyn Draw) {
+        if step.is_multiple_of(self.every) {
+            self.write(step, state);
+        }
+    }
+
+    /// Records the final `state` of a simulation, unless that step was already sampled.
+    pub fn record_last(&mut self, step: usize, state: &dyn Draw) {
+        if self.last_step != Some(step) {
+            self.write(step, state);
+        }
+    }
+
+    /// Returns the number of frames written, or the error that stopped the recording.
+    pub fn finish(self) -> io::Result<usize> {
+        match self.error {
+            Some(err) => Err(err),
+            None => Ok(self.frames),
+        }
+    }
+
+    fn write(&mut self, step: usize, state: &dyn Draw) {
+        if self.error.is_some() {
+            return;
+        }
+
+        let path = self
+            .dir
+            .join(format!("{:05}.{}", self.frames, self.format.extension()));
+
+        let result = fs::File::create(path).and_then(|file| {
+            let mut file = io::BufWriter::new(file);
+            self.encode(&state.draw(), &mut file)?;
+            file.flush()
+        });
+
+        match result {
+            Ok(()) => {
+                self.frames += 1;
+                self.last_step = Some(step);
+            }
+            Err(err) => self.error = Some(err),
+        }
+    }
+
+    fn encode(&self, frame: &Grid<Tile>, out: &mut impl Write) -> io::Result<()> {
+        let (width, height) = (frame.width() * self.scale, frame.height() * self.scale);
+
+        match self.format {
+            Format::Ascii => write!(out, "{}", frame.render(|tile| tile.symbol)),
+            Format::Ppm => {
+                write!(out, "P6\n{width} {height}\n255\n")?;
+                self.pixels(frame, out, |tile| tile.colour.to_vec())
+            }
+            Format::Pgm => {
+                write!(out, "P5\n{width} {height}\n255\n")?;
+                self.pixels(frame, out, |tile| vec![tile.grey()])
+            }
+        }
+    }
+
+    fn pixels(
+        &self,
+        frame: &Grid<Tile>,
+        out: &mut impl Write,
+        pixel: impl Fn(&Tile) -> Vec<u8>,
+    ) -> io::Result<()> {
+        for row in frame.rows() {
+            let line = row
+                .iter()
+                .flat_map(|tile| pixel(tile).repeat(self.scale))
+                .collect::<Vec<_>>();
+
+            for _ in 0..self.scale {
+                out.write_all(&line)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Whether `path` is named like the frames of a recording in `format`.
+fn is_frame(path: &Path, format: Format) -> bool {
+    let numbered = path
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .is_some_and(|stem| stem.len() >= 5 && stem.bytes().all(|b| b.is_ascii_digit()));
+    let extension = path.extension().and_then(|extension| extension.to_str());
+
+    numbered && extension == Some(format.extension())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, path::Path};
+
+    use super::{Draw, Format, Recorder, Tile};
+    use crate::util::grid::Grid;
+
+    struct Counter(usize);
+
+    impl Draw for Counter {
+        fn draw(&self) -> Grid<Tile> {
+            let mut grid = Grid::new(2, 1, Tile::new('.', [0, 0, 0]));
+            grid[(self.0 % 2, 0)] = Tile::new('#', [255, 255, 255]);
+            grid
+        }
+    }
+
+    fn record(format: Format, every: usize, scale: usize) -> (Vec<Vec<u8>>, usize) {
+        let dir = std::env::temp_dir().join(format!(
+            "advent-of-code-visualize-{}-{format:?}-{every}-{scale}",
+            std::process::id()
+        ));
+
+        record_into(&dir, format, every, scale)
+    }
+
+    fn record_into(
+        dir: &Path,
+        format: Format,
+        every: usize,
+        scale: usize,
+    ) -> (Vec<Vec<u8>>, usize) {
+        let mut recorder = Recorder::new(dir, format)
+            .unwrap()
+            .every(every)
+            .scale(scale);
+
+        for step in 0..=6 {
+            recorder.record(step, &Counter(step));
+        }
+
+        recorder.record_last(6, &Counter(6));
+        recorder.record_last(7, &Counter(7));
+
+        let frames = recorder.finish().unwrap();
+        let mut files = fs::read_dir(dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .collect::<Vec<_>>();
+
+        files.sort();
+
+        let contents = files.iter().map(|path| fs::read(path).unwrap()).collect();
+
+        fs::remove_dir_all(dir).unwrap();
+
+        (contents, frames)
+    }
+
+    #[test]
+    fn sampling() {
+        let (frames, count) = record(Format::Ascii, 3, 1);
+
+        assert_eq!(count, 4);
+        assert_eq!(frames, [b"#.\n", b".#\n", b"#.\n", b".#\n"]);
+    }
+
+    #[test]
+    fn images() {
+        let (frames, _) = record(Format::Pgm, 10, 2);
+
+        assert_eq!(frames[0], b"P5\n4 2\n255\n\xff\xff\0\0\xff\xff\0\0");
+
+        let (frames, _) = record(Format::Ppm, 10, 1);
+
+        assert_eq!(frames[2], b"P6\n2 1\n255\n\0\0\0\xff\xff\xff");
+    }
+
+    #[test]
+    fn rerecording() {
+        let dir = std::env::temp_dir().join(format!(
+            "advent-of-code-visualize-{}-rerecording",
+            std::process::id()
+        ));
+
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("00009.txt"), b"stale").unwrap();
+        fs::write(dir.join("00009.ppm"), b"other format").unwrap();
+        fs::write(dir.join("notes.txt"), b"kept").unwrap();
+
+        let (frames, count) = record_into(&dir, Format::Ascii, 3, 1);
+
+        assert_eq!(count, 4);
+        assert_eq!(
+            frames,
+            [
+                &b"#.\n"[..],
+                b".#\n",
+                b"#.\n",
+                b".#\n",
+                b"other format",
+                b"kept"
+            ]
+        );
+    }
+}
@@ -0,0 +1,55 @@
+#include <stdio.h>
+#include <string.h>
+
+#include "advent_of_code.h"
+
+static int failures = 0;
+
+static void check(uint8_t day, uint8_t part, const char *input, size_t len,
+                  AocStatus expected_status, const char *expected) {
+  char *answer = NULL;
+  AocStatus status = aoc_solve(day, part, (const uint8_t *)input, len, &answer);
+
+  if (status != expected_status || answer == NULL ||
+      (expected != NULL && strcmp(answer, expected) != 0)) {
+    fprintf(stderr, "Day %d part %d: got status %d and \"%s\"\n", day, part,
+            status, answer != NULL ? answer : "(null)");
+    failures++;
+  }
+
+  aoc_free_string(answer);
+}
+
+int main(void) {
+  const char *calories =
+      "1000\n2000\n3000\n\n4000\n\n5000\n6000\n\n7000\n8000\n9000\n\n10000";
+  const char *signal = "mjqjpqmgbljsphdztnvjfqwrcgsmlb";
+
+  check(1, 1, calories, strlen(calories), AOC_STATUS_OK, "24000");
+  check(1, 2, calories, strlen(calories), AOC_STATUS_OK, "45000");
+  check(6, 2, signal, strlen(signal), AOC_STATUS_OK, "19");
+
+  /* Only `len` bytes are read, so the buffer doesn't need a NUL at the end. */
+  check(6, 1, "bvwbjplbgvbhsrlpgdmjqwftvnczXXXX", 28, AOC_STATUS_OK, "5");
+
+  check(25, 2, "", 0, AOC_STATUS_UNKNOWN_PUZZLE, "No solution for day 25 part 2");
+  check(2, 1, "A Y\nB Q", 7, AOC_STATUS_INVALID_INPUT, NULL);
+  check(2, 1, "A \xff", 3, AOC_STATUS_INVALID_UTF8, NULL);
+
+  char *answer = NULL;
+
+  if (aoc_solve(1, 1, NULL, 1, &answer) != AOC_STATUS_INVALID_ARGUMENT ||
+      answer != NULL) {
+    fprintf(stderr, "A null input with a length was accepted\n");
+    failures++;
+  }
+
+  if (aoc_solve(1, 1, NULL, 0, NULL) != AOC_STATUS_INVALID_ARGUMENT) {
+    fprintf(stderr, "A null answer was accepted\n");
+    failures++;
+  }
+
+  aoc_free_string(NULL);
+
+  return failures == 0 ? 0 : 1;
+}
@@ -0,0 +1,92 @@
+//! Solves every example in `input/2022/examples` and compares the answers to the ones stored for it
+//! in the `answers.toml` next to it.
+//!
+//! `build.rs` generates one test per `dayN-*.txt` file, so a new example only needs its file and
+//! its answers.
+
+use std::{collections::HashMap, fs, path::PathBuf};
+
+use advent_of_code::{parse, solve_parsed, Params};
+use serde::Deserialize;
+
+include!(concat!(env!("OUT_DIR"), "/examples.rs"));
+
+/// What an example is solved with and should be solved to, as a table of `answers.toml`.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Expected {
+    part1: Option<toml::Value>,
+    part2: Option<toml::Value>,
+    /// Tunables for the example, by their names in [`Params::set`].
+    #[serde(default)]
+    params: HashMap<String, toml::Value>,
+}
+
+fn examples() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("input/2022/examples")
+}
+
+fn read_answers() -> HashMap<String, Expected> {
+    let answers = fs::read_to_string(examples().join("answers.toml")).unwrap();
+
+    toml::from_str(&answers).expect("Malformed answers.toml")
+}
+
+/// Strings are compared without their quotes, like the answers of `--verify`.
+fn text(value: &toml::Value) -> String {
+    match value {
+        toml::Value::String(value) => value.clone(),
+        value => value.to_string(),
+    }
+}
+
+fn check(name: &str) {
+    let day = name
+        .strip_prefix("day")
+        .and_then(|rest| rest.split('-').next())
+        .and_then(|day| day.parse().ok())
+        .unwrap_or_else(|| panic!("{name} is not named like dayN-description"));
+
+    let input = fs::read_to_string(examples().join(format!("{name}.txt"))).unwrap();
+    let answers = read_answers();
+    let expected = answers
+        .get(name)
+        .unwrap_or_else(|| panic!("{name} has no answers in answers.toml"));
+
+    assert!(
+        expected.part1.is_some() || expected.part2.is_some(),
+        "{name} has no answer for either part"
+    );
+
+    let mut params = Params::default();
+
+    for (param, value) in &expected.params {
+        params.set(param, &text(value)).unwrap();
+    }
+
+    let parsed = parse(day, &input).unwrap_or_else(|err| panic!("{err}"));
+
+    assert_eq!(
+        parsed.to_json()["day"],
+        day,
+        "{name} dumps its parsed model"
+    );
+
+    for (part, answer) in [(1, &expected.part1), (2, &expected.part2)] {
+        if let Some(answer) = answer {
+            let result = solve_parsed(&parsed, part, &params).unwrap_or_else(|err| panic!("{err}"));
+
+            assert_eq!(result.to_string(), text(answer), "{name} part {part}");
+        }
+    }
+}
+
+#[test]
+fn every_answer_has_an_example() {
+    for name in read_answers().keys() {
+        assert!(
+            examples().join(format!("{name}.txt")).is_file(),
+            "answers.toml has answers for {name}, which is not an example"
+        );
+    }
+}
@@ -0,0 +1,54 @@
+//! Compiles the C program in `tests/c` against the shared library and its header, and runs it.
+//! Also checks that the header checked in at `include` matches the one the build generated.
+
+#![cfg(unix)]
+
+use std::{env, path::Path, process::Command};
+
+#[test]
+fn c_interface() {
+    let manifest = Path::new(env!("CARGO_MANIFEST_DIR"));
+    // Integration tests are built into the same directory as the shared library.
+    let lib_dir = env::current_exe().unwrap().parent().unwrap().to_path_buf();
+    let exe = lib_dir.join(format!("c-solve-{}", std::process::id()));
+
+    let compiler = cc::Build::new()
+        .target(env!("BUILD_TARGET"))
+        .host(env!("BUILD_TARGET"))
+        .opt_level(0)
+        .cargo_metadata(false)
+        .cargo_warnings(false)
+        .get_compiler();
+
+    let status = compiler
+        .to_command()
+        .arg(manifest.join("tests/c/solve.c"))
+        .arg("-I")
+        .arg(Path::new(env!("OUT_DIR")).join("include"))
+        .arg("-L")
+        .arg(&lib_dir)
+        .arg("-ladvent_of_code")
+        .arg(format!("-Wl,-rpath,{}", lib_dir.display()))
+        .arg("-o")
+        .arg(&exe)
+        .status()
+        .expect("Could not run the C compiler");
+
+    assert!(status.success(), "Could not compile tests/c/solve.c");
+
+    let status = Command::new(&exe).status().unwrap();
+
+    std::fs::remove_file(&exe).unwrap();
+
+    assert!(status.success(), "The C test failed");
+}
+
+#[test]
+fn header_is_up_to_date() {
+    let read = |dir: &Path| std::fs::read_to_string(dir.join("include/advent_of_code.h")).unwrap();
+
+    assert!(
+        read(Path::new(env!("CARGO_MANIFEST_DIR"))) == read(Path::new(env!("OUT_DIR"))),
+        "include/advent_of_code.h is out of date, run `AOC_UPDATE_HEADER=1 cargo build`"
+    );
+}